@@ -5,19 +5,35 @@
 
 use std::path::PathBuf;
 
-use tauri::State;
+use tauri::{AppHandle, Emitter, State};
 
 use crate::error::AppError;
 use crate::models::*;
+use crate::services::cancel::CancelToken;
 use crate::services::MboxService;
 use crate::state::AppState;
 
+/// Event carrying `ExtractionProgress` while attachments are extracted
+const EXTRACTION_PROGRESS_EVENT: &str = "attachment-extraction-progress";
+
 /// Open an MBOX file and build/load its index
+///
+/// Rule labels and smart folders are evaluated after the lock is released,
+/// since body conditions and queries may decode every message.
 #[tauri::command]
 pub async fn open_mbox(path: String, state: State<'_, AppState>) -> Result<MboxStats, AppError> {
     let path_buf = PathBuf::from(&path);
-    let mut service = state.service.lock().unwrap();
-    service.open(&path_buf)
+    let snapshot = {
+        let mut service = state.service.lock().unwrap();
+        service.open(&path_buf)?;
+        MboxService::snapshot_for_search(&service)
+    };
+    rerun_rules(&state, snapshot).await?;
+    let snapshot = MboxService::snapshot_for_search(&state.service.lock().unwrap());
+    rerun_saved_searches(&state, snapshot).await?;
+
+    let service = state.service.lock().unwrap();
+    Ok(service.stats())
 }
 
 /// Get a paginated list of emails
@@ -40,16 +56,20 @@ pub fn get_email_count(state: State<'_, AppState>) -> usize {
 
 /// Get a single email's full body
 #[tauri::command]
-pub fn get_email_body(index: usize, state: State<'_, AppState>) -> Result<EmailBody, AppError> {
+pub fn get_email_body(
+    index: usize,
+    load_remote: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<EmailBody, AppError> {
     let mut service = state.service.lock().unwrap();
-    service.get_email_body(index)
+    service.get_email_body(index, load_remote.unwrap_or(false))
 }
 
-/// Search emails using mboxshell query syntax
+/// Search emails using mboxshell query syntax, a regex or a fuzzy pattern
 /// Supports both metadata search (fast) and body/fulltext search (slower)
 #[tauri::command]
 pub async fn search_emails(
-    query: String,
+    search: PatternSearch,
     limit: Option<usize>,
     state: State<'_, AppState>,
 ) -> Result<SearchResults, AppError> {
@@ -60,32 +80,521 @@ pub async fn search_emails(
     };
 
     // Run search in a blocking task to not block the main thread
-    let result = tokio::task::spawn_blocking(move || snapshot.search(&query, limit))
+    let result = tokio::task::spawn_blocking(move || snapshot.search_pattern(&search, limit))
         .await
         .map_err(|e| AppError::MboxShell(format!("Search task failed: {e}")))?;
 
     result
 }
 
-/// Get emails filtered by label
+/// Search emails and return facet counts, optionally narrowed by facet selections
+#[tauri::command]
+pub async fn search_emails_faceted(
+    query: String,
+    filters: Option<Vec<FacetFilter>>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<FacetedSearchResults, AppError> {
+    let snapshot = {
+        let service = state.service.lock().unwrap();
+        MboxService::snapshot_for_search(&service)
+    };
+
+    let filters = filters.unwrap_or_default();
+    tokio::task::spawn_blocking(move || snapshot.search_faceted(&query, &filters, limit))
+        .await
+        .map_err(|e| AppError::MboxShell(format!("Search task failed: {e}")))?
+}
+
+/// Get emails filtered by label (Gmail label by default, or a smart folder)
 #[tauri::command]
 pub fn get_emails_by_label(
     label: String,
+    kind: Option<LabelKind>,
     state: State<'_, AppState>,
 ) -> Result<Vec<EmailEntry>, AppError> {
     let service = state.service.lock().unwrap();
-    service.get_emails_by_label(&label)
+    service.get_emails_by_label_kind(&label, kind.unwrap_or_default())
+}
+
+/// Detect the language of every email and return stats with per-language counts
+#[tauri::command]
+pub async fn detect_languages(state: State<'_, AppState>) -> Result<MboxStats, AppError> {
+    // The snapshot shares the language cache, so later listings include it
+    let snapshot = {
+        let service = state.service.lock().unwrap();
+        MboxService::snapshot_for_search(&service)
+    };
+
+    tokio::task::spawn_blocking(move || snapshot.detect_languages())
+        .await
+        .map_err(|e| AppError::MboxShell(format!("Language detection failed: {e}")))??;
+
+    let service = state.service.lock().unwrap();
+    Ok(service.stats())
+}
+
+/// Get emails in a language (ISO code or English name)
+#[tauri::command]
+pub async fn get_emails_by_language(
+    language: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<EmailEntry>, AppError> {
+    let snapshot = {
+        let service = state.service.lock().unwrap();
+        MboxService::snapshot_for_search(&service)
+    };
+
+    tokio::task::spawn_blocking(move || snapshot.get_emails_by_language(&language))
+        .await
+        .map_err(|e| AppError::MboxShell(format!("Language detection failed: {e}")))?
+}
+
+/// Extract and index attachment text so `attachment:` searches are fast
+#[tauri::command]
+pub async fn index_attachment_text(state: State<'_, AppState>) -> Result<usize, AppError> {
+    // The snapshot shares the index cell, so the result is kept by the service
+    let snapshot = {
+        let service = state.service.lock().unwrap();
+        MboxService::snapshot_for_search(&service)
+    };
+
+    tokio::task::spawn_blocking(move || snapshot.index_attachment_text())
+        .await
+        .map_err(|e| AppError::MboxShell(format!("Indexing task failed: {e}")))?
+}
+
+/// Count message, thread and unique hits for each search term
+#[tauri::command]
+pub async fn get_hit_report(
+    terms: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<HitReport, AppError> {
+    let snapshot = {
+        let service = state.service.lock().unwrap();
+        MboxService::snapshot_for_search(&service)
+    };
+
+    tokio::task::spawn_blocking(move || snapshot.hit_report(&terms))
+        .await
+        .map_err(|e| AppError::MboxShell(format!("Hit report task failed: {e}")))?
+}
+
+/// Run a hit report and export it as CSV
+#[tauri::command]
+pub async fn export_hit_report(
+    terms: Vec<String>,
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<HitReport, AppError> {
+    let snapshot = {
+        let service = state.service.lock().unwrap();
+        MboxService::snapshot_for_search(&service)
+    };
+
+    let path = PathBuf::from(&path);
+    tokio::task::spawn_blocking(move || snapshot.export_hit_report(&terms, &path))
+        .await
+        .map_err(|e| AppError::MboxShell(format!("Hit report task failed: {e}")))?
+}
+
+/// Find messages with similar body content outside the message's thread
+#[tauri::command]
+pub async fn get_similar_emails(
+    index: usize,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<SimilarMessage>, AppError> {
+    let snapshot = {
+        let service = state.service.lock().unwrap();
+        MboxService::snapshot_for_search(&service)
+    };
+
+    tokio::task::spawn_blocking(move || snapshot.similar_messages(index, limit.unwrap_or(10)))
+        .await
+        .map_err(|e| AppError::MboxShell(format!("Similarity task failed: {e}")))?
+}
+
+/// Summarize an email's own text (quotes and signature stripped)
+#[tauri::command]
+pub async fn summarize_email(
+    index: usize,
+    sentences: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Summary, AppError> {
+    let snapshot = {
+        let service = state.service.lock().unwrap();
+        MboxService::snapshot_for_search(&service)
+    };
+
+    tokio::task::spawn_blocking(move || snapshot.summarize_email(index, sentences.unwrap_or(3)))
+        .await
+        .map_err(|e| AppError::MboxShell(format!("Summary task failed: {e}")))?
+}
+
+/// Summarize the whole thread an email belongs to
+#[tauri::command]
+pub async fn summarize_thread(
+    index: usize,
+    sentences: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Summary, AppError> {
+    let snapshot = {
+        let service = state.service.lock().unwrap();
+        MboxService::snapshot_for_search(&service)
+    };
+
+    tokio::task::spawn_blocking(move || snapshot.summarize_thread(index, sentences.unwrap_or(5)))
+        .await
+        .map_err(|e| AppError::MboxShell(format!("Summary task failed: {e}")))?
+}
+
+/// Detect exact and near-duplicate messages
+#[tauri::command]
+pub async fn find_duplicates(state: State<'_, AppState>) -> Result<DuplicateReport, AppError> {
+    let snapshot = {
+        let service = state.service.lock().unwrap();
+        MboxService::snapshot_for_search(&service)
+    };
+
+    tokio::task::spawn_blocking(move || snapshot.find_duplicates())
+        .await
+        .map_err(|e| AppError::MboxShell(format!("Duplicate detection failed: {e}")))?
+}
+
+/// Cluster the archive into topics, exposed as `topic` virtual labels
+///
+/// Starting a new run cancels any clustering still in progress.
+#[tauri::command]
+pub async fn cluster_topics(
+    clusters: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<TopicCluster>, AppError> {
+    let cancel = CancelToken::default();
+    std::mem::replace(&mut *state.clustering.lock().unwrap(), cancel.clone()).cancel();
+    let snapshot = {
+        let service = state.service.lock().unwrap();
+        MboxService::snapshot_for_search(&service)
+    };
+
+    let model = tokio::task::spawn_blocking(move || snapshot.cluster_topics(clusters, &cancel))
+        .await
+        .map_err(|e| AppError::MboxShell(format!("Topic clustering failed: {e}")))??;
+
+    let mut service = state.service.lock().unwrap();
+    service.set_topic_labels(model)
+}
+
+/// Cancel a running topic clustering
+#[tauri::command]
+pub fn cancel_clustering(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.clustering.lock().unwrap().cancel();
+    Ok(())
+}
+
+/// List the mailing lists in the archive with counts and date span
+#[tauri::command]
+pub async fn get_mailing_lists(state: State<'_, AppState>) -> Result<Vec<MailingList>, AppError> {
+    let snapshot = {
+        let service = state.service.lock().unwrap();
+        MboxService::snapshot_for_search(&service)
+    };
+
+    tokio::task::spawn_blocking(move || snapshot.get_mailing_lists())
+        .await
+        .map_err(|e| AppError::MboxShell(format!("Mailing list task failed: {e}")))?
+}
+
+/// Get the emails of one mailing list
+#[tauri::command]
+pub async fn get_emails_by_list(
+    list_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<EmailEntry>, AppError> {
+    let snapshot = {
+        let service = state.service.lock().unwrap();
+        MboxService::snapshot_for_search(&service)
+    };
+
+    tokio::task::spawn_blocking(move || snapshot.get_emails_by_list(&list_id))
+        .await
+        .map_err(|e| AppError::MboxShell(format!("Mailing list task failed: {e}")))?
+}
+
+/// Categorize emails (Primary, Promotions, Social, Updates, Forums) as virtual labels
+#[tauri::command]
+pub async fn categorize_emails(state: State<'_, AppState>) -> Result<Vec<LabelCount>, AppError> {
+    // Read headers off the main lock; the snapshot shares the header cache
+    let snapshot = {
+        let service = state.service.lock().unwrap();
+        MboxService::snapshot_for_search(&service)
+    };
+    tokio::task::spawn_blocking(move || snapshot.classify_categories())
+        .await
+        .map_err(|e| AppError::MboxShell(format!("Categorization failed: {e}")))??;
+
+    let mut service = state.service.lock().unwrap();
+    service.set_category_labels()
+}
+
+/// Hide duplicate copies from listings, counts and exports
+#[tauri::command]
+pub async fn set_duplicate_collapse(
+    mode: DuplicateCollapse,
+    state: State<'_, AppState>,
+) -> Result<MboxStats, AppError> {
+    if mode != DuplicateCollapse::Off {
+        // Fingerprint off the main lock; the snapshot shares the service's cache
+        let snapshot = {
+            let service = state.service.lock().unwrap();
+            MboxService::snapshot_for_search(&service)
+        };
+        tokio::task::spawn_blocking(move || snapshot.find_duplicates())
+            .await
+            .map_err(|e| AppError::MboxShell(format!("Duplicate detection failed: {e}")))??;
+    }
+
+    let (stats, snapshot) = {
+        let mut service = state.service.lock().unwrap();
+        let stats = service.set_duplicate_collapse(mode)?;
+        (stats, MboxService::snapshot_for_search(&service))
+    };
+    rerun_saved_searches(&state, snapshot).await?;
+    Ok(stats)
+}
+
+/// List the other copies of an email, including collapsed ones
+#[tauri::command]
+pub async fn get_duplicate_copies(
+    index: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<DuplicateCopy>, AppError> {
+    let snapshot = {
+        let service = state.service.lock().unwrap();
+        MboxService::snapshot_for_search(&service)
+    };
+
+    tokio::task::spawn_blocking(move || snapshot.get_duplicate_copies(index))
+        .await
+        .map_err(|e| AppError::MboxShell(format!("Duplicate lookup failed: {e}")))?
+}
+
+/// Get the user's labelling rules
+#[tauri::command]
+pub fn get_rules(state: State<'_, AppState>) -> Vec<LabelRule> {
+    let service = state.service.lock().unwrap();
+    service.get_rules()
+}
+
+/// Save a labelling rule, re-run the rules and return the label's live count
+#[tauri::command]
+pub async fn save_rule(
+    rule: LabelRule,
+    state: State<'_, AppState>,
+) -> Result<LabelCount, AppError> {
+    let (rule, snapshot) = {
+        let mut service = state.service.lock().unwrap();
+        let rule = service.save_rule(rule)?;
+        (rule, MboxService::snapshot_for_search(&service))
+    };
+    rerun_rules(&state, snapshot).await?;
+
+    let service = state.service.lock().unwrap();
+    Ok(service.rule_label_count(&rule.label))
+}
+
+/// Delete a labelling rule by name
+#[tauri::command]
+pub async fn delete_rule(name: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    let snapshot = {
+        let mut service = state.service.lock().unwrap();
+        service.delete_rule(&name)?;
+        MboxService::snapshot_for_search(&service)
+    };
+    rerun_rules(&state, snapshot).await
+}
+
+/// Evaluate the rules on a snapshot, since body conditions may decode every
+/// message, then swap the labels into the service.
+async fn rerun_rules(state: &State<'_, AppState>, snapshot: MboxService) -> Result<(), AppError> {
+    let labels = tokio::task::spawn_blocking(move || snapshot.rule_labels())
+        .await
+        .map_err(|e| AppError::MboxShell(format!("Rule evaluation failed: {e}")))?;
+    state.service.lock().unwrap().apply_rule_labels(labels);
+    Ok(())
+}
+
+/// Evaluate the saved searches on a snapshot, since queries may decode every
+/// message, then swap the smart folders into the service.
+async fn rerun_saved_searches(
+    state: &State<'_, AppState>,
+    snapshot: MboxService,
+) -> Result<(), AppError> {
+    let labels = tokio::task::spawn_blocking(move || snapshot.saved_search_labels())
+        .await
+        .map_err(|e| AppError::MboxShell(format!("Saved search evaluation failed: {e}")))?;
+    state
+        .service
+        .lock()
+        .unwrap()
+        .apply_saved_search_labels(labels);
+    Ok(())
 }
 
 /// Download an attachment from an email
+///
+/// `nested` addresses an attachment of a message embedded in the part, one
+/// attachment index per level.
 #[tauri::command]
 pub fn get_attachment(
     email_index: usize,
     attachment_index: usize,
+    nested: Option<Vec<usize>>,
     state: State<'_, AppState>,
 ) -> Result<Vec<u8>, AppError> {
-    let mut service = state.service.lock().unwrap();
-    service.get_attachment(email_index, attachment_index)
+    let source = state.service.lock().unwrap().message_source(email_index)?;
+    source.get_attachment(attachment_index, &nested.unwrap_or_default())
+}
+
+/// List the entries of an archive attachment (ZIP, TAR, TAR.GZ or 7z)
+///
+/// `path` opens archives nested inside it, one entry name per level.
+#[tauri::command]
+pub async fn list_archive(
+    email_index: usize,
+    attachment_index: usize,
+    path: Option<Vec<String>>,
+    state: State<'_, AppState>,
+) -> Result<ArchiveListing, AppError> {
+    let source = state.service.lock().unwrap().message_source(email_index)?;
+
+    // Decoding and unpacking the archive must not hold the service lock
+    tokio::task::spawn_blocking(move || {
+        source.list_archive(attachment_index, &path.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| AppError::MboxShell(format!("Listing the archive failed: {e}")))?
+}
+
+/// Save one file from an archive attachment without saving the archive
+///
+/// `destination` is a file path, or a directory the entry is saved into
+/// under its own name.
+#[tauri::command]
+pub async fn extract_archive_entry(
+    email_index: usize,
+    attachment_index: usize,
+    path: Vec<String>,
+    destination: String,
+    conflict: Option<ConflictPolicy>,
+    state: State<'_, AppState>,
+) -> Result<SavedAttachment, AppError> {
+    let source = state.service.lock().unwrap().message_source(email_index)?;
+
+    tokio::task::spawn_blocking(move || {
+        source.extract_archive_entry(
+            attachment_index,
+            &path,
+            &PathBuf::from(destination),
+            conflict.unwrap_or_default(),
+        )
+    })
+    .await
+    .map_err(|e| AppError::MboxShell(format!("Extracting the archive entry failed: {e}")))?
+}
+
+/// Save an attachment to a file path, or into a directory under its own name
+#[tauri::command]
+pub async fn save_attachment(
+    email_index: usize,
+    attachment_index: usize,
+    nested: Option<Vec<usize>>,
+    destination: String,
+    conflict: Option<ConflictPolicy>,
+    state: State<'_, AppState>,
+) -> Result<SavedAttachment, AppError> {
+    let source = state.service.lock().unwrap().message_source(email_index)?;
+
+    // Decoding and copying a large part must not hold the service lock
+    tokio::task::spawn_blocking(move || {
+        source.save_attachment(
+            attachment_index,
+            &nested.unwrap_or_default(),
+            &PathBuf::from(destination),
+            conflict.unwrap_or_default(),
+        )
+    })
+    .await
+    .map_err(|e| AppError::MboxShell(format!("Saving the attachment failed: {e}")))?
+}
+
+/// Extract the attachments of a search result into a templated folder tree
+///
+/// Emits `attachment-extraction-progress` after each message. Starting a new
+/// run cancels any extraction still in progress.
+#[tauri::command]
+pub async fn extract_attachments(
+    request: ExtractionRequest,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ExtractionSummary, AppError> {
+    let cancel = CancelToken::default();
+    std::mem::replace(&mut *state.extraction.lock().unwrap(), cancel.clone()).cancel();
+    let snapshot = {
+        let service = state.service.lock().unwrap();
+        MboxService::snapshot_for_search(&service)
+    };
+
+    tokio::task::spawn_blocking(move || {
+        snapshot.extract_attachments(&request, &cancel, &mut |progress| {
+            let _ = app.emit(EXTRACTION_PROGRESS_EVENT, progress);
+        })
+    })
+    .await
+    .map_err(|e| AppError::MboxShell(format!("Attachment extraction failed: {e}")))?
+}
+
+/// Cancel a running attachment extraction
+#[tauri::command]
+pub fn cancel_extraction(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.extraction.lock().unwrap().cancel();
+    Ok(())
+}
+
+/// List, filter and sort every attachment in the mailbox
+#[tauri::command]
+pub async fn get_attachment_catalog(
+    query: CatalogQuery,
+    state: State<'_, AppState>,
+) -> Result<AttachmentCatalog, AppError> {
+    // The snapshot shares the catalog cell, so hashing runs once per mailbox
+    let snapshot = {
+        let service = state.service.lock().unwrap();
+        MboxService::snapshot_for_search(&service)
+    };
+
+    tokio::task::spawn_blocking(move || snapshot.get_attachment_catalog(&query))
+        .await
+        .map_err(|e| AppError::MboxShell(format!("Attachment catalog task failed: {e}")))?
+}
+
+/// Find every other message carrying the same attachment content
+#[tauri::command]
+pub async fn find_attachment_copies(
+    email_index: usize,
+    attachment_index: usize,
+    state: State<'_, AppState>,
+) -> Result<AttachmentCopies, AppError> {
+    let snapshot = {
+        let service = state.service.lock().unwrap();
+        MboxService::snapshot_for_search(&service)
+    };
+
+    tokio::task::spawn_blocking(move || {
+        snapshot.find_attachment_copies(email_index, attachment_index)
+    })
+    .await
+    .map_err(|e| AppError::MboxShell(format!("Attachment copies task failed: {e}")))?
 }
 
 /// Close the currently open MBOX file
@@ -102,3 +611,37 @@ pub fn get_labels(state: State<'_, AppState>) -> Result<Vec<LabelCount>, AppErro
     let service = state.service.lock().unwrap();
     Ok(service.get_labels())
 }
+
+/// Get the saved searches (smart folders) of the open MBOX file
+#[tauri::command]
+pub fn get_saved_searches(state: State<'_, AppState>) -> Vec<SavedSearch> {
+    let service = state.service.lock().unwrap();
+    service.get_saved_searches()
+}
+
+/// Save a query as a named smart folder and return its live count
+#[tauri::command]
+pub async fn save_search(
+    name: String,
+    query: String,
+    state: State<'_, AppState>,
+) -> Result<LabelCount, AppError> {
+    let (count, snapshot) = {
+        let mut service = state.service.lock().unwrap();
+        let count = service.save_search(&name, &query)?;
+        (count, MboxService::snapshot_for_search(&service))
+    };
+    rerun_saved_searches(&state, snapshot).await?;
+    Ok(count)
+}
+
+/// Delete a saved search by name
+#[tauri::command]
+pub async fn delete_saved_search(name: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    let snapshot = {
+        let mut service = state.service.lock().unwrap();
+        service.delete_saved_search(&name)?;
+        MboxService::snapshot_for_search(&service)
+    };
+    rerun_saved_searches(&state, snapshot).await
+}
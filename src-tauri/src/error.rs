@@ -16,6 +16,9 @@ pub enum AppError {
 
     #[error("MBOX error: {0}")]
     MboxShell(String),
+
+    #[error("Operation cancelled")]
+    Cancelled,
 }
 
 impl Serialize for AppError {
@@ -46,6 +49,11 @@ mod tests {
         assert_eq!(err.to_string(), "MBOX error: index corrupt");
     }
 
+    #[test]
+    fn cancelled_displays_message() {
+        assert_eq!(AppError::Cancelled.to_string(), "Operation cancelled");
+    }
+
     #[test]
     fn io_error_converts_from_std_io() {
         let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "file missing");
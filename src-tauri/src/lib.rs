@@ -4,10 +4,12 @@ mod commands;
 mod error;
 mod menu;
 mod models;
+mod protocol;
 mod services;
 mod state;
 
 use state::AppState;
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -17,7 +19,15 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_store::Builder::default().build())
         .manage(AppState::default())
+        .register_asynchronous_uri_scheme_protocol(services::scheme::SCHEME, protocol::handle)
         .setup(|app| {
+            let data_dir = app.path().app_data_dir()?;
+            app.state::<AppState>()
+                .service
+                .lock()
+                .unwrap()
+                .set_data_dir(data_dir);
+
             let m = menu::create_menu(app)?;
             app.set_menu(m)?;
             Ok(())
@@ -31,10 +41,40 @@ pub fn run() {
             commands::get_email_count,
             commands::get_email_body,
             commands::search_emails,
+            commands::search_emails_faceted,
             commands::get_emails_by_label,
             commands::get_attachment,
+            commands::save_attachment,
+            commands::extract_attachments,
+            commands::cancel_extraction,
+            commands::get_attachment_catalog,
+            commands::find_attachment_copies,
+            commands::list_archive,
+            commands::extract_archive_entry,
+            commands::detect_languages,
+            commands::get_emails_by_language,
+            commands::index_attachment_text,
+            commands::get_hit_report,
+            commands::export_hit_report,
+            commands::get_similar_emails,
+            commands::summarize_email,
+            commands::summarize_thread,
+            commands::find_duplicates,
+            commands::set_duplicate_collapse,
+            commands::get_duplicate_copies,
+            commands::cluster_topics,
+            commands::categorize_emails,
+            commands::get_mailing_lists,
+            commands::get_emails_by_list,
+            commands::cancel_clustering,
             commands::close_mbox,
-            commands::get_labels
+            commands::get_labels,
+            commands::get_saved_searches,
+            commands::save_search,
+            commands::delete_saved_search,
+            commands::get_rules,
+            commands::save_rule,
+            commands::delete_rule
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,35 @@
+//! Archive attachment listing types for frontend IPC.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+    SevenZ,
+}
+
+/// One file or directory inside an archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    /// Path inside the archive, used to extract or open the entry
+    pub path: String,
+    /// Uncompressed size in bytes as recorded in the archive
+    pub size: u64,
+    pub compressed_size: Option<u64>,
+    /// Modification time (`YYYY-MM-DDTHH:MM:SS`, UTC with a `Z` when known)
+    pub modified: Option<String>,
+    pub is_dir: bool,
+    /// Whether the entry looks like an archive that can be listed in turn
+    pub is_archive: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveListing {
+    pub format: ArchiveFormat,
+    pub entries: Vec<ArchiveEntry>,
+    /// True when the archive has more entries than are listed
+    pub truncated: bool,
+}
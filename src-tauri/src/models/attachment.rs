@@ -0,0 +1,65 @@
+//! Attachment export types for frontend IPC.
+
+use serde::{Deserialize, Serialize};
+
+/// What to do when a file with the attachment's name already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Append " (1)", " (2)"... to the name
+    #[default]
+    Rename,
+    Overwrite,
+    /// Leave the existing file and report the attachment as skipped
+    Skip,
+}
+
+/// Outcome of saving one attachment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedAttachment {
+    /// Path written (or, when skipped, the existing file)
+    pub path: String,
+    /// Bytes written
+    pub size: u64,
+    pub skipped: bool,
+}
+
+/// A bulk extraction of the attachments of a search result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractionRequest {
+    /// Search query selecting the messages (empty for all visible messages)
+    #[serde(default)]
+    pub query: String,
+    /// Root directory; created if missing
+    pub destination: String,
+    /// Relative path template, e.g. `{year}/{sender_domain}/{date}_{filename}`
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Extensions (`pdf`) or MIME types (`image/*`); empty extracts everything
+    #[serde(default)]
+    pub file_types: Vec<String>,
+    #[serde(default)]
+    pub conflict: ConflictPolicy,
+}
+
+/// Progress of a running extraction, emitted after each message
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractionProgress {
+    pub processed_messages: usize,
+    pub total_messages: usize,
+    pub written: usize,
+    /// Attachments identical to one already written in this run
+    pub duplicates: usize,
+    /// Attachments left alone because the target existed (`skip` policy)
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+/// Result of a finished extraction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractionSummary {
+    pub progress: ExtractionProgress,
+    pub bytes_written: u64,
+    /// CSV mapping every extracted file back to its message
+    pub manifest_path: String,
+}
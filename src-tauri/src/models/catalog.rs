@@ -0,0 +1,73 @@
+//! Archive-wide attachment catalog types for frontend IPC.
+
+use serde::{Deserialize, Serialize};
+
+/// One attachment of the mailbox with its parent message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogAttachment {
+    pub email_index: usize,
+    pub attachment_index: usize,
+    pub filename: String,
+    pub content_type: String,
+    /// Decoded size in bytes
+    pub size: u64,
+    /// Hex SHA-256 of the decoded content; `None` when the part failed to decode
+    pub sha256: Option<String>,
+    pub date: String,
+    pub from_name: String,
+    pub from_address: String,
+    pub subject: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CatalogSort {
+    #[default]
+    Date,
+    Filename,
+    Size,
+    ContentType,
+    Sender,
+}
+
+/// Filters, order and page of a catalog listing
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CatalogQuery {
+    /// Case-insensitive substring of the filename
+    #[serde(default)]
+    pub filename: Option<String>,
+    /// Extensions (`xlsx`) or MIME types (`image/*`); empty matches all
+    #[serde(default)]
+    pub file_types: Vec<String>,
+    /// Case-insensitive substring of the sender name or address
+    #[serde(default)]
+    pub sender: Option<String>,
+    #[serde(default)]
+    pub min_size: Option<u64>,
+    #[serde(default)]
+    pub max_size: Option<u64>,
+    #[serde(default)]
+    pub sort: CatalogSort,
+    #[serde(default)]
+    pub descending: bool,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// An attachment and the other messages carrying identical content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentCopies {
+    pub source: CatalogAttachment,
+    /// Oldest first, so the list traces how the file circulated
+    pub copies: Vec<CatalogAttachment>,
+}
+
+/// A page of the catalog with totals over every matching attachment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentCatalog {
+    pub total: usize,
+    pub total_size: u64,
+    pub attachments: Vec<CatalogAttachment>,
+}
@@ -0,0 +1,35 @@
+//! Inbox category types for frontend IPC.
+
+use serde::{Deserialize, Serialize};
+
+/// Gmail-style inbox category, inferred from headers for non-Gmail archives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MailCategory {
+    Primary,
+    Promotions,
+    Social,
+    Updates,
+    Forums,
+}
+
+impl MailCategory {
+    pub const ALL: [MailCategory; 5] = [
+        MailCategory::Primary,
+        MailCategory::Promotions,
+        MailCategory::Social,
+        MailCategory::Updates,
+        MailCategory::Forums,
+    ];
+
+    /// Name of the category's virtual label
+    pub fn label(self) -> &'static str {
+        match self {
+            MailCategory::Primary => "Primary",
+            MailCategory::Promotions => "Promotions",
+            MailCategory::Social => "Social",
+            MailCategory::Updates => "Updates",
+            MailCategory::Forums => "Forums",
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! Duplicate detection types for frontend IPC.
+
+use serde::{Deserialize, Serialize};
+
+use super::email::EmailEntry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateKind {
+    /// Same Message-ID, or same normalized sender, subject, date, body and attachments
+    Exact,
+    /// Body differs only slightly (MinHash shingle similarity)
+    Near,
+}
+
+/// Which duplicates the session hides from listings, counts and exports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateCollapse {
+    #[default]
+    Off,
+    /// Hide exact copies, keeping each group's representative
+    Exact,
+    /// Hide exact copies and near duplicates
+    ExactAndNear,
+}
+
+/// A set of duplicate messages, identified by email index
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    pub kind: DuplicateKind,
+    /// Earliest copy in the MBOX file
+    pub representative: usize,
+    pub duplicates: Vec<usize>,
+}
+
+/// All duplicate groups in the open mailbox
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateReport {
+    pub groups: Vec<DuplicateGroup>,
+    /// Messages that are exact copies of a representative
+    pub exact_duplicates: usize,
+    /// Distinct messages that are near copies of a representative
+    pub near_duplicates: usize,
+}
+
+/// Another copy of a message, with where it lives in the MBOX file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateCopy {
+    pub email: EmailEntry,
+    pub kind: DuplicateKind,
+    pub is_representative: bool,
+    /// Whether the copy is currently hidden by the collapse mode
+    pub hidden: bool,
+}
@@ -17,6 +17,10 @@ pub struct EmailEntry {
     pub subject: String,
     pub has_attachments: bool,
     pub labels: Vec<String>,
+    /// Detected body language (ISO 639-1 code); `None` until detection has run
+    pub language: Option<String>,
+    /// Labels applied by user-defined rules
+    pub rule_labels: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +36,60 @@ pub struct EmailBody {
     pub html: Option<String>,
     pub raw_headers: String,
     pub attachments: Vec<AttachmentInfo>,
+    /// Plain-text rendition split into new content, quotes, forwards and signature
+    pub segments: Vec<BodySegment>,
+    /// Remote resources removed from `html` when it was sanitized
+    pub remote_content: RemoteContent,
+    /// `message/rfc822` attachments parsed into messages of their own
+    pub embedded: Vec<EmbeddedMessage>,
+}
+
+/// An email attached to another one, e.g. forwarded as attachment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddedMessage {
+    /// Index of the part in the parent's `attachments`
+    pub attachment_index: usize,
+    pub from: String,
+    pub to: Vec<String>,
+    pub subject: String,
+    /// `Date` header as written in the message
+    pub date: String,
+    pub body: EmailBody,
+}
+
+/// What sanitizing an HTML body did with remote resources
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteContent {
+    /// Whether remote images and stylesheets were allowed to load
+    pub allowed: bool,
+    /// Remote images and CSS `url()`s removed
+    pub blocked: usize,
+    /// Tracking pixels removed (always, even when remote content is allowed)
+    pub trackers: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SegmentKind {
+    /// Text written by the sender of this message
+    Content,
+    /// Quoted reply text (`>` lines or an "Original Message" block)
+    Quote,
+    /// A forwarded message
+    Forward,
+    Signature,
+}
+
+/// A contiguous part of a message body
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BodySegment {
+    pub kind: SegmentKind,
+    /// Text with quote markers removed
+    pub text: String,
+    /// Quote nesting level (0 for content and signatures)
+    pub depth: usize,
+    /// "On ... wrote:" line or banner that introduced a quote or forward
+    pub attribution: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +98,8 @@ pub struct AttachmentInfo {
     pub content_type: String,
     pub size: u64,
     pub part_index: usize,
+    /// `mbox` scheme URL the part can be streamed from
+    pub url: String,
 }
 
 impl From<&MailEntry> for EmailEntry {
@@ -70,6 +130,8 @@ impl From<&MailEntry> for EmailEntry {
             subject: entry.subject.clone(),
             has_attachments: entry.has_attachments,
             labels: entry.labels.clone(),
+            language: None,
+            rule_labels: Vec::new(),
         }
     }
 }
@@ -89,8 +151,12 @@ impl From<&MailBody> for EmailBody {
                     content_type: a.content_type.clone(),
                     size: a.size,
                     part_index: i,
+                    url: String::new(),
                 })
                 .collect(),
+            segments: Vec::new(),
+            remote_content: RemoteContent::default(),
+            embedded: Vec::new(),
         }
     }
 }
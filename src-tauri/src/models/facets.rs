@@ -0,0 +1,51 @@
+//! Faceted search types for frontend IPC.
+
+use serde::{Deserialize, Serialize};
+
+use super::email::EmailEntry;
+
+/// Dimension a search result can be refined by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FacetField {
+    Sender,
+    SenderDomain,
+    Label,
+    Year,
+    Month,
+    AttachmentType,
+    HasAttachment,
+}
+
+/// A facet selection applied on top of the query
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FacetFilter {
+    pub field: FacetField,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// Facet counts over the full (filtered) match set
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchFacets {
+    pub senders: Vec<FacetCount>,
+    pub sender_domains: Vec<FacetCount>,
+    pub labels: Vec<FacetCount>,
+    pub years: Vec<FacetCount>,
+    pub months: Vec<FacetCount>,
+    pub attachment_types: Vec<FacetCount>,
+    pub has_attachment: Vec<FacetCount>,
+}
+
+/// Search results with count and facets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FacetedSearchResults {
+    pub emails: Vec<EmailEntry>,
+    pub total_count: usize,
+    pub facets: SearchFacets,
+}
@@ -0,0 +1,23 @@
+//! Search-term hit report types for frontend IPC.
+
+use serde::{Deserialize, Serialize};
+
+/// Hits for a single search term
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TermHits {
+    pub term: String,
+    pub messages: usize,
+    pub threads: usize,
+    /// Messages hit by this term and no other
+    pub unique_messages: usize,
+}
+
+/// Per-term and deduplicated hit counts for a list of search terms
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HitReport {
+    pub terms: Vec<TermHits>,
+    /// Messages hit by at least one term
+    pub total_messages: usize,
+    pub total_threads: usize,
+    pub messages_searched: usize,
+}
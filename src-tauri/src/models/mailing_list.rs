@@ -0,0 +1,20 @@
+//! Mailing-list types for frontend IPC.
+
+use serde::{Deserialize, Serialize};
+
+/// A mailing list seen in the archive, from its messages' List-* headers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MailingList {
+    /// `List-Id` identifier, e.g. `users.rust-lang.org`
+    pub list_id: String,
+    /// Display name from `List-Id`, when the list sets one
+    pub name: Option<String>,
+    pub count: usize,
+    /// Dates of the oldest and newest message (RFC 3339)
+    pub first_date: String,
+    pub last_date: String,
+    /// Latest `List-Post`, `List-Unsubscribe` and `List-Archive` URLs
+    pub post: Option<String>,
+    pub unsubscribe: Option<String>,
+    pub archive: Option<String>,
+}
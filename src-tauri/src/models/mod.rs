@@ -1,7 +1,43 @@
 //! Data transfer objects for frontend IPC.
 
+pub mod archive;
+pub mod attachment;
+pub mod catalog;
+pub mod category;
+pub mod duplicates;
 pub mod email;
+pub mod facets;
+pub mod hit_report;
+pub mod mailing_list;
+pub mod pattern;
+pub mod rule;
+pub mod saved_search;
+pub mod similarity;
 pub mod stats;
+pub mod summary;
+pub mod topics;
 
-pub use email::{EmailBody, EmailEntry};
-pub use stats::{LabelCount, MboxStats, SearchResults};
+pub use archive::{ArchiveEntry, ArchiveFormat, ArchiveListing};
+pub use attachment::{
+    ConflictPolicy, ExtractionProgress, ExtractionRequest, ExtractionSummary, SavedAttachment,
+};
+pub use catalog::{
+    AttachmentCatalog, AttachmentCopies, CatalogAttachment, CatalogQuery, CatalogSort,
+};
+pub use category::MailCategory;
+pub use duplicates::{
+    DuplicateCollapse, DuplicateCopy, DuplicateGroup, DuplicateKind, DuplicateReport,
+};
+pub use email::{
+    AttachmentInfo, BodySegment, EmailBody, EmailEntry, EmbeddedMessage, RemoteContent, SegmentKind,
+};
+pub use facets::{FacetCount, FacetField, FacetFilter, FacetedSearchResults, SearchFacets};
+pub use hit_report::{HitReport, TermHits};
+pub use mailing_list::MailingList;
+pub use pattern::{MatchField, MatchedText, MessageMatches, PatternSearch, SearchMode};
+pub use rule::{LabelRule, RuleCondition, RuleField, RuleOperator};
+pub use saved_search::SavedSearch;
+pub use similarity::SimilarMessage;
+pub use stats::{LabelCount, LabelKind, LanguageCount, MboxStats, SearchResults};
+pub use summary::{Summary, SummarySentence};
+pub use topics::TopicCluster;
@@ -0,0 +1,67 @@
+//! Regex and fuzzy search types for frontend IPC.
+
+use serde::{Deserialize, Serialize};
+
+/// How the search text is interpreted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchMode {
+    /// mboxshell query syntax (`from:`, `subject:`, ...)
+    #[default]
+    Query,
+    /// Regular expression
+    Regex,
+    /// Typo-tolerant word matching by edit distance
+    Fuzzy,
+}
+
+/// Message field a regex or fuzzy pattern is applied to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchField {
+    Subject,
+    From,
+    To,
+    Body,
+    /// Extracted text of an attachment
+    Attachment,
+}
+
+/// Options for a regex or fuzzy search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatternSearch {
+    #[serde(default)]
+    pub mode: SearchMode,
+    pub pattern: String,
+    /// Fields to search; empty means subject, addresses and body
+    #[serde(default)]
+    pub fields: Vec<MatchField>,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Maximum edit distance per word in fuzzy mode; derived from word length if unset
+    #[serde(default)]
+    pub max_distance: Option<usize>,
+    /// Search only the sender's own body text, skipping quotes, forwards and signatures
+    #[serde(default)]
+    pub exclude_quoted: bool,
+}
+
+/// A matched substring within one field of a message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchedText {
+    pub field: MatchField,
+    /// Byte offsets into the field's (plain-text) value
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+    /// Which attachment matched, for `Attachment` matches
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachment_index: Option<usize>,
+}
+
+/// Matched substrings of a single message in a search result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageMatches {
+    pub index: usize,
+    pub matches: Vec<MatchedText>,
+}
@@ -0,0 +1,60 @@
+//! Labelling rule types for frontend IPC.
+
+use serde::{Deserialize, Serialize};
+
+/// Message attribute a rule condition looks at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleField {
+    /// Sender name and address
+    From,
+    /// Domain of the sender address
+    FromDomain,
+    /// To and Cc names and addresses
+    To,
+    Subject,
+    /// Gmail labels of the message
+    Label,
+    /// `"true"` or `"false"`
+    HasAttachments,
+    /// Decoded body text (slower: every body is read)
+    Body,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleOperator {
+    Contains,
+    Equals,
+    StartsWith,
+    EndsWith,
+    /// Regular expression
+    Matches,
+}
+
+/// One test of a rule; text comparisons ignore case
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleCondition {
+    pub field: RuleField,
+    pub operator: RuleOperator,
+    pub value: String,
+}
+
+/// A user-defined rule that applies a virtual label to matching messages
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LabelRule {
+    /// Unique rule name
+    pub name: String,
+    /// Label applied to matches; several rules may share one
+    pub label: String,
+    pub conditions: Vec<RuleCondition>,
+    /// Match when any condition holds instead of all of them
+    #[serde(default)]
+    pub match_any: bool,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
@@ -0,0 +1,10 @@
+//! Saved search (smart folder) types for frontend IPC.
+
+use serde::{Deserialize, Serialize};
+
+/// A named query persisted per mailbox and shown as a smart folder
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String,
+}
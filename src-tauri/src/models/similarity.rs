@@ -0,0 +1,13 @@
+//! Similar-message ("more like this") types for frontend IPC.
+
+use serde::{Deserialize, Serialize};
+
+use super::email::EmailEntry;
+
+/// A message related to another by body content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarMessage {
+    pub email: EmailEntry,
+    /// Cosine similarity of TF-IDF signatures, from 0 to 1
+    pub score: f32,
+}
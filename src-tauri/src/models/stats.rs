@@ -3,6 +3,7 @@
 use serde::{Deserialize, Serialize};
 
 use super::email::EmailEntry;
+use super::pattern::MessageMatches;
 
 /// Statistics about the opened MBOX file
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,12 +11,41 @@ pub struct MboxStats {
     pub total_messages: usize,
     pub total_with_attachments: usize,
     pub labels: Vec<LabelCount>,
+    /// Messages hidden by the duplicate collapse mode (not counted above)
+    pub hidden_duplicates: usize,
+    /// Messages per detected language, most common first; empty until detection has run
+    pub languages: Vec<LanguageCount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageCount {
+    /// ISO 639-1 code, or `und` for messages whose language could not be told
+    pub language: String,
+    pub count: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LabelCount {
     pub label: String,
     pub count: usize,
+    pub kind: LabelKind,
+}
+
+/// Where a label comes from: the mbox itself or a viewer-side virtual folder
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LabelKind {
+    /// Gmail label read from `X-Gmail-Labels`
+    #[default]
+    Gmail,
+    /// Named saved search shown as a smart folder
+    SavedSearch,
+    /// Cluster found by topic clustering
+    Topic,
+    /// Inbox category inferred from headers (Primary, Promotions...)
+    Category,
+    /// Label applied by a user-defined rule
+    Rule,
 }
 
 /// Progress update for indexing
@@ -31,4 +61,7 @@ pub struct IndexProgress {
 pub struct SearchResults {
     pub emails: Vec<EmailEntry>,
     pub total_count: usize,
+    /// Matched substrings per returned email (regex and fuzzy modes only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matches: Vec<MessageMatches>,
 }
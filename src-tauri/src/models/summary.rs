@@ -0,0 +1,22 @@
+//! Extractive summary types for frontend IPC.
+
+use serde::{Deserialize, Serialize};
+
+/// A sentence picked for a summary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummarySentence {
+    pub text: String,
+    /// Email the sentence was taken from
+    pub email_index: usize,
+    /// TextRank centrality, higher is more representative
+    pub score: f32,
+}
+
+/// Top sentences (in reading order) and keywords of a message or thread
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Summary {
+    pub sentences: Vec<SummarySentence>,
+    pub keywords: Vec<String>,
+    /// Emails the summary covers, oldest first
+    pub email_indices: Vec<usize>,
+}
@@ -0,0 +1,13 @@
+//! Topic clustering types for frontend IPC.
+
+use serde::{Deserialize, Serialize};
+
+/// An automatically discovered topic, exposed as a `topic` virtual label
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicCluster {
+    /// Virtual label name, e.g. "Topic 1: invoice, payment, acme"
+    pub label: String,
+    /// Highest-weighted terms of the cluster centroid
+    pub keywords: Vec<String>,
+    pub count: usize,
+}
@@ -0,0 +1,149 @@
+//! `mbox` URI scheme handler serving message parts to the webview.
+
+use tauri::http::{header, HeaderValue, Request, Response, StatusCode};
+use tauri::{Manager, Runtime, UriSchemeContext, UriSchemeResponder};
+
+use crate::error::AppError;
+use crate::services::byte_range::{self, RangeRequest};
+use crate::services::scheme::{self, SchemeRequest};
+use crate::state::AppState;
+
+pub fn handle<R: Runtime>(
+    ctx: UriSchemeContext<'_, R>,
+    request: Request<Vec<u8>>,
+    responder: UriSchemeResponder,
+) {
+    let app = ctx.app_handle().clone();
+    // Decoding a part can take a while; keep it off the webview's thread
+    tauri::async_runtime::spawn_blocking(move || {
+        let response = match scheme::parse_path(request.uri().path()) {
+            Some(SchemeRequest::InlinePart { index, content_id }) => {
+                inline_part(&app, &request, index, &content_id)
+            }
+            Some(SchemeRequest::Attachment {
+                email_index,
+                attachment_index,
+                nested,
+            }) => attachment(&app, &request, email_index, attachment_index, &nested),
+            None => Err(AppError::NotFound(request.uri().path().to_string())),
+        };
+        responder.respond(response.unwrap_or_else(|e| error_response(&e)));
+    });
+}
+
+fn inline_part<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    request: &Request<Vec<u8>>,
+    index: usize,
+    content_id: &str,
+) -> Result<Response<Vec<u8>>, AppError> {
+    let state = app.state::<AppState>();
+    let part = state
+        .service
+        .lock()
+        .unwrap()
+        .get_inline_part(index, content_id)?;
+
+    // Indices are only meaningful for the open mailbox, so responses are
+    // revalidated rather than cached by URL
+    let etag = part.etag();
+    let builder = Response::builder()
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::ETAG, &etag);
+    let unchanged = request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .is_some_and(|value| value.as_bytes() == etag.as_bytes());
+    if unchanged {
+        return Ok(builder
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Vec::new())
+            .unwrap());
+    }
+
+    Ok(builder
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type(&part.content_type))
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+        .header(header::CONTENT_SECURITY_POLICY, "sandbox")
+        .body(part.data)
+        .unwrap())
+}
+
+/// Serve an attachment from the spool, honouring single byte ranges.
+///
+/// Parts over `MAX_RANGE_BYTES` are only ever sent in ranges, so no request
+/// reads a whole large part into memory.
+///
+/// `?disposition=inline` asks for it to be displayed rather than downloaded.
+fn attachment<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    request: &Request<Vec<u8>>,
+    email_index: usize,
+    attachment_index: usize,
+    nested: &[usize],
+) -> Result<Response<Vec<u8>>, AppError> {
+    let source = app
+        .state::<AppState>()
+        .service
+        .lock()
+        .unwrap()
+        .message_source(email_index)?;
+    // Decoding a large part must not hold up every other command
+    let spooled = source.spool_attachment(attachment_index, nested)?;
+
+    let inline = request
+        .uri()
+        .query()
+        .is_some_and(|q| q.split('&').any(|p| p == "disposition=inline"));
+    let disposition = byte_range::content_disposition(&spooled.filename, inline);
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, content_type(&spooled.content_type))
+        .header(
+            header::CONTENT_DISPOSITION,
+            HeaderValue::from_str(&disposition).unwrap_or(HeaderValue::from_static("attachment")),
+        )
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+        .header(header::CONTENT_SECURITY_POLICY, "sandbox");
+
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok());
+    let response = match RangeRequest::parse(range, spooled.size) {
+        RangeRequest::Full => builder
+            .status(StatusCode::OK)
+            .header(header::CONTENT_LENGTH, spooled.size)
+            .body(std::fs::read(&spooled.path)?),
+        RangeRequest::Partial { start, end } => builder
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(
+                header::CONTENT_RANGE,
+                format!("bytes {start}-{end}/{}", spooled.size),
+            )
+            .header(header::CONTENT_LENGTH, end - start + 1)
+            .body(byte_range::read_range(&spooled.path, start, end)?),
+        RangeRequest::Unsatisfiable => builder
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", spooled.size))
+            .body(Vec::new()),
+    };
+    Ok(response.unwrap())
+}
+
+fn content_type(value: &str) -> HeaderValue {
+    HeaderValue::from_str(value).unwrap_or(HeaderValue::from_static("application/octet-stream"))
+}
+
+fn error_response(error: &AppError) -> Response<Vec<u8>> {
+    let code = match error {
+        AppError::NotFound(_) | AppError::Validation(_) => StatusCode::NOT_FOUND,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    Response::builder()
+        .status(code)
+        .body(error.to_string().into_bytes())
+        .unwrap()
+}
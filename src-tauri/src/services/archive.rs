@@ -0,0 +1,399 @@
+//! Listing and extracting entries of archive attachments (ZIP, TAR, TAR.GZ, 7z).
+//!
+//! Archives are read from memory. Entry sizes recorded in an archive can lie,
+//! so every read is capped whatever the headers claim: a zip bomb fails with
+//! a validation error instead of exhausting memory. Archives inside archives
+//! are opened one level at a time, up to `MAX_DEPTH` levels.
+
+use std::io::{self, Cursor, Read};
+
+use flate2::read::GzDecoder;
+use sevenz_rust::{Password, SevenZReader};
+
+use crate::error::AppError;
+use crate::models::{ArchiveEntry, ArchiveFormat, ArchiveListing};
+
+/// Largest single entry that is extracted into memory.
+pub const MAX_ENTRY_BYTES: u64 = 128 * 1024 * 1024;
+
+/// Largest total a stream must be decompressed through to reach an entry
+/// (TAR.GZ and solid 7z archives).
+pub const MAX_STREAM_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Archives nested deeper than this are not opened.
+pub const MAX_DEPTH: usize = 4;
+
+/// Entries listed per archive.
+pub const MAX_ENTRIES: usize = 10_000;
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const EMPTY_ZIP_MAGIC: &[u8] = b"PK\x05\x06";
+const SEVEN_Z_MAGIC: &[u8] = b"7z\xBC\xAF\x27\x1C";
+const GZIP_MAGIC: &[u8] = b"\x1F\x8B";
+/// `ustar` magic of a TAR header, at this offset.
+const TAR_MAGIC_OFFSET: usize = 257;
+
+impl ArchiveFormat {
+    /// The format of an archive from its leading bytes, or its name when
+    /// those are not conclusive.
+    pub fn detect(filename: &str, data: &[u8]) -> Option<Self> {
+        if data.starts_with(ZIP_MAGIC) || data.starts_with(EMPTY_ZIP_MAGIC) {
+            return Some(Self::Zip);
+        }
+        if data.starts_with(SEVEN_Z_MAGIC) {
+            return Some(Self::SevenZ);
+        }
+        if data.starts_with(GZIP_MAGIC) {
+            // A bare `.gz` is one compressed file, not an archive
+            return (Self::from_name(filename) == Some(Self::TarGz)).then_some(Self::TarGz);
+        }
+        if data.get(TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + 5) == Some(b"ustar") {
+            return Some(Self::Tar);
+        }
+        Self::from_name(filename)
+    }
+
+    /// The format a file name suggests.
+    pub fn from_name(filename: &str) -> Option<Self> {
+        let name = filename.to_ascii_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".7z") {
+            Some(Self::SevenZ)
+        } else {
+            None
+        }
+    }
+}
+
+/// List the entries of an archive.
+pub fn list(filename: &str, data: &[u8]) -> Result<ArchiveListing, AppError> {
+    let format = detect(filename, data)?;
+    let mut entries = Vec::new();
+    let mut truncated = false;
+    let mut push = |entry: ArchiveEntry| {
+        if entries.len() < MAX_ENTRIES {
+            entries.push(entry);
+        } else {
+            truncated = true;
+        }
+    };
+
+    match format {
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(Cursor::new(data)).map_err(unreadable)?;
+            for i in 0..archive.len() {
+                let file = archive.by_index_raw(i).map_err(unreadable)?;
+                push(ArchiveEntry {
+                    path: file.name().to_string(),
+                    size: file.size(),
+                    compressed_size: Some(file.compressed_size()),
+                    modified: file.last_modified().map(|t| {
+                        format!(
+                            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+                            t.year(),
+                            t.month(),
+                            t.day(),
+                            t.hour(),
+                            t.minute(),
+                            t.second()
+                        )
+                    }),
+                    is_dir: file.is_dir(),
+                    is_archive: ArchiveFormat::from_name(file.name()).is_some(),
+                });
+            }
+        }
+        ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+            let mut archive = tar::Archive::new(tar_stream(format, data));
+            for entry in archive.entries().map_err(unreadable)? {
+                let entry = entry.map_err(unreadable)?;
+                let path = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+                let header = entry.header();
+                push(ArchiveEntry {
+                    is_archive: ArchiveFormat::from_name(&path).is_some(),
+                    path,
+                    size: entry.size(),
+                    compressed_size: None,
+                    modified: header
+                        .mtime()
+                        .ok()
+                        .filter(|&t| t > 0)
+                        .map(|t| format_unix_time(t as i64)),
+                    is_dir: header.entry_type().is_dir(),
+                });
+            }
+        }
+        ArchiveFormat::SevenZ => {
+            let reader = seven_z(data)?;
+            for file in &reader.archive().files {
+                push(ArchiveEntry {
+                    path: file.name.clone(),
+                    size: file.size,
+                    compressed_size: None,
+                    modified: file
+                        .has_last_modified_date
+                        .then(|| format_unix_time(file.last_modified_date.to_unix_time())),
+                    is_dir: file.is_directory,
+                    is_archive: ArchiveFormat::from_name(&file.name).is_some(),
+                });
+            }
+        }
+    }
+
+    Ok(ArchiveListing {
+        format,
+        entries,
+        truncated,
+    })
+}
+
+/// Extract one file of an archive by its listed path.
+pub fn extract(filename: &str, data: &[u8], path: &str) -> Result<Vec<u8>, AppError> {
+    let not_found = || AppError::NotFound(format!("Archive entry not found: {path}"));
+    match detect(filename, data)? {
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(Cursor::new(data)).map_err(unreadable)?;
+            let mut file = archive.by_name(path).map_err(|e| match e {
+                zip::result::ZipError::FileNotFound => not_found(),
+                e => unreadable(e),
+            })?;
+            check_size(path, file.size())?;
+            read_capped(path, &mut file)
+        }
+        format @ (ArchiveFormat::Tar | ArchiveFormat::TarGz) => {
+            let mut archive = tar::Archive::new(tar_stream(format, data));
+            for entry in archive.entries().map_err(unreadable)? {
+                let mut entry = entry.map_err(unreadable)?;
+                if *entry.path_bytes() == *path.as_bytes() {
+                    check_size(path, entry.size())?;
+                    return read_capped(path, &mut entry);
+                }
+            }
+            Err(not_found())
+        }
+        ArchiveFormat::SevenZ => {
+            let mut reader = seven_z(data)?;
+            let files = &reader.archive().files;
+            let target = files
+                .iter()
+                .find(|f| f.name == path && !f.is_directory)
+                .ok_or_else(not_found)?;
+            check_size(path, target.size)?;
+            // Solid blocks are decoded through every entry before the target
+            let total: u64 = files.iter().map(|f| f.size).sum();
+            if total > MAX_STREAM_BYTES {
+                return Err(too_large(path, total, MAX_STREAM_BYTES));
+            }
+
+            let mut found = None;
+            reader
+                .for_each_entries(|entry, content| {
+                    if entry.name == path {
+                        found = Some(read_capped(path, content));
+                        return Ok(false);
+                    }
+                    // Unread entries must be consumed to keep the block aligned
+                    io::copy(content, &mut io::sink()).map_err(sevenz_rust::Error::io)?;
+                    Ok(true)
+                })
+                .map_err(unreadable)?;
+            found.unwrap_or_else(|| Err(not_found()))
+        }
+    }
+}
+
+/// Follow `path` through nested archives: each element names an entry of
+/// the archive the previous one produced. Returns the last entry's name and
+/// content (the outer archive itself for an empty path).
+pub fn open_path(
+    filename: &str,
+    data: Vec<u8>,
+    path: &[String],
+) -> Result<(String, Vec<u8>), AppError> {
+    if path.len() > MAX_DEPTH {
+        return Err(AppError::Validation(format!(
+            "Archives nested more than {MAX_DEPTH} levels deep are not opened"
+        )));
+    }
+    let mut current = (filename.to_string(), data);
+    for entry in path {
+        let data = extract(&current.0, &current.1, entry)?;
+        current = (entry.clone(), data);
+    }
+    Ok(current)
+}
+
+fn detect(filename: &str, data: &[u8]) -> Result<ArchiveFormat, AppError> {
+    ArchiveFormat::detect(filename, data)
+        .ok_or_else(|| AppError::Validation(format!("Not a supported archive: {filename}")))
+}
+
+/// The uncompressed TAR stream, capped at `MAX_STREAM_BYTES`.
+fn tar_stream(format: ArchiveFormat, data: &[u8]) -> Box<dyn Read + '_> {
+    match format {
+        ArchiveFormat::TarGz => Box::new(GzDecoder::new(data).take(MAX_STREAM_BYTES)),
+        _ => Box::new(data),
+    }
+}
+
+fn seven_z(data: &[u8]) -> Result<SevenZReader<Cursor<&[u8]>>, AppError> {
+    SevenZReader::new(Cursor::new(data), data.len() as u64, Password::empty()).map_err(unreadable)
+}
+
+fn check_size(path: &str, size: u64) -> Result<(), AppError> {
+    if size > MAX_ENTRY_BYTES {
+        return Err(too_large(path, size, MAX_ENTRY_BYTES));
+    }
+    Ok(())
+}
+
+/// Read an entry, failing rather than trusting the size its header declared.
+fn read_capped(path: &str, content: &mut dyn Read) -> Result<Vec<u8>, AppError> {
+    let mut data = Vec::new();
+    content
+        .take(MAX_ENTRY_BYTES + 1)
+        .read_to_end(&mut data)
+        .map_err(unreadable)?;
+    if data.len() as u64 > MAX_ENTRY_BYTES {
+        return Err(too_large(path, data.len() as u64, MAX_ENTRY_BYTES));
+    }
+    Ok(data)
+}
+
+fn too_large(path: &str, size: u64, limit: u64) -> AppError {
+    AppError::Validation(format!(
+        "Archive entry {path} is too large to open ({size} bytes, limit {limit})"
+    ))
+}
+
+fn unreadable(e: impl std::fmt::Display) -> AppError {
+    AppError::Validation(format!("Cannot read archive: {e}"))
+}
+
+/// `YYYY-MM-DDTHH:MM:SSZ` for seconds since the Unix epoch.
+fn format_unix_time(secs: i64) -> String {
+    let (days, time) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    // Civil date from days since 1970-01-01 (proleptic Gregorian)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn zip_of(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in files {
+            writer
+                .start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn tar_gz_of(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, data) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mtime(1_700_000_000);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, *data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn lists_and_extracts_zip_entries() {
+        let data = zip_of(&[("docs/a.txt", b"alpha"), ("b.bin", b"beta")]);
+        let listing = list("files.zip", &data).unwrap();
+        assert_eq!(listing.format, ArchiveFormat::Zip);
+        let paths: Vec<&str> = listing.entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["docs/a.txt", "b.bin"]);
+        assert_eq!(listing.entries[0].size, 5);
+
+        assert_eq!(extract("files.zip", &data, "b.bin").unwrap(), b"beta");
+        assert!(matches!(
+            extract("files.zip", &data, "missing"),
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn lists_and_extracts_tar_gz_entries() {
+        let data = tar_gz_of(&[("report.csv", b"a,b\n1,2\n")]);
+        let listing = list("backup.tgz", &data).unwrap();
+        assert_eq!(listing.format, ArchiveFormat::TarGz);
+        assert_eq!(listing.entries[0].path, "report.csv");
+        assert_eq!(
+            listing.entries[0].modified.as_deref(),
+            Some("2023-11-14T22:13:20Z")
+        );
+        assert_eq!(
+            extract("backup.tgz", &data, "report.csv").unwrap(),
+            b"a,b\n1,2\n"
+        );
+    }
+
+    #[test]
+    fn lists_and_extracts_seven_z_entries() {
+        let mut writer = sevenz_rust::SevenZWriter::new(Cursor::new(Vec::new())).unwrap();
+        for (name, data) in [("one.txt", &b"first"[..]), ("two.txt", b"second")] {
+            let mut entry = sevenz_rust::SevenZArchiveEntry::new();
+            entry.name = name.to_string();
+            writer.push_archive_entry(entry, Some(data)).unwrap();
+        }
+        let data = writer.finish().unwrap().into_inner();
+
+        let listing = list("pack.7z", &data).unwrap();
+        assert_eq!(listing.format, ArchiveFormat::SevenZ);
+        assert_eq!(listing.entries.len(), 2);
+        assert_eq!(extract("pack.7z", &data, "two.txt").unwrap(), b"second");
+    }
+
+    #[test]
+    fn opens_nested_archives() {
+        let inner = tar_gz_of(&[("deep.txt", b"found")]);
+        let outer = zip_of(&[("inner.tar.gz", &inner)]);
+
+        let listing = list("outer.zip", &outer).unwrap();
+        assert!(listing.entries[0].is_archive);
+
+        let path = vec!["inner.tar.gz".to_string(), "deep.txt".to_string()];
+        let (name, data) = open_path("outer.zip", outer.clone(), &path).unwrap();
+        assert_eq!(name, "deep.txt");
+        assert_eq!(data, b"found");
+
+        let too_deep = vec!["inner.tar.gz".to_string(); MAX_DEPTH + 1];
+        assert!(open_path("outer.zip", outer, &too_deep).is_err());
+    }
+
+    #[test]
+    fn rejects_non_archives() {
+        assert!(list("notes.txt", b"plain text").is_err());
+        assert_eq!(ArchiveFormat::detect("data.gz", GZIP_MAGIC), None);
+    }
+}
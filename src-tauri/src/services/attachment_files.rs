@@ -0,0 +1,277 @@
+//! Writing decoded attachments to user-chosen locations.
+//!
+//! Attachment filenames come from the message and cannot be trusted: they
+//! are reduced to a single path component that is valid on every platform
+//! before being joined to a destination directory.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::AppError;
+use crate::models::{ConflictPolicy, SavedAttachment};
+
+use super::spool::SpooledAttachment;
+
+/// Longest filename written, in bytes (the common filesystem limit).
+const MAX_FILENAME_BYTES: usize = 255;
+
+/// Device names Windows reserves regardless of extension.
+const RESERVED_NAMES: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// Fallback for attachments whose name sanitizes to nothing.
+const DEFAULT_FILENAME: &str = "attachment";
+
+/// Reduce an attachment filename to a safe single path component.
+pub fn sanitize_filename(name: &str) -> String {
+    // Keep only the last component of anything that looks like a path
+    let base = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let cleaned: String = base
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let mut cleaned = cleaned.trim().trim_end_matches(['.', ' ']).to_string();
+
+    if cleaned.trim_matches('.').is_empty() {
+        return DEFAULT_FILENAME.to_string();
+    }
+    let stem = cleaned.split('.').next().unwrap_or_default().trim_end();
+    if RESERVED_NAMES.contains(&stem.to_ascii_lowercase().as_str()) {
+        cleaned.insert(0, '_');
+    }
+    truncate_keeping_extension(&cleaned, MAX_FILENAME_BYTES)
+}
+
+/// Where a file named `name` in `dir` should be written under `policy`,
+/// or `None` when it should be skipped.
+pub fn resolve_conflict(dir: &Path, name: &str, policy: ConflictPolicy) -> Option<PathBuf> {
+    let path = dir.join(name);
+    if !path.exists() {
+        return Some(path);
+    }
+    match policy {
+        ConflictPolicy::Overwrite => Some(path),
+        ConflictPolicy::Skip => None,
+        ConflictPolicy::Rename => {
+            // Leave room for the counter in names already at the length limit
+            let name = truncate_keeping_extension(name, MAX_FILENAME_BYTES - 16);
+            let (stem, ext) = split_extension(&name);
+            (1..)
+                .map(|n| dir.join(format!("{stem} ({n}){ext}")))
+                .find(|candidate| !candidate.exists())
+        }
+    }
+}
+
+/// Copy a spooled attachment to `destination`.
+///
+/// A directory destination gets the sanitized original filename, resolved
+/// against existing files with `policy`; any other path is written as given.
+/// The file is copied under a temporary name and renamed into place, so an
+/// interrupted save never leaves a truncated file with the final name.
+pub fn save(
+    spooled: &SpooledAttachment,
+    destination: &Path,
+    policy: ConflictPolicy,
+) -> Result<SavedAttachment, AppError> {
+    if !destination.is_absolute() {
+        return Err(AppError::Validation(
+            "Destination must be an absolute path".to_string(),
+        ));
+    }
+    let target = if destination.is_dir() {
+        let name = sanitize_filename(&spooled.filename);
+        match resolve_conflict(destination, &name, policy) {
+            Some(path) => path,
+            None => {
+                return Ok(SavedAttachment {
+                    path: destination.join(name).display().to_string(),
+                    size: 0,
+                    skipped: true,
+                })
+            }
+        }
+    } else if destination.parent().is_some_and(Path::is_dir) {
+        destination.to_path_buf()
+    } else {
+        return Err(AppError::Validation(format!(
+            "Destination folder does not exist: {}",
+            destination.display()
+        )));
+    };
+
+    let partial = partial_path(&target);
+    let size = fs::copy(&spooled.path, &partial)?;
+    finish(&partial, &target)?;
+
+    Ok(SavedAttachment {
+        path: target.display().to_string(),
+        size,
+        skipped: false,
+    })
+}
+
+/// Write `data` to `target` via a temporary file renamed into place.
+pub fn write_file(target: &Path, data: &[u8]) -> Result<(), AppError> {
+    let partial = partial_path(target);
+    fs::write(&partial, data)?;
+    finish(&partial, target)
+}
+
+/// Lowercase hex SHA-256 of a decoded attachment.
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Hidden sibling of `target` that the file is written to first.
+///
+/// The name is short, so targets at the filename length limit still fit,
+/// and unique per writer, so concurrent saves cannot share it.
+fn partial_path(target: &Path) -> PathBuf {
+    static WRITERS: AtomicUsize = AtomicUsize::new(0);
+    let writer = WRITERS.fetch_add(1, Ordering::Relaxed);
+    target.with_file_name(format!(".mboxviewer-{}-{writer}.part", std::process::id()))
+}
+
+fn finish(partial: &Path, target: &Path) -> Result<(), AppError> {
+    fs::rename(partial, target).map_err(|e| {
+        let _ = fs::remove_file(partial);
+        e.into()
+    })
+}
+
+/// `("report", ".pdf")`; names without a dot, or starting with one, have no extension.
+fn split_extension(name: &str) -> (&str, &str) {
+    match name.rfind('.') {
+        Some(i) if i > 0 => name.split_at(i),
+        _ => (name, ""),
+    }
+}
+
+fn truncate_keeping_extension(name: &str, max: usize) -> String {
+    if name.len() <= max {
+        return name.to_string();
+    }
+    let (stem, ext) = split_extension(name);
+    let ext = if ext.len() < max / 2 { ext } else { "" };
+    let mut end = max - ext.len();
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{ext}", &stem[..end.min(stem.len())])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_traversal_and_invalid_characters() {
+        assert_eq!(sanitize_filename("../../etc/passwd"), "passwd");
+        assert_eq!(sanitize_filename("C:\\Windows\\evil.exe"), "evil.exe");
+        assert_eq!(sanitize_filename("what?<now>.txt"), "what__now_.txt");
+        assert_eq!(sanitize_filename("tab\there.txt"), "tab_here.txt");
+        assert_eq!(sanitize_filename("trailing. . "), "trailing");
+        assert_eq!(sanitize_filename(".."), DEFAULT_FILENAME);
+        assert_eq!(sanitize_filename(""), DEFAULT_FILENAME);
+        assert_eq!(sanitize_filename("dir/"), DEFAULT_FILENAME);
+    }
+
+    #[test]
+    fn prefixes_reserved_device_names() {
+        assert_eq!(sanitize_filename("CON"), "_CON");
+        assert_eq!(sanitize_filename("nul.txt"), "_nul.txt");
+        assert_eq!(sanitize_filename("console.txt"), "console.txt");
+    }
+
+    #[test]
+    fn truncates_long_names_keeping_the_extension() {
+        let name = format!("{}.pdf", "é".repeat(200));
+        let sanitized = sanitize_filename(&name);
+        assert!(sanitized.len() <= MAX_FILENAME_BYTES);
+        assert!(sanitized.ends_with(".pdf"));
+    }
+
+    #[test]
+    fn resolves_conflicts_by_policy() {
+        let dir = std::env::temp_dir().join(format!(
+            "mboxviewer-attachment-files-test-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("report.pdf"), b"old").unwrap();
+        fs::write(dir.join("report (1).pdf"), b"old").unwrap();
+
+        assert_eq!(
+            resolve_conflict(&dir, "report.pdf", ConflictPolicy::Rename),
+            Some(dir.join("report (2).pdf"))
+        );
+        assert_eq!(
+            resolve_conflict(&dir, "report.pdf", ConflictPolicy::Overwrite),
+            Some(dir.join("report.pdf"))
+        );
+        assert_eq!(
+            resolve_conflict(&dir, "report.pdf", ConflictPolicy::Skip),
+            None
+        );
+        assert_eq!(
+            resolve_conflict(&dir, "new.pdf", ConflictPolicy::Skip),
+            Some(dir.join("new.pdf"))
+        );
+
+        let spool = dir.join("spooled");
+        fs::write(&spool, b"payload").unwrap();
+        let spooled = SpooledAttachment {
+            path: spool,
+            filename: "../report.pdf".to_string(),
+            content_type: "application/pdf".to_string(),
+            size: 7,
+        };
+        let saved = save(&spooled, &dir, ConflictPolicy::Rename).unwrap();
+        assert_eq!(saved.path, dir.join("report (2).pdf").display().to_string());
+        assert_eq!(saved.size, 7);
+        assert!(save(&spooled, &dir, ConflictPolicy::Skip).unwrap().skipped);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn saves_names_at_the_length_limit() {
+        let dir = std::env::temp_dir().join(format!(
+            "mboxviewer-attachment-files-long-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let spool = dir.join("spooled");
+        fs::write(&spool, b"payload").unwrap();
+        let spooled = SpooledAttachment {
+            path: spool,
+            filename: format!("{}.pdf", "a".repeat(400)),
+            content_type: "application/pdf".to_string(),
+            size: 7,
+        };
+
+        let first = save(&spooled, &dir, ConflictPolicy::Rename).unwrap();
+        let second = save(&spooled, &dir, ConflictPolicy::Rename).unwrap();
+        assert_ne!(first.path, second.path);
+        for saved in [first, second] {
+            let name = Path::new(&saved.path).file_name().unwrap().len();
+            assert!(name <= MAX_FILENAME_BYTES);
+            assert_eq!(fs::read(&saved.path).unwrap(), b"payload");
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
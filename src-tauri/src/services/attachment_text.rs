@@ -0,0 +1,343 @@
+//! Text extraction from attachment contents and a searchable index over it.
+//!
+//! Supports PDF, DOCX, XLSX/XLS/ODS, ODT/ODP, embedded emails and text-like
+//! parts using pure-Rust parsers. Unknown or unparseable parts simply yield no text.
+
+use std::io::{Cursor, Read};
+
+use calamine::Reader as _;
+use quick_xml::events::Event;
+use regex::{Regex, RegexBuilder};
+
+/// Parts larger than this are not extracted (decoded size).
+pub const MAX_EXTRACT_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Extracted text of one attachment.
+#[derive(Debug, Clone)]
+pub struct AttachmentText {
+    pub email_index: usize,
+    pub attachment_index: usize,
+    pub text: String,
+}
+
+/// Extracted text of every text-bearing attachment in the mailbox.
+#[derive(Debug, Default)]
+pub struct AttachmentTextIndex {
+    pub(crate) documents: Vec<AttachmentText>,
+}
+
+/// A phrase hit inside an attachment (byte offsets into its extracted text).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttachmentHit {
+    pub email_index: usize,
+    pub attachment_index: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl AttachmentTextIndex {
+    pub fn new(documents: Vec<AttachmentText>) -> Self {
+        Self { documents }
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// First case-insensitive occurrence of `phrase` in each attachment containing it.
+    pub fn find(&self, phrase: &str) -> Vec<AttachmentHit> {
+        let Some(needle) = phrase_regex(phrase) else {
+            return Vec::new();
+        };
+        self.documents
+            .iter()
+            .filter_map(|doc| {
+                let found = needle.find(&doc.text)?;
+                Some(AttachmentHit {
+                    email_index: doc.email_index,
+                    attachment_index: doc.attachment_index,
+                    start: found.start(),
+                    end: found.end(),
+                })
+            })
+            .collect()
+    }
+
+    /// Extracted documents belonging to one message.
+    pub fn documents_of(&self, email_index: usize) -> impl Iterator<Item = &AttachmentText> {
+        self.documents
+            .iter()
+            .filter(move |d| d.email_index == email_index)
+    }
+
+    pub fn text(&self, email_index: usize, attachment_index: usize) -> Option<&str> {
+        self.documents
+            .iter()
+            .find(|d| d.email_index == email_index && d.attachment_index == attachment_index)
+            .map(|d| d.text.as_str())
+    }
+}
+
+/// A case-insensitive literal matcher for `phrase`, run on the original text
+/// so match offsets always fall on its char boundaries.
+fn phrase_regex(phrase: &str) -> Option<Regex> {
+    if phrase.is_empty() {
+        return None;
+    }
+    RegexBuilder::new(&regex::escape(phrase))
+        .case_insensitive(true)
+        .build()
+        .ok()
+}
+
+/// Whether a part looks like something `extract_text` can read.
+pub fn is_extractable(filename: &str, content_type: &str) -> bool {
+    kind_of(filename, content_type).is_some()
+}
+
+/// Extract plain text from an attachment, or `None` if the type is unsupported
+/// or the document cannot be parsed.
+pub fn extract_text(filename: &str, content_type: &str, data: &[u8]) -> Option<String> {
+    let text = match kind_of(filename, content_type)? {
+        DocKind::Pdf => {
+            // pdf-extract panics on some malformed files; treat that as "no text"
+            std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem(data))
+                .ok()?
+                .ok()?
+        }
+        DocKind::Docx => xml_text(&zip_entry(data, "word/document.xml")?, b"p"),
+        DocKind::OpenDocumentText => xml_text(&zip_entry(data, "content.xml")?, b"p"),
+        DocKind::Spreadsheet => spreadsheet_text(data)?,
+        DocKind::Html => super::body_text::html_to_text(&String::from_utf8_lossy(data)),
+        DocKind::Message => super::rfc822::plain_text(data),
+        DocKind::Text => String::from_utf8_lossy(data).into_owned(),
+    };
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+enum DocKind {
+    Pdf,
+    Docx,
+    OpenDocumentText,
+    Spreadsheet,
+    Html,
+    Message,
+    Text,
+}
+
+fn kind_of(filename: &str, content_type: &str) -> Option<DocKind> {
+    let mime = super::facets::mime_essence(content_type);
+    let ext = filename
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    let kind = match (mime.as_str(), ext.as_str()) {
+        ("application/pdf", _) | (_, "pdf") => DocKind::Pdf,
+        ("application/vnd.openxmlformats-officedocument.wordprocessingml.document", _)
+        | (_, "docx") => DocKind::Docx,
+        ("application/vnd.oasis.opendocument.text", _)
+        | ("application/vnd.oasis.opendocument.presentation", _)
+        | (_, "odt" | "odp") => DocKind::OpenDocumentText,
+        ("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet", _)
+        | ("application/vnd.ms-excel", _)
+        | ("application/vnd.oasis.opendocument.spreadsheet", _)
+        | (_, "xlsx" | "xlsm" | "xls" | "ods") => DocKind::Spreadsheet,
+        ("text/html", _) | (_, "html" | "htm") => DocKind::Html,
+        ("message/rfc822", _) | (_, "eml") => DocKind::Message,
+        (m, _) if m.starts_with("text/") => DocKind::Text,
+        (_, "txt" | "csv" | "md" | "log" | "json" | "xml") => DocKind::Text,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+fn zip_entry(data: &[u8], name: &str) -> Option<String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data)).ok()?;
+    let file = archive.by_name(name).ok()?;
+    let mut xml = String::new();
+    file.take(MAX_EXTRACT_BYTES).read_to_string(&mut xml).ok()?;
+    Some(xml)
+}
+
+/// Concatenate the text nodes of an XML document, breaking lines after
+/// elements whose local name is `paragraph`.
+fn xml_text(xml: &str, paragraph: &[u8]) -> String {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let mut out = String::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Text(t)) => {
+                if let Ok(text) = t.unescape() {
+                    out.push_str(&text);
+                }
+            }
+            Ok(Event::End(e)) if e.local_name().as_ref() == paragraph => out.push('\n'),
+            Ok(Event::Empty(e)) if matches!(e.local_name().as_ref(), b"tab" | b"s") => {
+                out.push(' ')
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Whether the files of a ZIP container declare at most `MAX_EXTRACT_BYTES`
+/// in total. Data that is not a ZIP (legacy XLS) passes.
+fn within_unzipped_limit(data: &[u8]) -> bool {
+    let Ok(mut archive) = zip::ZipArchive::new(Cursor::new(data)) else {
+        return true;
+    };
+    let mut total = 0u64;
+    for i in 0..archive.len() {
+        let Ok(file) = archive.by_index_raw(i) else {
+            return false;
+        };
+        total = total.saturating_add(file.size());
+        if total > MAX_EXTRACT_BYTES {
+            return false;
+        }
+    }
+    true
+}
+
+fn spreadsheet_text(data: &[u8]) -> Option<String> {
+    // calamine inflates whole sheets with no limit of its own
+    if !within_unzipped_limit(data) {
+        return None;
+    }
+    let mut workbook = calamine::open_workbook_auto_from_rs(Cursor::new(data.to_vec())).ok()?;
+    let mut out = String::new();
+    for (name, range) in workbook.worksheets() {
+        out.push_str(&name);
+        out.push('\n');
+        for row in range.rows() {
+            let cells: Vec<String> = row.iter().map(|c| c.to_string()).collect();
+            out.push_str(cells.join("\t").trim_end());
+            out.push('\n');
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn zip_with(name: &str, contents: &str) -> Vec<u8> {
+        let mut buf = Cursor::new(Vec::new());
+        let mut writer = zip::ZipWriter::new(&mut buf);
+        writer
+            .start_file(name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(contents.as_bytes()).unwrap();
+        writer.finish().unwrap();
+        buf.into_inner()
+    }
+
+    fn index(texts: &[(usize, usize, &str)]) -> AttachmentTextIndex {
+        AttachmentTextIndex::new(
+            texts
+                .iter()
+                .map(|&(email_index, attachment_index, text)| AttachmentText {
+                    email_index,
+                    attachment_index,
+                    text: text.to_string(),
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn extracts_plain_text_by_content_type_or_extension() {
+        assert_eq!(
+            extract_text("notes", "text/plain; charset=utf-8", b" hello ").as_deref(),
+            Some("hello")
+        );
+        assert_eq!(
+            extract_text("data.csv", "application/octet-stream", b"a,b").as_deref(),
+            Some("a,b")
+        );
+        assert!(extract_text("photo.jpg", "image/jpeg", b"\xff\xd8").is_none());
+    }
+
+    #[test]
+    fn extracts_docx_paragraphs() {
+        let xml = r#"<w:document xmlns:w="x"><w:body><w:p><w:r><w:t>Purchase</w:t></w:r><w:r><w:t xml:space="preserve"> order &amp; terms</w:t></w:r></w:p><w:p><w:r><w:t>Total</w:t></w:r></w:p></w:body></w:document>"#;
+        let docx = zip_with("word/document.xml", xml);
+        assert_eq!(
+            extract_text("po.docx", "application/octet-stream", &docx).as_deref(),
+            Some("Purchase order & terms\nTotal")
+        );
+    }
+
+    #[test]
+    fn extracts_odt_paragraphs() {
+        let xml = r#"<office:document-content xmlns:text="t"><text:p>Hello<text:s/>world</text:p></office:document-content>"#;
+        let odt = zip_with("content.xml", xml);
+        assert_eq!(
+            extract_text("a.odt", "application/vnd.oasis.opendocument.text", &odt).as_deref(),
+            Some("Hello world")
+        );
+    }
+
+    #[test]
+    fn corrupt_documents_yield_no_text() {
+        assert!(extract_text("a.docx", "", b"not a zip").is_none());
+        assert!(extract_text("a.pdf", "application/pdf", b"%PDF-garbage").is_none());
+    }
+
+    #[test]
+    fn find_matches_phrase_case_insensitively() {
+        let idx = index(&[
+            (0, 0, "Please see the Purchase Order"),
+            (1, 2, "nothing here"),
+        ]);
+        assert_eq!(
+            idx.find("purchase order"),
+            vec![AttachmentHit {
+                email_index: 0,
+                attachment_index: 0,
+                start: 15,
+                end: 29
+            }]
+        );
+        assert!(idx.find("").is_empty());
+    }
+
+    #[test]
+    fn find_offsets_stay_on_char_boundaries() {
+        // `İ` and the Kelvin sign change byte length when lowercased
+        let text = "İİ Total: 5 \u{212A}elvin";
+        let idx = index(&[(0, 0, text)]);
+        let hits = idx.find("KELVIN");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(&text[hits[0].start..hits[0].end], "\u{212A}elvin");
+        let hits = idx.find("total");
+        assert_eq!(&text[hits[0].start..hits[0].end], "Total");
+    }
+
+    #[test]
+    fn skips_spreadsheets_that_unzip_past_the_limit() {
+        let mut buf = Cursor::new(Vec::new());
+        let mut writer = zip::ZipWriter::new(&mut buf);
+        writer
+            .start_file(
+                "xl/sharedStrings.xml",
+                zip::write::SimpleFileOptions::default(),
+            )
+            .unwrap();
+        let chunk = vec![b' '; 1024 * 1024];
+        for _ in 0..=MAX_EXTRACT_BYTES / chunk.len() as u64 {
+            writer.write_all(&chunk).unwrap();
+        }
+        writer.finish().unwrap();
+        let data = buf.into_inner();
+        assert!(!within_unzipped_limit(&data));
+        assert!(within_unzipped_limit(&zip_with("content.xml", "<a/>")));
+        assert!(extract_text("big.xlsx", "", &data).is_none());
+    }
+}
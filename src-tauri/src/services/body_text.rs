@@ -0,0 +1,86 @@
+//! Plain-text extraction from decoded message bodies.
+//!
+//! Text analysis (search, language detection, similarity...) works on a
+//! single plain-text rendition: the `text/plain` part when present,
+//! otherwise the `text/html` part with markup stripped.
+
+/// Best plain-text rendition of a body.
+pub fn plain_text(text: Option<&str>, html: Option<&str>) -> String {
+    match (text, html) {
+        (Some(text), _) if !text.trim().is_empty() => text.to_string(),
+        (_, Some(html)) => html_to_text(html),
+        _ => String::new(),
+    }
+}
+
+/// Strip tags, drop `<script>`/`<style>` content and decode common entities.
+pub fn html_to_text(html: &str) -> String {
+    let mut out = String::with_capacity(html.len() / 2);
+    let mut rest = html;
+
+    while let Some(lt) = rest.find('<') {
+        out.push_str(&rest[..lt]);
+        rest = &rest[lt..];
+
+        let Some(gt) = rest.find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = rest[1..gt].trim_start_matches('/').to_ascii_lowercase();
+        let name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        rest = &rest[gt + 1..];
+
+        if !rest.is_empty() && (name == "script" || name == "style") {
+            let close = format!("</{name}");
+            rest = match rest.to_ascii_lowercase().find(&close) {
+                Some(end) => rest[end..].find('>').map_or("", |gt| &rest[end + gt + 1..]),
+                None => "",
+            };
+        }
+        if matches!(
+            name.as_str(),
+            "br" | "p" | "div" | "tr" | "li" | "h1" | "h2" | "h3" | "h4" | "blockquote"
+        ) {
+            out.push('\n');
+        }
+    }
+    out.push_str(rest);
+
+    decode_entities(&out)
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_plain_text_part() {
+        assert_eq!(plain_text(Some("hello"), Some("<b>html</b>")), "hello");
+    }
+
+    #[test]
+    fn falls_back_to_html_when_text_is_blank() {
+        assert_eq!(plain_text(Some("  "), Some("<b>html</b>")), "html");
+        assert_eq!(plain_text(None, None), "");
+    }
+
+    #[test]
+    fn html_to_text_strips_tags_scripts_and_entities() {
+        let html = "<html><style>p{}</style><p>Tom &amp; Jerry</p><SCRIPT>x()</SCRIPT>end";
+        assert_eq!(html_to_text(html), "\nTom & Jerry\nend");
+    }
+}
@@ -0,0 +1,184 @@
+//! HTTP `Range` handling for parts served through the `mbox` scheme.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::error::AppError;
+
+/// Partial responses are capped at this many bytes; clients request the rest
+/// with a follow-up range.
+pub const MAX_RANGE_BYTES: u64 = 8 * 1024 * 1024;
+
+/// How a request's `Range` header applies to a part of `size` bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// No (usable) range on a part of at most `MAX_RANGE_BYTES`: send it whole
+    Full,
+    /// Inclusive byte range to send with 206 Partial Content
+    Partial { start: u64, end: u64 },
+    /// Range outside the part: 416 Range Not Satisfiable
+    Unsatisfiable,
+}
+
+impl RangeRequest {
+    /// Interpret a `Range` header value.
+    ///
+    /// Only single `bytes=` ranges are honoured; multi-range requests get the
+    /// whole part, as RFC 9110 allows. Larger parts are never sent whole: a
+    /// request without a usable range gets the first `MAX_RANGE_BYTES`, and
+    /// the client continues with ranges.
+    pub fn parse(header: Option<&str>, size: u64) -> Self {
+        match Self::parse_header(header, size) {
+            Self::Full if size > MAX_RANGE_BYTES => Self::Partial {
+                start: 0,
+                end: MAX_RANGE_BYTES - 1,
+            },
+            range => range,
+        }
+    }
+
+    fn parse_header(header: Option<&str>, size: u64) -> Self {
+        let Some(spec) = header.and_then(|h| h.trim().strip_prefix("bytes=")) else {
+            return Self::Full;
+        };
+        if spec.contains(',') {
+            return Self::Full;
+        }
+        let Some((start, end)) = spec.split_once('-') else {
+            return Self::Full;
+        };
+        let (start, end) = (start.trim(), end.trim());
+
+        let (start, end) = if start.is_empty() {
+            // Suffix range: the last `end` bytes
+            match end.parse::<u64>() {
+                Ok(0) | Err(_) => return Self::Unsatisfiable,
+                Ok(suffix) => (size.saturating_sub(suffix), size.saturating_sub(1)),
+            }
+        } else {
+            let Ok(start) = start.parse::<u64>() else {
+                return Self::Full;
+            };
+            let end = match end {
+                "" => size.saturating_sub(1),
+                end => match end.parse::<u64>() {
+                    Ok(end) if end >= start => end.min(size.saturating_sub(1)),
+                    _ => return Self::Full,
+                },
+            };
+            (start, end)
+        };
+
+        if size == 0 || start >= size {
+            return Self::Unsatisfiable;
+        }
+        Self::Partial {
+            start,
+            end: end.min(start + MAX_RANGE_BYTES - 1),
+        }
+    }
+}
+
+/// Read `start..=end` of a file.
+pub fn read_range(path: &Path, start: u64, end: u64) -> Result<Vec<u8>, AppError> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut data = Vec::with_capacity((end - start + 1) as usize);
+    file.take(end - start + 1).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// `Content-Disposition` value with an ASCII fallback and an RFC 6266
+/// `filename*` for non-ASCII names.
+pub fn content_disposition(filename: &str, inline: bool) -> String {
+    let kind = if inline { "inline" } else { "attachment" };
+    let fallback: String = filename
+        .chars()
+        .map(|c| match c {
+            ' '..='~' if c != '"' && c != '\\' => c,
+            _ => '_',
+        })
+        .collect();
+    if fallback == filename {
+        format!("{kind}; filename=\"{fallback}\"")
+    } else {
+        format!(
+            "{kind}; filename=\"{fallback}\"; filename*=UTF-8''{}",
+            super::scheme::percent_encode(filename)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_single_ranges() {
+        use RangeRequest::*;
+        assert_eq!(RangeRequest::parse(None, 100), Full);
+        assert_eq!(
+            RangeRequest::parse(Some("bytes=0-9"), 100),
+            Partial { start: 0, end: 9 }
+        );
+        assert_eq!(
+            RangeRequest::parse(Some("bytes=90-"), 100),
+            Partial { start: 90, end: 99 }
+        );
+        assert_eq!(
+            RangeRequest::parse(Some("bytes=-10"), 100),
+            Partial { start: 90, end: 99 }
+        );
+        assert_eq!(
+            RangeRequest::parse(Some("bytes=50-500"), 100),
+            Partial { start: 50, end: 99 }
+        );
+        assert_eq!(RangeRequest::parse(Some("bytes=100-"), 100), Unsatisfiable);
+        assert_eq!(RangeRequest::parse(Some("bytes=0-1,5-6"), 100), Full);
+        assert_eq!(RangeRequest::parse(Some("items=0-1"), 100), Full);
+        assert_eq!(RangeRequest::parse(Some("bytes=9-2"), 100), Full);
+    }
+
+    #[test]
+    fn caps_open_ended_ranges() {
+        let size = 3 * MAX_RANGE_BYTES;
+        assert_eq!(
+            RangeRequest::parse(Some("bytes=10-"), size),
+            RangeRequest::Partial {
+                start: 10,
+                end: 10 + MAX_RANGE_BYTES - 1
+            }
+        );
+    }
+
+    #[test]
+    fn large_parts_are_never_sent_whole() {
+        let size = MAX_RANGE_BYTES + 1;
+        let first_chunk = RangeRequest::Partial {
+            start: 0,
+            end: MAX_RANGE_BYTES - 1,
+        };
+        assert_eq!(RangeRequest::parse(None, size), first_chunk);
+        assert_eq!(
+            RangeRequest::parse(Some("bytes=0-1,5-6"), size),
+            first_chunk
+        );
+        assert_eq!(
+            RangeRequest::parse(None, MAX_RANGE_BYTES),
+            RangeRequest::Full
+        );
+    }
+
+    #[test]
+    fn disposition_escapes_non_ascii_names() {
+        assert_eq!(
+            content_disposition("report.pdf", false),
+            "attachment; filename=\"report.pdf\""
+        );
+        assert_eq!(
+            content_disposition("Informe año.pdf", true),
+            "inline; filename=\"Informe a_o.pdf\"; filename*=UTF-8''Informe%20a%C3%B1o.pdf"
+        );
+    }
+}
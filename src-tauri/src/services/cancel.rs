@@ -0,0 +1,44 @@
+//! Cooperative cancellation for long-running background work.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::error::AppError;
+
+/// A shared flag that a running job polls between units of work.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Bail out with `AppError::Cancelled` once cancellation was requested.
+    pub fn check(&self) -> Result<(), AppError> {
+        if self.is_cancelled() {
+            Err(AppError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_the_flag() {
+        let token = CancelToken::default();
+        let job = token.clone();
+        assert!(job.check().is_ok());
+        token.cancel();
+        assert!(job.is_cancelled());
+        assert!(matches!(job.check(), Err(AppError::Cancelled)));
+    }
+}
@@ -0,0 +1,196 @@
+//! Filtering and sorting the archive-wide attachment catalog.
+
+use super::extraction;
+use crate::models::{AttachmentCatalog, CatalogAttachment, CatalogQuery, CatalogSort};
+
+/// Page size when the query sets no limit.
+pub const DEFAULT_LIMIT: usize = 500;
+
+/// An attachment as cached for the catalog; message fields are looked up
+/// from the entries when listing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogRecord {
+    pub email_index: usize,
+    pub attachment_index: usize,
+    pub filename: String,
+    pub content_type: String,
+    pub size: u64,
+    pub sha256: Option<String>,
+}
+
+/// Records holding the same content as `source` in other, visible messages,
+/// oldest first. `timestamp` gives the date of a message by index.
+pub fn copies_of<'a>(
+    records: &'a [CatalogRecord],
+    source: &CatalogRecord,
+    is_hidden: impl Fn(usize) -> bool,
+    timestamp: impl Fn(usize) -> i64,
+) -> Vec<&'a CatalogRecord> {
+    let Some(sha256) = source.sha256.as_deref() else {
+        return Vec::new();
+    };
+    let mut copies: Vec<&CatalogRecord> = records
+        .iter()
+        .filter(|r| {
+            r.email_index != source.email_index
+                && !is_hidden(r.email_index)
+                && r.sha256.as_deref() == Some(sha256)
+        })
+        .collect();
+    copies.sort_by_key(|r| (timestamp(r.email_index), r.email_index, r.attachment_index));
+    copies
+}
+
+/// Filter, sort and page catalog attachments.
+pub fn query(mut items: Vec<CatalogAttachment>, query: &CatalogQuery) -> AttachmentCatalog {
+    let filename = query.filename.as_deref().map(str::to_lowercase);
+    let sender = query.sender.as_deref().map(str::to_lowercase);
+    items.retain(|item| {
+        filename
+            .as_deref()
+            .is_none_or(|f| item.filename.to_lowercase().contains(f))
+            && sender.as_deref().is_none_or(|s| {
+                item.from_address.to_lowercase().contains(s)
+                    || item.from_name.to_lowercase().contains(s)
+            })
+            && query.min_size.is_none_or(|min| item.size >= min)
+            && query.max_size.is_none_or(|max| item.size <= max)
+            && extraction::matches_type(&query.file_types, &item.filename, &item.content_type)
+    });
+
+    items.sort_by(|a, b| {
+        let order = match query.sort {
+            CatalogSort::Date => a.date.cmp(&b.date),
+            CatalogSort::Filename => a.filename.to_lowercase().cmp(&b.filename.to_lowercase()),
+            CatalogSort::Size => a.size.cmp(&b.size),
+            CatalogSort::ContentType => a.content_type.cmp(&b.content_type),
+            CatalogSort::Sender => a.from_address.cmp(&b.from_address),
+        };
+        let order = if query.descending {
+            order.reverse()
+        } else {
+            order
+        };
+        order.then_with(|| {
+            (a.email_index, a.attachment_index).cmp(&(b.email_index, b.attachment_index))
+        })
+    });
+
+    let total = items.len();
+    let total_size = items.iter().map(|item| item.size).sum();
+    let attachments = items
+        .into_iter()
+        .skip(query.offset)
+        .take(query.limit.unwrap_or(DEFAULT_LIMIT))
+        .collect();
+    AttachmentCatalog {
+        total,
+        total_size,
+        attachments,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(email_index: usize, filename: &str, size: u64, from: &str) -> CatalogAttachment {
+        CatalogAttachment {
+            email_index,
+            attachment_index: 0,
+            filename: filename.to_string(),
+            content_type: "application/octet-stream".to_string(),
+            size,
+            sha256: None,
+            date: format!("2020-01-0{email_index}T00:00:00+00:00"),
+            from_name: String::new(),
+            from_address: from.to_string(),
+            subject: String::new(),
+        }
+    }
+
+    fn items() -> Vec<CatalogAttachment> {
+        vec![
+            item(1, "budget.xlsx", 300, "ana@acme.com"),
+            item(2, "photo.jpg", 100, "bob@example.com"),
+            item(3, "Budget-2021.XLSX", 200, "ana@acme.com"),
+        ]
+    }
+
+    fn record(email_index: usize, attachment_index: usize, sha256: &str) -> CatalogRecord {
+        CatalogRecord {
+            email_index,
+            attachment_index,
+            filename: format!("copy-{email_index}.pdf"),
+            content_type: "application/pdf".to_string(),
+            size: 10,
+            sha256: Some(sha256.to_string()),
+        }
+    }
+
+    fn indices(catalog: &AttachmentCatalog) -> Vec<usize> {
+        catalog.attachments.iter().map(|a| a.email_index).collect()
+    }
+
+    #[test]
+    fn filters_by_name_type_sender_and_size() {
+        let catalog = query(
+            items(),
+            &CatalogQuery {
+                filename: Some("budget".to_string()),
+                file_types: vec!["xlsx".to_string()],
+                sender: Some("ACME".to_string()),
+                min_size: Some(250),
+                ..CatalogQuery::default()
+            },
+        );
+        assert_eq!(indices(&catalog), vec![1]);
+        assert_eq!(catalog.total_size, 300);
+    }
+
+    #[test]
+    fn sorts_and_pages() {
+        let by_size = CatalogQuery {
+            sort: CatalogSort::Size,
+            descending: true,
+            ..CatalogQuery::default()
+        };
+        assert_eq!(indices(&query(items(), &by_size)), vec![1, 3, 2]);
+
+        let paged = CatalogQuery {
+            sort: CatalogSort::Filename,
+            offset: 1,
+            limit: Some(1),
+            ..CatalogQuery::default()
+        };
+        let catalog = query(items(), &paged);
+        assert_eq!(catalog.total, 3);
+        assert_eq!(indices(&catalog), vec![1]);
+    }
+
+    #[test]
+    fn copies_skip_the_same_message_and_hidden_ones_oldest_first() {
+        let records = vec![
+            record(0, 0, "aa"),
+            record(0, 1, "aa"),
+            record(1, 0, "aa"),
+            record(2, 0, "bb"),
+            record(3, 0, "aa"),
+            record(4, 0, "aa"),
+        ];
+        // Message 4 is the oldest, message 1 is hidden
+        let timestamp = |index: usize| [40, 10, 20, 30, 0][index];
+        let copies = copies_of(&records, &records[0], |index| index == 1, timestamp);
+        let found: Vec<(usize, usize)> = copies
+            .iter()
+            .map(|r| (r.email_index, r.attachment_index))
+            .collect();
+        assert_eq!(found, vec![(4, 0), (3, 0)]);
+
+        let undecoded = CatalogRecord {
+            sha256: None,
+            ..record(2, 1, "")
+        };
+        assert!(copies_of(&records, &undecoded, |_| false, timestamp).is_empty());
+    }
+}
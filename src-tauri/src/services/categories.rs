@@ -0,0 +1,175 @@
+//! Inbox categorization from header heuristics.
+//!
+//! Checked in order: social networks by sender domain, discussion lists
+//! (`List-Id` with `List-Post`, or `Precedence: list`), automated
+//! notifications (`Auto-Submitted`, no-reply senders), then bulk mail
+//! (`List-Unsubscribe`, `Precedence: bulk`, mailer markers). Anything else
+//! is primary.
+
+use super::facets;
+use super::headers::MessageHeaders;
+use crate::models::MailCategory;
+
+/// Domains (and their subdomains) of social networks' notification mail.
+const SOCIAL_DOMAINS: &[&str] = &[
+    "facebookmail.com",
+    "facebook.com",
+    "linkedin.com",
+    "twitter.com",
+    "x.com",
+    "instagram.com",
+    "pinterest.com",
+    "tiktok.com",
+    "redditmail.com",
+    "meetup.com",
+    "xing.com",
+    "mastodon.social",
+];
+
+/// Domains that host discussion groups.
+const FORUM_DOMAINS: &[&str] = &["googlegroups.com", "groups.io", "discoursemail.com"];
+
+/// Sender local parts used by automated systems.
+const NOTIFICATION_SENDERS: &[&str] = &[
+    "noreply",
+    "no-reply",
+    "donotreply",
+    "do-not-reply",
+    "notification",
+    "notifications",
+    "alert",
+    "alerts",
+    "mailer-daemon",
+    "receipts",
+    "billing",
+];
+
+/// Sender local parts used for marketing mail.
+const PROMOTION_SENDERS: &[&str] = &[
+    "newsletter",
+    "news",
+    "marketing",
+    "offers",
+    "promo",
+    "promotions",
+    "deals",
+];
+
+pub fn classify(headers: &MessageHeaders, sender: &str) -> MailCategory {
+    let sender = sender.to_lowercase();
+    let (local, domain) = match sender.rsplit_once('@') {
+        Some((local, _)) => (local, facets::sender_domain(&sender)),
+        None => (sender.as_str(), String::new()),
+    };
+    let precedence = headers.precedence.as_deref();
+
+    if in_domains(&domain, SOCIAL_DOMAINS) {
+        return MailCategory::Social;
+    }
+    if in_domains(&domain, FORUM_DOMAINS)
+        || (headers.list_id.is_some() && headers.list_post.is_some())
+        || precedence == Some("list")
+    {
+        return MailCategory::Forums;
+    }
+    if headers.auto_submitted.is_some() || matches_prefix(local, NOTIFICATION_SENDERS) {
+        return MailCategory::Updates;
+    }
+    if headers.list_unsubscribe.is_some()
+        || headers.bulk_mailer
+        || matches!(precedence, Some("bulk" | "junk"))
+        || matches_prefix(local, PROMOTION_SENDERS)
+    {
+        return MailCategory::Promotions;
+    }
+    MailCategory::Primary
+}
+
+fn in_domains(domain: &str, list: &[&str]) -> bool {
+    list.iter()
+        .any(|d| domain == *d || domain.ends_with(&format!(".{d}")))
+}
+
+/// `noreply`, `noreply+tag` or `noreply-billing` all count as `noreply`.
+fn matches_prefix(local: &str, list: &[&str]) -> bool {
+    list.iter().any(|p| {
+        local == *p
+            || local
+                .strip_prefix(p)
+                .is_some_and(|rest| rest.starts_with(['+', '-', '.', '_']))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn social_networks_by_sender_domain() {
+        let headers = MessageHeaders {
+            list_unsubscribe: Some("mailto:u@x".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            classify(&headers, "notification@facebookmail.com"),
+            MailCategory::Social
+        );
+        assert_eq!(
+            classify(&headers, "messages-noreply@linkedin.com"),
+            MailCategory::Social
+        );
+    }
+
+    #[test]
+    fn discussion_lists_are_forums() {
+        let headers = MessageHeaders {
+            list_id: Some("users.rust-lang.org".to_string()),
+            list_post: Some("mailto:users@rust-lang.org".to_string()),
+            list_unsubscribe: Some("mailto:u@x".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            classify(&headers, "alice@example.com"),
+            MailCategory::Forums
+        );
+    }
+
+    #[test]
+    fn automated_senders_are_updates() {
+        let headers = MessageHeaders::default();
+        assert_eq!(
+            classify(&headers, "no-reply@bank.com"),
+            MailCategory::Updates
+        );
+        assert_eq!(
+            classify(&headers, "noreply+ship@shop.com"),
+            MailCategory::Updates
+        );
+        let auto = MessageHeaders {
+            auto_submitted: Some("auto-generated".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(classify(&auto, "ops@example.com"), MailCategory::Updates);
+    }
+
+    #[test]
+    fn bulk_mail_is_promotions_and_the_rest_primary() {
+        let bulk = MessageHeaders {
+            list_unsubscribe: Some("mailto:u@x".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(classify(&bulk, "hello@brand.com"), MailCategory::Promotions);
+        assert_eq!(
+            classify(&MessageHeaders::default(), "newsletter@brand.com"),
+            MailCategory::Promotions
+        );
+        assert_eq!(
+            classify(&MessageHeaders::default(), "bob@example.com"),
+            MailCategory::Primary
+        );
+        assert_eq!(
+            classify(&MessageHeaders::default(), "newsroom@example.com"),
+            MailCategory::Primary
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! Topic clustering with spherical k-means over TF-IDF vectors.
+//!
+//! Centroids are seeded farthest-first, so results are deterministic for a
+//! given mailbox. The cancel token is polled between passes and every few
+//! thousand documents within a pass.
+
+use std::collections::HashMap;
+
+use super::cancel::CancelToken;
+use super::tfidf::{self, TermVector, TfIdfIndex};
+use crate::error::AppError;
+
+/// Largest cluster count accepted.
+pub const MAX_CLUSTERS: usize = 100;
+
+/// Terms kept per centroid, so memory does not grow with the vocabulary.
+const CENTROID_TERMS: usize = 1000;
+
+/// Upper bound for the automatic cluster count.
+const DEFAULT_MAX: usize = 20;
+
+/// Keywords reported per cluster.
+pub const KEYWORDS: usize = 5;
+
+const MAX_ITERATIONS: usize = 25;
+const CANCEL_CHECK_EVERY: usize = 2048;
+
+/// One topic: its member documents (message indices) and top keywords.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cluster {
+    pub members: Vec<usize>,
+    pub keywords: Vec<String>,
+}
+
+/// Rule-of-thumb cluster count for `n` documents.
+pub fn default_k(n: usize) -> usize {
+    (((n as f64) / 2.0).sqrt().round() as usize).clamp(2, DEFAULT_MAX)
+}
+
+/// Cluster the documents in `docs` (indices into `index.vectors`) into at
+/// most `k` topics, largest first. Documents without terms are left out.
+pub fn cluster(
+    index: &TfIdfIndex,
+    docs: &[usize],
+    k: usize,
+    cancel: &CancelToken,
+) -> Result<Vec<Cluster>, AppError> {
+    let docs: Vec<usize> = docs
+        .iter()
+        .copied()
+        .filter(|&d| index.vectors.get(d).is_some_and(|v| !v.is_empty()))
+        .collect();
+    let k = k.min(docs.len());
+    if k == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut centroids = seed(index, &docs, k, cancel)?;
+    let mut assignment = vec![usize::MAX; docs.len()];
+
+    for _ in 0..MAX_ITERATIONS {
+        cancel.check()?;
+        let mut changed = false;
+        for (n, &doc) in docs.iter().enumerate() {
+            if n % CANCEL_CHECK_EVERY == 0 {
+                cancel.check()?;
+            }
+            let best = nearest(&index.vectors[doc], &centroids);
+            if assignment[n] != best {
+                assignment[n] = best;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+        for (c, centroid) in centroids.iter_mut().enumerate() {
+            let members = docs
+                .iter()
+                .zip(&assignment)
+                .filter(|&(_, &a)| a == c)
+                .map(|(&doc, _)| &index.vectors[doc]);
+            // An empty cluster keeps its previous centroid
+            if let Some(mean) = centroid_of(members) {
+                *centroid = mean;
+            }
+        }
+    }
+
+    let mut clusters: Vec<Cluster> = centroids
+        .iter()
+        .enumerate()
+        .map(|(c, centroid)| Cluster {
+            members: docs
+                .iter()
+                .zip(&assignment)
+                .filter(|&(_, &a)| a == c)
+                .map(|(&doc, _)| doc)
+                .collect(),
+            keywords: top_keywords(index, centroid, KEYWORDS),
+        })
+        .filter(|c| !c.members.is_empty())
+        .collect();
+    clusters.sort_by(|a, b| b.members.len().cmp(&a.members.len()));
+    Ok(clusters)
+}
+
+/// Farthest-first seeding: start from the first document, then repeatedly
+/// pick the document least similar to every centroid chosen so far.
+fn seed(
+    index: &TfIdfIndex,
+    docs: &[usize],
+    k: usize,
+    cancel: &CancelToken,
+) -> Result<Vec<TermVector>, AppError> {
+    let mut centroids = vec![index.vectors[docs[0]].clone()];
+    let mut closest = vec![f32::MIN; docs.len()];
+    while centroids.len() < k {
+        cancel.check()?;
+        let last = centroids.last().expect("at least one centroid");
+        let mut next = 0;
+        for (n, &doc) in docs.iter().enumerate() {
+            closest[n] = closest[n].max(index.vectors[doc].cosine(last));
+            if closest[n] < closest[next] {
+                next = n;
+            }
+        }
+        centroids.push(index.vectors[docs[next]].clone());
+    }
+    Ok(centroids)
+}
+
+fn nearest(vector: &TermVector, centroids: &[TermVector]) -> usize {
+    let mut best = (0, f32::MIN);
+    for (c, centroid) in centroids.iter().enumerate() {
+        let score = vector.cosine(centroid);
+        if score > best.1 {
+            best = (c, score);
+        }
+    }
+    best.0
+}
+
+/// Normalized sum of `vectors`, cut to its `CENTROID_TERMS` heaviest terms;
+/// `None` when there are no vectors.
+fn centroid_of<'a>(vectors: impl Iterator<Item = &'a TermVector>) -> Option<TermVector> {
+    let mut sum: HashMap<u32, f32> = HashMap::new();
+    let mut any = false;
+    for vector in vectors {
+        any = true;
+        for &(id, w) in &vector.0 {
+            *sum.entry(id).or_default() += w;
+        }
+    }
+    if !any {
+        return None;
+    }
+    let mut weights: Vec<(u32, f32)> = sum.into_iter().collect();
+    weights.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    weights.truncate(CENTROID_TERMS);
+    Some(tfidf::normalize(weights))
+}
+
+fn top_keywords(index: &TfIdfIndex, centroid: &TermVector, n: usize) -> Vec<String> {
+    let mut weights: Vec<(u32, f32)> = centroid
+        .0
+        .iter()
+        .copied()
+        .filter(|&(_, w)| w > 0.0)
+        .collect();
+    weights.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    weights
+        .into_iter()
+        .take(n)
+        .map(|(id, _)| index.vocabulary[id as usize].clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> TfIdfIndex {
+        TfIdfIndex::build([
+            "invoice payment due for the acme contract",
+            "football match tickets for saturday stadium",
+            "acme invoice overdue payment reminder",
+            "",
+            "stadium parking for the football match",
+            "contract invoice payment terms acme",
+        ])
+    }
+
+    #[test]
+    fn groups_documents_by_topic() {
+        let idx = index();
+        let docs: Vec<usize> = (0..idx.vectors.len()).collect();
+        let clusters = cluster(&idx, &docs, 2, &CancelToken::default()).unwrap();
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].members, vec![0, 2, 5]);
+        assert_eq!(clusters[1].members, vec![1, 4]);
+        assert!(clusters[0].keywords.iter().any(|k| k == "invoice"));
+        assert!(clusters[1].keywords.iter().any(|k| k == "football"));
+    }
+
+    #[test]
+    fn cluster_count_is_bounded_by_documents() {
+        let idx = index();
+        let clusters = cluster(&idx, &[0], 5, &CancelToken::default()).unwrap();
+        assert_eq!(clusters.len(), 1);
+        assert!(cluster(&idx, &[3], 5, &CancelToken::default())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn cancelled_token_stops_clustering() {
+        let token = CancelToken::default();
+        token.cancel();
+        let idx = index();
+        let result = cluster(&idx, &[0, 1, 2], 2, &token);
+        assert!(matches!(result, Err(AppError::Cancelled)));
+    }
+
+    #[test]
+    fn centroids_keep_only_their_heaviest_terms() {
+        let vectors: Vec<TermVector> = (0..CENTROID_TERMS as u32 + 10)
+            .map(|id| TermVector(vec![(id, 1.0 + id as f32)]))
+            .collect();
+        let centroid = centroid_of(vectors.iter()).unwrap();
+        assert_eq!(centroid.0.len(), CENTROID_TERMS);
+        assert!(centroid.0.iter().all(|&(id, _)| id >= 10));
+        assert!(centroid_of(std::iter::empty()).is_none());
+    }
+
+    #[test]
+    fn default_k_is_clamped() {
+        assert_eq!(default_k(1), 2);
+        assert_eq!(default_k(200), 10);
+        assert_eq!(default_k(1_000_000), DEFAULT_MAX);
+    }
+}
@@ -0,0 +1,36 @@
+//! Minimal CSV writing for exports.
+
+/// Quote a field when it contains a separator, quote or line break.
+pub fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Join fields into one CSV line (without the trailing newline).
+pub fn row<S: AsRef<str>>(fields: &[S]) -> String {
+    fields
+        .iter()
+        .map(|f| field(f.as_ref()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_fields_are_unquoted() {
+        assert_eq!(row(&["a", "b c", "1"]), "a,b c,1");
+    }
+
+    #[test]
+    fn special_fields_are_quoted_and_escaped() {
+        assert_eq!(field("a,b"), "\"a,b\"");
+        assert_eq!(field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(field("two\nlines"), "\"two\nlines\"");
+    }
+}
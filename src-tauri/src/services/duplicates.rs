@@ -0,0 +1,350 @@
+//! Exact and near-duplicate message detection.
+//!
+//! Exact duplicates share a Message-ID or a normalized content hash
+//! (sender, subject, date, body and attachment names and sizes). Near
+//! duplicates are found with MinHash over word shingles: bodies whose
+//! estimated Jaccard similarity reaches `NEAR_SIMILARITY` are grouped, using
+//! LSH banding to avoid comparing every pair.
+
+use std::collections::HashMap;
+
+use sha2::{Digest, Sha256};
+
+use super::union_find::DisjointSets;
+use crate::models::DuplicateCollapse;
+
+/// Minimum estimated shingle Jaccard similarity for near duplicates.
+pub const NEAR_SIMILARITY: f32 = 0.7;
+
+/// MinHash signature length, split into `BANDS` bands for LSH.
+const HASHES: usize = 64;
+const BANDS: usize = 16;
+
+/// Bodies with fewer words than this are too short to judge similarity.
+const MIN_WORDS: usize = 8;
+
+const SHINGLE: usize = 3;
+
+/// What the detector needs to know about a message.
+#[derive(Debug, Clone, Default)]
+pub struct DuplicateInput {
+    pub message_id: Option<String>,
+    pub sender: String,
+    pub subject: String,
+    /// `None` when the message could not be decoded; it then only matches
+    /// other copies by Message-ID
+    pub body: Option<String>,
+    /// Date as Unix seconds
+    pub date: i64,
+    /// Attachment file names and sizes
+    pub attachments: Vec<(String, u64)>,
+    /// Position in the MBOX file; the earliest copy represents its group
+    pub offset: u64,
+}
+
+/// Per-message fingerprints, computed once so the body can be dropped.
+#[derive(Debug, Clone)]
+pub struct Fingerprint {
+    pub message_id: Option<String>,
+    pub content_hash: Option<[u8; 32]>,
+    pub minhash: Option<Vec<u64>>,
+    pub offset: u64,
+}
+
+impl Fingerprint {
+    pub fn of(input: &DuplicateInput) -> Self {
+        let Some(body) = input.body.as_deref().map(normalize) else {
+            return Self {
+                message_id: input.message_id.clone(),
+                content_hash: None,
+                minhash: None,
+                offset: input.offset,
+            };
+        };
+        let mut hasher = Sha256::new();
+        for part in [&normalize(&input.sender), &normalize(&input.subject), &body] {
+            hasher.update(part.as_bytes());
+            hasher.update([0]);
+        }
+        hasher.update(input.date.to_le_bytes());
+        for (name, size) in &input.attachments {
+            hasher.update(name.as_bytes());
+            hasher.update([0]);
+            hasher.update(size.to_le_bytes());
+        }
+        Self {
+            message_id: input.message_id.clone(),
+            content_hash: Some(hasher.finalize().into()),
+            minhash: minhash(&body),
+            offset: input.offset,
+        }
+    }
+}
+
+/// Duplicate groups over a mailbox (message indices, representative first).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DuplicateGroups {
+    pub exact: Vec<Vec<usize>>,
+    /// Near-duplicate groups over exact-group representatives and singletons
+    pub near: Vec<Vec<usize>>,
+}
+
+pub fn detect(prints: &[Fingerprint]) -> DuplicateGroups {
+    let mut exact_sets = DisjointSets::with_len(prints.len());
+    let mut by_id: HashMap<&str, usize> = HashMap::new();
+    let mut by_hash: HashMap<[u8; 32], usize> = HashMap::new();
+    for (i, p) in prints.iter().enumerate() {
+        if let Some(id) = &p.message_id {
+            if let Some(&first) = by_id.get(id.as_str()) {
+                exact_sets.union(first, i);
+            } else {
+                by_id.insert(id, i);
+            }
+        }
+        if let Some(hash) = p.content_hash {
+            match by_hash.get(&hash) {
+                Some(&first) => exact_sets.union(first, i),
+                None => {
+                    by_hash.insert(hash, i);
+                }
+            }
+        }
+    }
+    let exact: Vec<Vec<usize>> = exact_sets
+        .groups()
+        .into_iter()
+        .map(|g| representative_first(g, prints))
+        .collect();
+
+    // Near duplicates are looked for among distinct contents only
+    let mut hidden = vec![false; prints.len()];
+    for group in &exact {
+        for &i in &group[1..] {
+            hidden[i] = true;
+        }
+    }
+    let candidates: Vec<(usize, &[u64])> = prints
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !hidden[*i])
+        .filter_map(|(i, p)| p.minhash.as_deref().map(|h| (i, h)))
+        .collect();
+
+    DuplicateGroups {
+        near: near_groups(&candidates, prints),
+        exact,
+    }
+}
+
+/// Which messages a collapse mode hides: every group member but the
+/// representative, for exact groups and (in `ExactAndNear`) near groups.
+pub fn hidden_mask(groups: &DuplicateGroups, mode: DuplicateCollapse, len: usize) -> Vec<bool> {
+    let near: &[Vec<usize>] = match mode {
+        DuplicateCollapse::Off => return Vec::new(),
+        DuplicateCollapse::Exact => &[],
+        DuplicateCollapse::ExactAndNear => &groups.near,
+    };
+    let mut hidden = vec![false; len];
+    for group in groups.exact.iter().chain(near) {
+        for &i in &group[1..] {
+            hidden[i] = true;
+        }
+    }
+    hidden
+}
+
+/// Group signatures whose estimated similarity reaches `NEAR_SIMILARITY`.
+///
+/// Only signatures that agree on every row of at least one band are
+/// compared, which catches similar pairs with high probability.
+fn near_groups(candidates: &[(usize, &[u64])], prints: &[Fingerprint]) -> Vec<Vec<usize>> {
+    let rows = HASHES / BANDS;
+    let mut sets = DisjointSets::with_len(prints.len());
+    for band in 0..BANDS {
+        let mut buckets: HashMap<&[u64], Vec<(usize, &[u64])>> = HashMap::new();
+        for &(i, signature) in candidates {
+            buckets
+                .entry(&signature[band * rows..(band + 1) * rows])
+                .or_default()
+                .push((i, signature));
+        }
+        for bucket in buckets.values() {
+            for (a, &(i, si)) in bucket.iter().enumerate() {
+                for &(j, sj) in &bucket[a + 1..] {
+                    if similarity(si, sj) >= NEAR_SIMILARITY {
+                        sets.union(i, j);
+                    }
+                }
+            }
+        }
+    }
+    sets.groups()
+        .into_iter()
+        .map(|g| representative_first(g, prints))
+        .collect()
+}
+
+fn representative_first(mut group: Vec<usize>, prints: &[Fingerprint]) -> Vec<usize> {
+    group.sort_by_key(|&i| (prints[i].offset, i));
+    group
+}
+
+/// Lowercase and collapse whitespace so formatting differences don't matter.
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// MinHash signature over word shingles, or `None` for very short texts.
+pub fn minhash(normalized: &str) -> Option<Vec<u64>> {
+    let words: Vec<&str> = normalized.split(' ').filter(|w| !w.is_empty()).collect();
+    if words.len() < MIN_WORDS {
+        return None;
+    }
+
+    let mut signature = vec![u64::MAX; HASHES];
+    for shingle in words.windows(SHINGLE) {
+        let base = fnv1a(shingle);
+        for (seed, min) in signature.iter_mut().enumerate() {
+            *min = (*min).min(mix(base ^ (seed as u64).wrapping_mul(0x9e3779b97f4a7c15)));
+        }
+    }
+    Some(signature)
+}
+
+/// Estimated Jaccard similarity: the share of equal signature slots.
+pub fn similarity(a: &[u64], b: &[u64]) -> f32 {
+    let equal = a.iter().zip(b).filter(|(x, y)| x == y).count();
+    equal as f32 / a.len().max(1) as f32
+}
+
+fn fnv1a(words: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for word in words {
+        for byte in word.bytes().chain([b' ']) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+/// SplitMix64 finalizer, turning one hash into independent-looking variants.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEWSLETTER: &str = "Welcome to the weekly digest. This week we cover the new \
+        release of our product, upcoming events in your area, tips for getting the most \
+        out of your subscription and a short survey about your experience with support.";
+
+    fn input(id: Option<&str>, subject: &str, body: &str, offset: u64) -> DuplicateInput {
+        DuplicateInput {
+            message_id: id.map(str::to_string),
+            sender: "news@example.com".to_string(),
+            subject: subject.to_string(),
+            body: Some(body.to_string()),
+            date: 0,
+            attachments: Vec::new(),
+            offset,
+        }
+    }
+
+    fn detect_inputs(inputs: &[DuplicateInput]) -> DuplicateGroups {
+        detect(&inputs.iter().map(Fingerprint::of).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn same_message_id_is_exact_duplicate_with_earliest_copy_first() {
+        let groups = detect_inputs(&[
+            input(Some("a@x"), "Hi", "one", 500),
+            input(Some("a@x"), "Hi (fwd)", "other", 100),
+            input(Some("b@x"), "Hi", "two", 0),
+        ]);
+        assert_eq!(groups.exact, vec![vec![1, 0]]);
+    }
+
+    #[test]
+    fn same_normalized_content_is_exact_duplicate() {
+        let groups = detect_inputs(&[
+            input(None, "Report", "Numbers  are\nattached", 0),
+            input(Some("z@x"), "report", "numbers are attached", 10),
+        ]);
+        assert_eq!(groups.exact, vec![vec![0, 1]]);
+    }
+
+    #[test]
+    fn same_text_with_different_attachments_is_not_a_duplicate() {
+        let scan = |size| DuplicateInput {
+            attachments: vec![("scan.pdf".to_string(), size)],
+            ..input(None, "Scan", "", 0)
+        };
+        let groups = detect_inputs(&[scan(1000), scan(2000)]);
+        assert!(groups.exact.is_empty());
+
+        let dated = |date| DuplicateInput {
+            date,
+            ..input(None, "Daily report", "", 0)
+        };
+        assert!(detect_inputs(&[dated(100), dated(86_500)]).exact.is_empty());
+    }
+
+    #[test]
+    fn undecodable_messages_only_match_by_message_id() {
+        let broken = |id: Option<&str>| DuplicateInput {
+            body: None,
+            ..input(id, "Hi", "", 0)
+        };
+        let groups = detect_inputs(&[broken(None), broken(None), broken(Some("a@x"))]);
+        assert!(groups.exact.is_empty());
+        let groups = detect_inputs(&[broken(Some("a@x")), broken(Some("a@x"))]);
+        assert_eq!(groups.exact, vec![vec![0, 1]]);
+    }
+
+    #[test]
+    fn slightly_edited_newsletters_are_near_duplicates() {
+        let edited = NEWSLETTER.replace("short survey", "brief survey");
+        let groups = detect_inputs(&[
+            input(Some("1@x"), "Digest #1", NEWSLETTER, 0),
+            input(Some("2@x"), "Digest #2", &edited, 10),
+            input(
+                Some("3@x"),
+                "Lunch",
+                "Are we still on for lunch at noon tomorrow near the office?",
+                20,
+            ),
+        ]);
+        assert!(groups.exact.is_empty());
+        assert_eq!(groups.near, vec![vec![0, 1]]);
+    }
+
+    #[test]
+    fn hidden_mask_keeps_representatives() {
+        let groups = DuplicateGroups {
+            exact: vec![vec![2, 0]],
+            near: vec![vec![1, 3]],
+        };
+        assert!(hidden_mask(&groups, DuplicateCollapse::Off, 4).is_empty());
+        assert_eq!(
+            hidden_mask(&groups, DuplicateCollapse::Exact, 4),
+            vec![true, false, false, false]
+        );
+        assert_eq!(
+            hidden_mask(&groups, DuplicateCollapse::ExactAndNear, 4),
+            vec![true, false, false, true]
+        );
+    }
+
+    #[test]
+    fn short_bodies_have_no_signature() {
+        assert!(minhash("thanks").is_none());
+    }
+}
@@ -0,0 +1,264 @@
+//! Folder templates, type filters and the manifest for bulk attachment
+//! extraction.
+//!
+//! A template such as `{year}/{sender_domain}/{date}_{filename}` is split on
+//! `/` into directories; every rendered component goes through
+//! `attachment_files::sanitize_filename`, so message data can never escape
+//! the destination directory.
+
+use std::path::PathBuf;
+
+use super::attachment_files::sanitize_filename;
+use super::csv;
+use super::facets::mime_essence;
+use crate::error::AppError;
+
+pub const DEFAULT_TEMPLATE: &str = "{year}/{sender_domain}/{date}_{filename}";
+
+/// Placeholders a template may use.
+pub const PLACEHOLDERS: &[&str] = &[
+    "year",
+    "month",
+    "day",
+    "date",
+    "sender",
+    "sender_domain",
+    "sender_name",
+    "subject",
+    "filename",
+    "ext",
+    "index",
+];
+
+/// Subjects are cut to this many characters in paths.
+const MAX_SUBJECT_CHARS: usize = 80;
+
+pub const MANIFEST_FILENAME: &str = "manifest.csv";
+
+pub const MANIFEST_HEADER: &str =
+    "file,status,duplicate_of,sha256,size,email_index,date,from,subject,attachment_index,original_filename";
+
+/// Message and attachment data a template is rendered from.
+pub struct TemplateFields<'a> {
+    /// RFC 3339 date of the message
+    pub date: &'a str,
+    pub sender: &'a str,
+    pub sender_name: &'a str,
+    pub subject: &'a str,
+    pub filename: &'a str,
+    pub index: usize,
+}
+
+/// Render a template into a relative path.
+pub fn render(template: &str, fields: &TemplateFields) -> Result<PathBuf, AppError> {
+    let mut path = PathBuf::new();
+    for segment in template.split(['/', '\\']) {
+        if segment.trim().is_empty() {
+            continue;
+        }
+        let rendered = render_segment(segment, fields)?;
+        path.push(sanitize_filename(&rendered));
+    }
+    if path.as_os_str().is_empty() {
+        return Err(AppError::Validation("Template is empty".to_string()));
+    }
+    Ok(path)
+}
+
+/// Check a template before any file is written.
+pub fn validate(template: &str) -> Result<(), AppError> {
+    render(
+        template,
+        &TemplateFields {
+            date: "2000-01-01T00:00:00+00:00",
+            sender: "a@b",
+            sender_name: "",
+            subject: "",
+            filename: "f",
+            index: 0,
+        },
+    )
+    .map(|_| ())
+}
+
+fn render_segment(segment: &str, fields: &TemplateFields) -> Result<String, AppError> {
+    let mut out = String::new();
+    let mut rest = segment;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..].find('}').ok_or_else(|| {
+            AppError::Validation(format!("Unclosed placeholder in template: {segment}"))
+        })?;
+        let name = &rest[start + 1..start + end];
+        out.push_str(&placeholder(name, fields)?.replace(['/', '\\'], "_"));
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn placeholder(name: &str, fields: &TemplateFields) -> Result<String, AppError> {
+    let date = fields.date;
+    let domain = fields.sender.rsplit_once('@').map_or("", |(_, d)| d);
+    let value = match name {
+        "year" => date.get(..4).unwrap_or_default().to_string(),
+        "month" => date.get(5..7).unwrap_or_default().to_string(),
+        "day" => date.get(8..10).unwrap_or_default().to_string(),
+        "date" => date.get(..10).unwrap_or_default().to_string(),
+        "sender" => fields.sender.to_lowercase(),
+        "sender_domain" => domain.to_lowercase(),
+        "sender_name" => fields.sender_name.trim().to_string(),
+        "subject" => fields
+            .subject
+            .trim()
+            .chars()
+            .take(MAX_SUBJECT_CHARS)
+            .collect(),
+        "filename" => fields.filename.to_string(),
+        "ext" => fields
+            .filename
+            .rsplit_once('.')
+            .map_or("", |(_, ext)| ext)
+            .to_lowercase(),
+        "index" => fields.index.to_string(),
+        _ => {
+            return Err(AppError::Validation(format!(
+                "Unknown template placeholder {{{name}}}; expected one of: {}",
+                PLACEHOLDERS.join(", ")
+            )))
+        }
+    };
+    Ok(if value.is_empty() {
+        "unknown".to_string()
+    } else {
+        value
+    })
+}
+
+/// Whether an attachment passes the type filter: extensions (`pdf`, `.pdf`)
+/// or MIME types (`application/pdf`, `image/*`). An empty filter passes all.
+pub fn matches_type(file_types: &[String], filename: &str, content_type: &str) -> bool {
+    if file_types.is_empty() {
+        return true;
+    }
+    let essence = mime_essence(content_type);
+    let extension = filename.rsplit_once('.').map(|(_, ext)| ext.to_lowercase());
+    file_types.iter().any(|wanted| {
+        let wanted = wanted.trim().to_lowercase();
+        if let Some(major) = wanted.strip_suffix("/*") {
+            essence.split('/').next() == Some(major)
+        } else if wanted.contains('/') {
+            essence == wanted
+        } else {
+            extension.as_deref() == Some(wanted.trim_start_matches('.'))
+        }
+    })
+}
+
+/// One line of the manifest.
+pub struct ManifestRow<'a> {
+    /// Path relative to the destination directory
+    pub file: &'a str,
+    /// `written`, `duplicate`, `skipped` or `failed`; a cancelled run ends
+    /// with a line of its own (see [`cancelled_row`])
+    pub status: &'a str,
+    pub duplicate_of: &'a str,
+    pub sha256: &'a str,
+    pub size: u64,
+    pub email_index: usize,
+    pub date: &'a str,
+    pub from: &'a str,
+    pub subject: &'a str,
+    /// `None` on the row of a message that could not be decoded
+    pub attachment_index: Option<usize>,
+    pub original_filename: &'a str,
+}
+
+impl ManifestRow<'_> {
+    pub fn to_csv(&self) -> String {
+        csv::row(&[
+            self.file,
+            self.status,
+            self.duplicate_of,
+            self.sha256,
+            &self.size.to_string(),
+            &self.email_index.to_string(),
+            self.date,
+            self.from,
+            self.subject,
+            &self
+                .attachment_index
+                .map_or_else(String::new, |i| i.to_string()),
+            self.original_filename,
+        ])
+    }
+}
+
+/// Last manifest line of a run that was cancelled before it finished.
+pub fn cancelled_row() -> String {
+    csv::row(&["", "cancelled", "", "", "", "", "", "", "", "", ""])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields() -> TemplateFields<'static> {
+        TemplateFields {
+            date: "2019-03-07T10:00:00+00:00",
+            sender: "Billing@ACME.com",
+            sender_name: "ACME Billing",
+            subject: "Invoice 3/2019",
+            filename: "../invoice.PDF",
+            index: 42,
+        }
+    }
+
+    #[test]
+    fn renders_the_default_template() {
+        let path = render(DEFAULT_TEMPLATE, &fields()).unwrap();
+        assert_eq!(
+            path,
+            PathBuf::from("2019")
+                .join("acme.com")
+                .join("2019-03-07_.._invoice.PDF")
+        );
+    }
+
+    #[test]
+    fn values_cannot_add_directories() {
+        let path = render("{subject}/{ext}/{index}", &fields()).unwrap();
+        assert_eq!(path, PathBuf::from("Invoice 3_2019").join("pdf").join("42"));
+        let path = render("../{sender_name}/", &fields()).unwrap();
+        assert_eq!(path, PathBuf::from("attachment").join("ACME Billing"));
+    }
+
+    #[test]
+    fn rejects_unknown_or_unclosed_placeholders() {
+        assert!(validate(DEFAULT_TEMPLATE).is_ok());
+        assert!(validate("{year}/{sender_domian}")
+            .unwrap_err()
+            .to_string()
+            .contains("Unknown template placeholder {sender_domian}"));
+        assert!(validate("{year").is_err());
+        assert!(validate("//").is_err());
+    }
+
+    #[test]
+    fn filters_by_extension_or_mime_type() {
+        let pdf = ("Q3.PDF", "application/pdf; name=Q3.PDF");
+        assert!(matches_type(&[], pdf.0, pdf.1));
+        assert!(matches_type(&["pdf".to_string()], pdf.0, pdf.1));
+        assert!(matches_type(&[".pdf".to_string()], pdf.0, pdf.1));
+        assert!(matches_type(&["application/pdf".to_string()], pdf.0, pdf.1));
+        assert!(!matches_type(&["image/*".to_string()], pdf.0, pdf.1));
+        assert!(matches_type(&["image/*".to_string()], "a.png", "image/png"));
+    }
+
+    #[test]
+    fn cancelled_row_has_every_manifest_column() {
+        let row = cancelled_row();
+        assert_eq!(row.split(',').count(), MANIFEST_HEADER.split(',').count());
+        assert_eq!(row.split(',').nth(1), Some("cancelled"));
+    }
+}
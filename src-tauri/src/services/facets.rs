@@ -0,0 +1,225 @@
+//! Facet extraction, filtering and counting for search results.
+//!
+//! Works on `FacetRecord`, a flattened view of the fields each facet
+//! needs, so the logic stays independent of how entries are loaded.
+
+use std::collections::HashMap;
+
+use crate::models::{FacetCount, FacetField, FacetFilter, SearchFacets};
+
+/// Maximum number of values returned for open-ended facets (senders, labels...)
+const TOP_VALUES: usize = 25;
+
+/// The facet-relevant fields of a single message.
+#[derive(Debug, Clone, Default)]
+pub struct FacetRecord {
+    pub sender: String,
+    pub labels: Vec<String>,
+    /// RFC 3339 date, used to derive year and month buckets
+    pub date: String,
+    pub has_attachment: bool,
+    pub attachment_types: Vec<String>,
+}
+
+impl FacetRecord {
+    /// All values this record has for `field` (a message can carry several labels).
+    pub fn values(&self, field: FacetField) -> Vec<String> {
+        match field {
+            FacetField::Sender => vec![self.sender.to_lowercase()],
+            FacetField::SenderDomain => vec![sender_domain(&self.sender)],
+            FacetField::Label => self.labels.clone(),
+            FacetField::Year => self.date.get(..4).map(str::to_string).into_iter().collect(),
+            FacetField::Month => self.date.get(..7).map(str::to_string).into_iter().collect(),
+            FacetField::AttachmentType => self.attachment_types.clone(),
+            FacetField::HasAttachment => vec![self.has_attachment.to_string()],
+        }
+    }
+
+    /// Filters on the same field are OR-ed, different fields are AND-ed.
+    pub fn matches(&self, filters: &[FacetFilter]) -> bool {
+        let mut by_field: HashMap<FacetField, Vec<&str>> = HashMap::new();
+        for filter in filters {
+            by_field
+                .entry(filter.field)
+                .or_default()
+                .push(filter.value.as_str());
+        }
+
+        by_field.into_iter().all(|(field, wanted)| {
+            self.values(field)
+                .iter()
+                .any(|v| wanted.iter().any(|w| v.eq_ignore_ascii_case(w)))
+        })
+    }
+}
+
+/// Count every facet over `records`.
+pub fn compute(records: &[FacetRecord]) -> SearchFacets {
+    let count = |field: FacetField| -> Vec<FacetCount> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for record in records {
+            let mut values = record.values(field);
+            values.sort();
+            values.dedup();
+            for value in values.into_iter().filter(|v| !v.is_empty()) {
+                *counts.entry(value).or_insert(0) += 1;
+            }
+        }
+        counts
+            .into_iter()
+            .map(|(value, count)| FacetCount { value, count })
+            .collect()
+    };
+
+    SearchFacets {
+        senders: top_by_count(count(FacetField::Sender)),
+        sender_domains: top_by_count(count(FacetField::SenderDomain)),
+        labels: top_by_count(count(FacetField::Label)),
+        years: newest_first(count(FacetField::Year)),
+        months: newest_first(count(FacetField::Month)),
+        attachment_types: top_by_count(count(FacetField::AttachmentType)),
+        has_attachment: top_by_count(count(FacetField::HasAttachment)),
+    }
+}
+
+/// Domain part of an address, lowercased (empty if there is none).
+pub fn sender_domain(address: &str) -> String {
+    address
+        .rsplit_once('@')
+        .map(|(_, domain)| domain.trim_end_matches('>').to_lowercase())
+        .unwrap_or_default()
+}
+
+/// MIME type without parameters, lowercased (`application/pdf; name=x` -> `application/pdf`).
+pub fn mime_essence(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+}
+
+fn top_by_count(mut counts: Vec<FacetCount>) -> Vec<FacetCount> {
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    counts.truncate(TOP_VALUES);
+    counts
+}
+
+fn newest_first(mut counts: Vec<FacetCount>) -> Vec<FacetCount> {
+    counts.sort_by(|a, b| b.value.cmp(&a.value));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(sender: &str, labels: &[&str], date: &str, types: &[&str]) -> FacetRecord {
+        FacetRecord {
+            sender: sender.to_string(),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+            date: date.to_string(),
+            has_attachment: !types.is_empty(),
+            attachment_types: types.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    fn filter(field: FacetField, value: &str) -> FacetFilter {
+        FacetFilter {
+            field,
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn sender_domain_is_lowercased_domain_part() {
+        assert_eq!(sender_domain("Billing@Vendor.COM"), "vendor.com");
+        assert_eq!(sender_domain("no-domain"), "");
+    }
+
+    #[test]
+    fn mime_essence_strips_parameters() {
+        assert_eq!(
+            mime_essence("Application/PDF; name=\"a.pdf\""),
+            "application/pdf"
+        );
+    }
+
+    #[test]
+    fn compute_counts_each_facet() {
+        let records = vec![
+            record(
+                "a@x.com",
+                &["Inbox"],
+                "2019-03-01T10:00:00+00:00",
+                &["application/pdf"],
+            ),
+            record(
+                "b@x.com",
+                &["Inbox", "Work"],
+                "2019-04-01T10:00:00+00:00",
+                &[],
+            ),
+            record("a@x.com", &[], "2020-01-01T10:00:00+00:00", &["image/png"]),
+        ];
+
+        let facets = compute(&records);
+
+        assert_eq!(
+            facets.senders[0],
+            FacetCount {
+                value: "a@x.com".into(),
+                count: 2
+            }
+        );
+        assert_eq!(
+            facets.sender_domains,
+            vec![FacetCount {
+                value: "x.com".into(),
+                count: 3
+            }]
+        );
+        assert_eq!(
+            facets.labels[0],
+            FacetCount {
+                value: "Inbox".into(),
+                count: 2
+            }
+        );
+        assert_eq!(facets.years[0].value, "2020");
+        assert_eq!(facets.months.len(), 3);
+        assert_eq!(facets.attachment_types.len(), 2);
+        assert_eq!(
+            facets.has_attachment[0],
+            FacetCount {
+                value: "true".into(),
+                count: 2
+            }
+        );
+    }
+
+    #[test]
+    fn matches_ors_within_field_and_ands_across_fields() {
+        let r = record(
+            "a@x.com",
+            &["Work"],
+            "2019-03-01T10:00:00+00:00",
+            &["application/pdf"],
+        );
+
+        assert!(r.matches(&[]));
+        assert!(r.matches(&[
+            filter(FacetField::Year, "2018"),
+            filter(FacetField::Year, "2019"),
+        ]));
+        assert!(r.matches(&[
+            filter(FacetField::SenderDomain, "X.com"),
+            filter(FacetField::AttachmentType, "application/pdf"),
+        ]));
+        assert!(!r.matches(&[
+            filter(FacetField::Label, "Work"),
+            filter(FacetField::HasAttachment, "false"),
+        ]));
+    }
+}
@@ -0,0 +1,215 @@
+//! Raw header access for headers mboxshell does not index.
+//!
+//! Reads each message's header block straight from the MBOX file using the
+//! entry offset, so no body decoding is needed.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use mboxshell::model::mail::MailEntry;
+
+use crate::error::AppError;
+
+/// Header blocks larger than this are truncated.
+const MAX_HEADER_BYTES: u64 = 256 * 1024;
+
+/// Headers set by bulk mailers and marketing platforms.
+const BULK_MARKERS: &[&str] = &[
+    "feedback-id",
+    "x-campaign",
+    "x-campaignid",
+    "x-mailchimp-campaign",
+    "x-mc-user",
+    "x-sg-eid",
+    "x-mailgun-tag",
+    "x-marketo-id",
+];
+
+/// The subset of headers the viewer derives features from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MessageHeaders {
+    pub message_id: Option<String>,
+    pub in_reply_to: Vec<String>,
+    pub references: Vec<String>,
+    /// `List-Id` without the display name, lowercased
+    pub list_id: Option<String>,
+    /// Display name from `List-Id` ("Rust Users" in `Rust Users <users.rust-lang.org>`)
+    pub list_name: Option<String>,
+    /// First URL of `List-Post`, `List-Unsubscribe` and `List-Archive`
+    pub list_post: Option<String>,
+    pub list_unsubscribe: Option<String>,
+    pub list_archive: Option<String>,
+    /// `Precedence` value, lowercased (`bulk`, `list`, `junk`...)
+    pub precedence: Option<String>,
+    /// `Auto-Submitted` value, lowercased; `no` is treated as absent
+    pub auto_submitted: Option<String>,
+    /// Any of `BULK_MARKERS` is present
+    pub bulk_mailer: bool,
+}
+
+impl MessageHeaders {
+    /// Parse a raw header block (an mbox `From ` separator line is ignored).
+    pub fn parse(block: &str) -> Self {
+        let mut headers = Self::default();
+        for (name, value) in unfold(block) {
+            match name.to_ascii_lowercase().as_str() {
+                "message-id" => headers.message_id = message_ids(&value).into_iter().next(),
+                "in-reply-to" => headers.in_reply_to = message_ids(&value),
+                "references" => headers.references = message_ids(&value),
+                "list-id" => {
+                    headers.list_id = message_ids(&value).into_iter().next();
+                    headers.list_name = value
+                        .split_once('<')
+                        .map(|(name, _)| name.trim().trim_matches('"').to_string())
+                        .filter(|name| !name.is_empty());
+                }
+                "list-post" => headers.list_post = first_url(&value),
+                "list-unsubscribe" => headers.list_unsubscribe = first_url(&value),
+                "list-archive" => headers.list_archive = first_url(&value),
+                "precedence" => headers.precedence = Some(value.to_lowercase()),
+                "auto-submitted" if !value.eq_ignore_ascii_case("no") => {
+                    headers.auto_submitted = Some(value.to_lowercase())
+                }
+                name if BULK_MARKERS.contains(&name) => headers.bulk_mailer = true,
+                _ => {}
+            }
+        }
+        headers
+    }
+}
+
+/// Read and parse the header block of every entry.
+pub fn read_all(path: &Path, entries: &[MailEntry]) -> Result<Vec<MessageHeaders>, AppError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    entries
+        .iter()
+        .map(|entry| {
+            let block = read_block(&mut reader, entry.offset, entry.length)?;
+            Ok(MessageHeaders::parse(&block))
+        })
+        .collect()
+}
+
+/// Read the raw header block of one message (up to the first blank line).
+pub fn read_block<R: BufRead + Seek>(
+    reader: &mut R,
+    offset: u64,
+    length: u64,
+) -> Result<String, AppError> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut limited = reader.take(length.min(MAX_HEADER_BYTES));
+    let mut block = Vec::new();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if limited.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        if line.iter().all(|b| b.is_ascii_whitespace()) {
+            break;
+        }
+        block.extend_from_slice(&line);
+    }
+    Ok(String::from_utf8_lossy(&block).into_owned())
+}
+
+/// Split a header block into (name, value) pairs, joining folded lines.
+pub fn unfold(block: &str) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in block.lines() {
+        if line.starts_with("From ") && headers.is_empty() {
+            continue;
+        }
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    headers
+}
+
+/// First `<...>` URL of a List-* header (`NO` and empty values yield `None`).
+pub fn first_url(value: &str) -> Option<String> {
+    let url = match value.split_once('<') {
+        Some((_, rest)) => rest.split_once('>').map_or(rest, |(url, _)| url),
+        None => value,
+    };
+    let url = url.trim();
+    (!url.is_empty() && !url.eq_ignore_ascii_case("no")).then(|| url.to_string())
+}
+
+/// Extract `<...>` message ids, lowercased, from a header value.
+pub fn message_ids(value: &str) -> Vec<String> {
+    let ids: Vec<String> = value
+        .split('<')
+        .skip(1)
+        .filter_map(|part| part.split_once('>'))
+        .map(|(id, _)| id.trim().to_lowercase())
+        .filter(|id| !id.is_empty())
+        .collect();
+    if ids.is_empty() && !value.trim().is_empty() && !value.contains(char::is_whitespace) {
+        // Some mailers omit the angle brackets
+        return vec![value.trim().to_lowercase()];
+    }
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn unfold_joins_continuation_lines_and_skips_separator() {
+        let block = "From someone@example.com Mon Jan  1 00:00:00 2020\nSubject: Long\n  subject line\nX-A: 1\n";
+        assert_eq!(
+            unfold(block),
+            vec![
+                ("Subject".to_string(), "Long subject line".to_string()),
+                ("X-A".to_string(), "1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_reads_threading_headers() {
+        let block = "Message-ID: <A@x>\nIn-Reply-To: <b@x>\nReferences: <c@x>\n <b@x>\n";
+        let headers = MessageHeaders::parse(block);
+        assert_eq!(headers.message_id.as_deref(), Some("a@x"));
+        assert_eq!(headers.in_reply_to, vec!["b@x"]);
+        assert_eq!(headers.references, vec!["c@x", "b@x"]);
+    }
+
+    #[test]
+    fn parse_reads_list_and_bulk_headers() {
+        let block = "List-Id: \"Rust Users\" <Users.Rust-Lang.org>\nList-Unsubscribe: <https://x/U?a=1>, <mailto:u@x>\nList-Post: NO\nList-Archive: <https://x/archive>\nPrecedence: Bulk\nAuto-Submitted: no\nFeedback-ID: 1:2:3\n";
+        let headers = MessageHeaders::parse(block);
+        assert_eq!(headers.list_id.as_deref(), Some("users.rust-lang.org"));
+        assert_eq!(headers.list_name.as_deref(), Some("Rust Users"));
+        assert_eq!(headers.list_unsubscribe.as_deref(), Some("https://x/U?a=1"));
+        assert_eq!(headers.list_post, None);
+        assert_eq!(headers.list_archive.as_deref(), Some("https://x/archive"));
+        assert_eq!(headers.precedence.as_deref(), Some("bulk"));
+        assert_eq!(headers.auto_submitted, None);
+        assert!(headers.bulk_mailer);
+    }
+
+    #[test]
+    fn message_ids_accepts_bare_id() {
+        assert_eq!(message_ids("abc@x"), vec!["abc@x"]);
+        assert!(message_ids("").is_empty());
+    }
+
+    #[test]
+    fn read_block_stops_at_blank_line() {
+        let raw = b"junk\nFrom a@b Mon\nSubject: Hi\r\n\r\nBody text\n";
+        let mut reader = Cursor::new(&raw[..]);
+        let block = read_block(&mut reader, 5, (raw.len() - 5) as u64).unwrap();
+        assert_eq!(block, "From a@b Mon\nSubject: Hi\r\n");
+    }
+}
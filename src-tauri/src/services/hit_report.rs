@@ -0,0 +1,117 @@
+//! Search-term hit reports for review workflows.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{HitReport, TermHits};
+
+use super::csv;
+
+/// Build a report from each term's matching message indices.
+///
+/// `thread_ids[i]` is the thread of message `i`.
+pub fn build(
+    term_hits: &[(String, Vec<usize>)],
+    thread_ids: &[usize],
+    messages_searched: usize,
+) -> HitReport {
+    let mut hit_counts: HashMap<usize, usize> = HashMap::new();
+    for (_, indices) in term_hits {
+        for &i in indices {
+            *hit_counts.entry(i).or_insert(0) += 1;
+        }
+    }
+
+    let threads_of = |indices: &mut dyn Iterator<Item = usize>| {
+        indices.map(|i| thread_ids[i]).collect::<HashSet<_>>().len()
+    };
+
+    let terms = term_hits
+        .iter()
+        .map(|(term, indices)| TermHits {
+            term: term.clone(),
+            messages: indices.len(),
+            threads: threads_of(&mut indices.iter().copied()),
+            unique_messages: indices.iter().filter(|i| hit_counts[i] == 1).count(),
+        })
+        .collect();
+
+    HitReport {
+        terms,
+        total_messages: hit_counts.len(),
+        total_threads: threads_of(&mut hit_counts.keys().copied()),
+        messages_searched,
+    }
+}
+
+/// Render a report as CSV, with a final deduplicated total row.
+pub fn to_csv(report: &HitReport) -> String {
+    let mut out = csv::row(&["term", "messages", "threads", "unique_messages"]);
+    out.push('\n');
+    for t in &report.terms {
+        out.push_str(&csv::row(&[
+            t.term.clone(),
+            t.messages.to_string(),
+            t.threads.to_string(),
+            t.unique_messages.to_string(),
+        ]));
+        out.push('\n');
+    }
+    out.push_str(&csv::row(&[
+        "TOTAL (deduplicated)".to_string(),
+        report.total_messages.to_string(),
+        report.total_threads.to_string(),
+        String::new(),
+    ]));
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> HitReport {
+        // Messages 0 and 1 share a thread; message 3 is hit by both terms
+        let threads = vec![0, 0, 1, 2, 3];
+        build(
+            &[
+                ("invoice".to_string(), vec![0, 1, 3]),
+                ("\"wire transfer\"".to_string(), vec![3, 4]),
+            ],
+            &threads,
+            5,
+        )
+    }
+
+    #[test]
+    fn counts_messages_threads_and_unique_hits_per_term() {
+        let report = sample();
+        assert_eq!(
+            report.terms[0],
+            TermHits {
+                term: "invoice".to_string(),
+                messages: 3,
+                threads: 2,
+                unique_messages: 2,
+            }
+        );
+        assert_eq!(report.terms[1].unique_messages, 1);
+    }
+
+    #[test]
+    fn totals_are_deduplicated() {
+        let report = sample();
+        assert_eq!(report.total_messages, 4);
+        assert_eq!(report.total_threads, 3);
+        assert_eq!(report.messages_searched, 5);
+    }
+
+    #[test]
+    fn csv_has_header_rows_and_total() {
+        let csv = to_csv(&sample());
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "term,messages,threads,unique_messages");
+        assert_eq!(lines[2], "\"\"\"wire transfer\"\"\",2,2,1");
+        assert_eq!(lines[3], "TOTAL (deduplicated),4,3,");
+    }
+}
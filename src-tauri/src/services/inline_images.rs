@@ -0,0 +1,61 @@
+//! Inline (`cid:`) images served through the `mbox` URI scheme.
+//!
+//! Sanitized HTML keeps `cid:` references; they are rewritten to scheme URLs
+//! so the webview asks the backend for the part.
+
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+use sha2::{Digest, Sha256};
+
+use super::scheme;
+
+/// `cid:` references in sanitized markup: `src` attributes and CSS `url()`s
+/// (ammonia serializes the latter with `&quot;` quotes).
+static CID_REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)(src="|url\(&quot;)cid:([^"&]+)"#).unwrap());
+
+/// A decoded message part ready to be served.
+#[derive(Debug, Clone)]
+pub struct InlinePart {
+    pub data: Vec<u8>,
+    pub content_type: String,
+}
+
+impl InlinePart {
+    /// Strong validator for conditional requests.
+    pub fn etag(&self) -> String {
+        let digest = Sha256::digest(&self.data);
+        let hex: String = digest[..16].iter().map(|b| format!("{b:02x}")).collect();
+        format!("\"{hex}\"")
+    }
+}
+
+/// Point the `cid:` references of a message's sanitized HTML at the scheme.
+pub fn rewrite(html: &str, index: usize) -> String {
+    CID_REFERENCE
+        .replace_all(html, |caps: &Captures| {
+            format!("{}{}", &caps[1], scheme::inline_part_url(index, &caps[2]))
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrites_src_and_css_references() {
+        let html = r#"<img src="cid:logo@example"><td style="background-image:url(&quot;cid:bg 1&quot;)">"#;
+        let rewritten = rewrite(html, 7);
+        assert!(rewritten.contains(&format!(
+            r#"src="{}""#,
+            scheme::inline_part_url(7, "logo@example")
+        )));
+        assert!(rewritten.contains(&format!(
+            "url(&quot;{}&quot;)",
+            scheme::inline_part_url(7, "bg 1")
+        )));
+        assert!(rewritten.ends_with("/message/7/cid/bg%201&quot;)\">"));
+    }
+}
@@ -0,0 +1,109 @@
+//! Offline language detection of message text.
+//!
+//! Languages are reported as ISO 639-1 codes (`en`, `es`...) where one
+//! exists, falling back to whatlang's ISO 639-3 code otherwise.
+
+use whatlang::Lang;
+
+/// Detections below this confidence are treated as unknown.
+const MIN_CONFIDENCE: f64 = 0.3;
+
+/// Only the start of a message is needed to tell its language.
+const MAX_CHARS: usize = 2000;
+
+const ISO_639_1: &[(Lang, &str)] = &[
+    (Lang::Eng, "en"),
+    (Lang::Spa, "es"),
+    (Lang::Deu, "de"),
+    (Lang::Fra, "fr"),
+    (Lang::Ita, "it"),
+    (Lang::Por, "pt"),
+    (Lang::Nld, "nl"),
+    (Lang::Rus, "ru"),
+    (Lang::Ukr, "uk"),
+    (Lang::Pol, "pl"),
+    (Lang::Ces, "cs"),
+    (Lang::Swe, "sv"),
+    (Lang::Dan, "da"),
+    (Lang::Nob, "nb"),
+    (Lang::Fin, "fi"),
+    (Lang::Tur, "tr"),
+    (Lang::Ell, "el"),
+    (Lang::Heb, "he"),
+    (Lang::Ara, "ar"),
+    (Lang::Hin, "hi"),
+    (Lang::Cmn, "zh"),
+    (Lang::Jpn, "ja"),
+    (Lang::Kor, "ko"),
+];
+
+/// Short code of a whatlang language.
+pub fn code(lang: Lang) -> &'static str {
+    ISO_639_1
+        .iter()
+        .find(|(l, _)| *l == lang)
+        .map_or_else(|| lang.code(), |(_, c)| c)
+}
+
+/// Primary language of a text, if it can be told with some confidence.
+pub fn detect(text: &str) -> Option<&'static str> {
+    let sample: String = text.chars().take(MAX_CHARS).collect();
+    let info = whatlang::detect(&sample)?;
+    (info.confidence() >= MIN_CONFIDENCE).then(|| code(info.lang()))
+}
+
+/// Resolve user input (`es`, `spa` or `spanish`) to the code used in results.
+pub fn parse(value: &str) -> Option<&'static str> {
+    let value = value.trim().to_lowercase();
+    Lang::all()
+        .iter()
+        .find(|&&lang| {
+            code(lang) == value || lang.code() == value || lang.eng_name().to_lowercase() == value
+        })
+        .map(|&lang| code(lang))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_the_archive_languages() {
+        let samples = [
+            (
+                "en",
+                "Please find attached the quarterly report for the board meeting next week.",
+            ),
+            (
+                "es",
+                "Adjunto el informe trimestral para la reunión del consejo de la próxima semana.",
+            ),
+            (
+                "de",
+                "Anbei finden Sie den Quartalsbericht für die Vorstandssitzung nächste Woche.",
+            ),
+            (
+                "fr",
+                "Veuillez trouver ci-joint le rapport trimestriel pour la réunion du conseil.",
+            ),
+        ];
+        for (expected, text) in samples {
+            assert_eq!(detect(text), Some(expected), "{text}");
+        }
+    }
+
+    #[test]
+    fn empty_text_has_no_language() {
+        assert_eq!(detect(""), None);
+        assert_eq!(detect("12345 !!!"), None);
+    }
+
+    #[test]
+    fn parse_accepts_short_long_and_english_names() {
+        assert_eq!(parse("es"), Some("es"));
+        assert_eq!(parse("SPA"), Some("es"));
+        assert_eq!(parse("German"), Some("de"));
+        assert_eq!(parse("epo"), Some("epo"));
+        assert_eq!(parse("klingon"), None);
+    }
+}
@@ -0,0 +1,127 @@
+//! Grouping messages by mailing list.
+
+use std::collections::HashMap;
+
+use super::headers::MessageHeaders;
+
+/// Messages of one list; details come from the first message that has them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListGroup {
+    pub list_id: String,
+    pub name: Option<String>,
+    pub post: Option<String>,
+    pub unsubscribe: Option<String>,
+    pub archive: Option<String>,
+    pub members: Vec<usize>,
+}
+
+/// Group `indices` by `List-Id`, largest list first.
+///
+/// Members are listed newest first by `timestamp`, and list details come
+/// from the latest message that carries them.
+pub fn group(
+    headers: &[MessageHeaders],
+    indices: impl IntoIterator<Item = usize>,
+    timestamp: impl Fn(usize) -> i64,
+) -> Vec<ListGroup> {
+    let mut indices: Vec<usize> = indices.into_iter().collect();
+    indices.sort_by_key(|&i| (std::cmp::Reverse(timestamp(i)), i));
+
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+    let mut groups: Vec<ListGroup> = Vec::new();
+    for i in indices {
+        let h = &headers[i];
+        let Some(list_id) = h.list_id.as_deref() else {
+            continue;
+        };
+        let position = *positions.entry(list_id).or_insert_with(|| {
+            groups.push(ListGroup {
+                list_id: list_id.to_string(),
+                ..Default::default()
+            });
+            groups.len() - 1
+        });
+        let group = &mut groups[position];
+        group.name = group.name.take().or_else(|| h.list_name.clone());
+        group.post = group.post.take().or_else(|| h.list_post.clone());
+        group.unsubscribe = group
+            .unsubscribe
+            .take()
+            .or_else(|| h.list_unsubscribe.clone());
+        group.archive = group.archive.take().or_else(|| h.list_archive.clone());
+        group.members.push(i);
+    }
+    groups.sort_by(|a, b| {
+        b.members
+            .len()
+            .cmp(&a.members.len())
+            .then(a.list_id.cmp(&b.list_id))
+    });
+    groups
+}
+
+/// Whether a message's list matches a `list:` search value: the full id,
+/// or any dot-separated suffix/prefix of it (`rust-lang.org`, `users`).
+pub fn matches(list_id: &str, value: &str) -> bool {
+    let value = value.trim().to_lowercase();
+    !value.is_empty()
+        && (list_id == value
+            || list_id.ends_with(&format!(".{value}"))
+            || list_id.starts_with(&format!("{value}.")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(id: &str, name: Option<&str>, post: Option<&str>) -> MessageHeaders {
+        MessageHeaders {
+            list_id: Some(id.to_string()),
+            list_name: name.map(str::to_string),
+            list_post: post.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn groups_by_list_id_keeping_first_details() {
+        let headers = vec![
+            list("users.rust-lang.org", None, Some("mailto:new@x")),
+            MessageHeaders::default(),
+            list("dev.example.org", Some("Dev"), None),
+            list(
+                "users.rust-lang.org",
+                Some("Rust Users"),
+                Some("mailto:old@x"),
+            ),
+        ];
+        let groups = group(&headers, 0..headers.len(), |_| 0);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].list_id, "users.rust-lang.org");
+        assert_eq!(groups[0].members, vec![0, 3]);
+        assert_eq!(groups[0].name.as_deref(), Some("Rust Users"));
+        assert_eq!(groups[0].post.as_deref(), Some("mailto:new@x"));
+        assert_eq!(groups[1].members, vec![2]);
+    }
+
+    #[test]
+    fn details_come_from_the_newest_message() {
+        let headers = vec![
+            list("users.rust-lang.org", None, Some("mailto:old@x")),
+            list("users.rust-lang.org", None, Some("mailto:new@x")),
+        ];
+        let dates = [100, 200];
+        let groups = group(&headers, 0..headers.len(), |i| dates[i]);
+        assert_eq!(groups[0].post.as_deref(), Some("mailto:new@x"));
+        assert_eq!(groups[0].members, vec![1, 0]);
+    }
+
+    #[test]
+    fn list_operator_matches_whole_labels() {
+        assert!(matches("users.rust-lang.org", "users.rust-lang.org"));
+        assert!(matches("users.rust-lang.org", "Rust-Lang.org"));
+        assert!(matches("users.rust-lang.org", "users"));
+        assert!(!matches("users.rust-lang.org", "lang.org"));
+        assert!(!matches("users.rust-lang.org", ""));
+    }
+}
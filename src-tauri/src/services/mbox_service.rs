@@ -3,8 +3,9 @@
 //! This module contains zero Tauri dependencies — all business logic
 //! is pure Rust, testable without a Tauri runtime.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
 
 use mboxshell::index::builder::build_index;
 use mboxshell::model::mail::MailEntry;
@@ -12,7 +13,266 @@ use mboxshell::search;
 use mboxshell::store::reader::MboxStore;
 
 use crate::error::AppError;
-use crate::models::{EmailBody, EmailEntry, LabelCount, MboxStats, SearchResults};
+use crate::models::{
+    ArchiveListing, AttachmentCatalog, AttachmentCopies, AttachmentInfo, CatalogAttachment,
+    CatalogQuery, ConflictPolicy, DuplicateCollapse, DuplicateCopy, DuplicateGroup, DuplicateKind,
+    DuplicateReport, EmailBody, EmailEntry, ExtractionProgress, ExtractionRequest,
+    ExtractionSummary, FacetFilter, FacetedSearchResults, HitReport, LabelCount, LabelKind,
+    LabelRule, LanguageCount, MailCategory, MailingList, MatchField, MatchedText, MboxStats,
+    MessageMatches, PatternSearch, SavedAttachment, SavedSearch, SearchMode, SearchResults,
+    SimilarMessage, Summary, SummarySentence, TopicCluster,
+};
+
+use super::archive;
+use super::attachment_files;
+use super::attachment_text::{self, AttachmentText, AttachmentTextIndex, MAX_EXTRACT_BYTES};
+use super::body_text;
+use super::cancel::CancelToken;
+use super::catalog::{self, CatalogRecord};
+use super::categories;
+use super::clustering::{self, Cluster};
+use super::duplicates::{self, DuplicateGroups, DuplicateInput, Fingerprint};
+use super::extraction::{self, ManifestRow, TemplateFields};
+use super::facets::{self, FacetRecord};
+use super::headers::{self, MessageHeaders};
+use super::hit_report;
+use super::inline_images::{self, InlinePart};
+use super::language;
+use super::mailing_lists;
+use super::mime;
+use super::pattern_search::{Matcher, MAX_MATCHES_PER_FIELD};
+use super::query::{self, QueryFilter};
+use super::quoting;
+use super::rfc822;
+use super::rules::{CompiledRule, RuleInput, RuleStore};
+use super::sanitize;
+use super::saved_searches::SavedSearchStore;
+use super::scheme;
+use super::spool::{Spool, SpooledAttachment};
+use super::summarize;
+use super::tfidf::TfIdfIndex;
+use super::threads;
+use super::tnef::{self, TnefAttachment, TnefMessage};
+
+/// Language code reported for messages whose language could not be told.
+const UNKNOWN_LANGUAGE: &str = "und";
+
+/// Sentences considered per summary; TextRank compares every pair.
+const MAX_SUMMARY_SENTENCES: usize = 2000;
+
+const SUMMARY_KEYWORDS: usize = 8;
+
+/// Attachments larger than this are not sent as IPC byte arrays; the webview
+/// loads them from their `mbox` scheme URL instead.
+pub const MAX_IPC_ATTACHMENT_BYTES: u64 = 10 * 1024 * 1024;
+
+/// A viewer-side label computed over the mailbox rather than read from it.
+pub(crate) struct VirtualLabel {
+    pub(crate) name: String,
+    pub(crate) kind: LabelKind,
+    pub(crate) indices: Vec<usize>,
+}
+
+/// One message of the open mailbox, for decoding its parts through a store
+/// of its own instead of under the service lock.
+pub struct MessageSource {
+    mbox_path: PathBuf,
+    entry: MailEntry,
+    spool_dir: PathBuf,
+}
+
+impl MessageSource {
+    /// Decode an attachment into the spool (once) so it can be served in ranges.
+    ///
+    /// `nested` addresses an attachment of a message embedded in the part.
+    pub fn spool_attachment(
+        &self,
+        attachment_index: usize,
+        nested: &[usize],
+    ) -> Result<SpooledAttachment, AppError> {
+        let spool = Spool::new(&self.spool_dir);
+        let mut store =
+            MboxStore::open(&self.mbox_path).map_err(|e| AppError::MboxShell(e.to_string()))?;
+        let entry = &self.entry;
+        let body = store
+            .get_message(entry)
+            .map_err(|e| AppError::MboxShell(e.to_string()))?;
+        let key = Spool::key(&self.mbox_path, entry.offset, attachment_index, nested);
+        let meta = match body.attachments.get(attachment_index).cloned() {
+            Some(meta) if nested.is_empty() => meta,
+            _ => {
+                let (filename, content_type, data) = if nested.is_empty() {
+                    let file = unpacked_attachment(&mut store, entry, attachment_index)?;
+                    (file.filename, file.content_type, file.data)
+                } else {
+                    let part = embedded_attachment(&mut store, entry, attachment_index, nested)?;
+                    (part.filename, part.content_type, part.data)
+                };
+                let path = spool.get_or_write(&key, || Ok(data))?;
+                return Ok(SpooledAttachment {
+                    size: std::fs::metadata(&path)?.len(),
+                    path,
+                    filename,
+                    content_type,
+                });
+            }
+        };
+        let path = spool.get_or_write(&key, || {
+            store
+                .get_attachment(entry, &meta)
+                .map_err(|e| AppError::MboxShell(e.to_string()))
+        })?;
+        Ok(SpooledAttachment {
+            size: std::fs::metadata(&path)?.len(),
+            path,
+            filename: meta.filename,
+            content_type: meta.content_type,
+        })
+    }
+
+    /// Write an attachment to `destination`, a file path or a directory.
+    ///
+    /// The part is decoded into the spool first and then copied from there.
+    /// mboxshell decodes a part in memory in one go, so the decode still
+    /// holds it once; the copy to `destination` streams from the spool.
+    pub fn save_attachment(
+        &self,
+        attachment_index: usize,
+        nested: &[usize],
+        destination: &Path,
+        policy: ConflictPolicy,
+    ) -> Result<SavedAttachment, AppError> {
+        let spooled = self.spool_attachment(attachment_index, nested)?;
+        attachment_files::save(&spooled, destination, policy)
+    }
+
+    /// Decode an attachment for sending over IPC.
+    pub fn get_attachment(
+        &self,
+        attachment_index: usize,
+        nested: &[usize],
+    ) -> Result<Vec<u8>, AppError> {
+        let (_, data) = self.load_attachment(
+            attachment_index,
+            nested,
+            MAX_IPC_ATTACHMENT_BYTES,
+            |size| {
+                AppError::Validation(format!(
+                    "Attachment is too large to transfer directly ({size} bytes); load it from its URL"
+                ))
+            },
+        )?;
+        Ok(data)
+    }
+
+    /// List the entries of an archive attachment, or of an archive nested in
+    /// it by following `path`.
+    pub fn list_archive(
+        &self,
+        attachment_index: usize,
+        path: &[String],
+    ) -> Result<ArchiveListing, AppError> {
+        let (filename, data) = self.load_archive(attachment_index)?;
+        let (name, data) = archive::open_path(&filename, data, path)?;
+        archive::list(&name, &data)
+    }
+
+    /// Write a single file of an archive attachment to `destination`, a file
+    /// path or a directory; `path` names one entry per nesting level.
+    ///
+    /// The entry goes through the spool like an attachment, so it is never
+    /// sent over IPC.
+    pub fn extract_archive_entry(
+        &self,
+        attachment_index: usize,
+        path: &[String],
+        destination: &Path,
+        policy: ConflictPolicy,
+    ) -> Result<SavedAttachment, AppError> {
+        let Some(last) = path.last() else {
+            return Err(AppError::Validation(
+                "No archive entry was selected".to_string(),
+            ));
+        };
+        let part = Spool::key(&self.mbox_path, self.entry.offset, attachment_index, &[]);
+        let key = Spool::archive_entry_key(&part, path);
+        let spool_path = Spool::new(&self.spool_dir).get_or_write(&key, || {
+            let (filename, data) = self.load_archive(attachment_index)?;
+            Ok(archive::open_path(&filename, data, path)?.1)
+        })?;
+        let spooled = SpooledAttachment {
+            size: std::fs::metadata(&spool_path)?.len(),
+            path: spool_path,
+            filename: last.rsplit(['/', '\\']).next().unwrap_or(last).to_string(),
+            content_type: "application/octet-stream".to_string(),
+        };
+        attachment_files::save(&spooled, destination, policy)
+    }
+
+    fn load_archive(&self, attachment_index: usize) -> Result<(String, Vec<u8>), AppError> {
+        self.load_attachment(attachment_index, &[], archive::MAX_ENTRY_BYTES, |size| {
+            AppError::Validation(format!("Archive is too large to open ({size} bytes)"))
+        })
+    }
+
+    /// Decode an attachment's name and content, refusing anything over `limit`.
+    fn load_attachment(
+        &self,
+        attachment_index: usize,
+        nested: &[usize],
+        limit: u64,
+        too_large: impl Fn(u64) -> AppError,
+    ) -> Result<(String, Vec<u8>), AppError> {
+        let mut store =
+            MboxStore::open(&self.mbox_path).map_err(|e| AppError::MboxShell(e.to_string()))?;
+        let entry = &self.entry;
+        let body = store
+            .get_message(entry)
+            .map_err(|e| AppError::MboxShell(e.to_string()))?;
+
+        let meta = match body.attachments.get(attachment_index).cloned() {
+            Some(meta) if nested.is_empty() => meta,
+            _ => {
+                let (filename, data) = if nested.is_empty() {
+                    let file = unpacked_attachment(&mut store, entry, attachment_index)?;
+                    (file.filename, file.data)
+                } else {
+                    let part = embedded_attachment(&mut store, entry, attachment_index, nested)?;
+                    (part.filename, part.data)
+                };
+                if data.len() as u64 > limit {
+                    return Err(too_large(data.len() as u64));
+                }
+                return Ok((filename, data));
+            }
+        };
+        if meta.size > limit {
+            return Err(too_large(meta.size));
+        }
+
+        let data = store
+            .get_attachment(entry, &meta)
+            .map_err(|e| AppError::MboxShell(e.to_string()))?;
+        Ok((meta.filename, data))
+    }
+}
+
+/// Rule labels evaluated for one mailbox and rule set, ready to swap in.
+pub struct RuleLabels {
+    mbox_path: Option<PathBuf>,
+    rules: Vec<LabelRule>,
+    matches: HashMap<usize, Vec<String>>,
+    labels: Vec<VirtualLabel>,
+}
+
+/// Smart folders evaluated for one mailbox, saved search set and collapse
+/// mask, ready to swap in.
+pub struct SavedSearchLabels {
+    mbox_path: Option<PathBuf>,
+    searches: Vec<SavedSearch>,
+    hidden: Arc<Vec<bool>>,
+    labels: Vec<VirtualLabel>,
+}
 
 /// Manages the state and operations for an opened MBOX file.
 ///
@@ -22,6 +282,28 @@ pub struct MboxService {
     pub(crate) mbox_path: Option<PathBuf>,
     pub(crate) entries: Vec<MailEntry>,
     pub(crate) store: Option<MboxStore>,
+    pub(crate) data_dir: Option<PathBuf>,
+    pub(crate) saved_searches: Vec<SavedSearch>,
+    /// User-defined labelling rules (per user, not per mailbox)
+    pub(crate) rules: Vec<LabelRule>,
+    pub(crate) virtual_labels: Vec<VirtualLabel>,
+    /// Rule labels per message index, shared with snapshots for listings
+    pub(crate) rule_matches: Arc<HashMap<usize, Vec<String>>>,
+    /// Extracted attachment text, built on first use and shared with search snapshots
+    pub(crate) attachment_text: Arc<OnceLock<AttachmentTextIndex>>,
+    /// Every attachment with its hash, built on first use and shared with snapshots
+    pub(crate) attachment_catalog: Arc<OnceLock<Vec<CatalogRecord>>>,
+    /// Raw headers not indexed by mboxshell, read on first use
+    pub(crate) headers: Arc<OnceLock<Vec<MessageHeaders>>>,
+    /// TF-IDF signatures of each body without quotes, built on first use
+    pub(crate) tfidf: Arc<OnceLock<TfIdfIndex>>,
+    /// Exact and near-duplicate groups, detected on first use
+    pub(crate) duplicates: Arc<OnceLock<DuplicateGroups>>,
+    /// Detected language code per message, computed on first use
+    pub(crate) languages: Arc<OnceLock<Vec<Option<&'static str>>>>,
+    pub(crate) collapse: DuplicateCollapse,
+    /// `hidden[i]` is true when message `i` is a collapsed duplicate (empty when off)
+    pub(crate) hidden: Arc<Vec<bool>>,
 }
 
 impl MboxService {
@@ -30,9 +312,28 @@ impl MboxService {
             mbox_path: None,
             entries: Vec::new(),
             store: None,
+            data_dir: None,
+            saved_searches: Vec::new(),
+            rules: Vec::new(),
+            virtual_labels: Vec::new(),
+            rule_matches: Arc::default(),
+            attachment_text: Arc::default(),
+            attachment_catalog: Arc::default(),
+            headers: Arc::default(),
+            tfidf: Arc::default(),
+            duplicates: Arc::default(),
+            languages: Arc::default(),
+            collapse: DuplicateCollapse::Off,
+            hidden: Arc::default(),
         }
     }
 
+    /// Set the directory where per-mailbox sidecar data is persisted.
+    pub fn set_data_dir(&mut self, dir: PathBuf) {
+        self.rules = RuleStore::new(&dir).load().unwrap_or_default();
+        self.data_dir = Some(dir);
+    }
+
     pub fn open(&mut self, path: &Path) -> Result<MboxStats, AppError> {
         if !path.exists() {
             return Err(AppError::NotFound(path.display().to_string()));
@@ -51,24 +352,33 @@ impl MboxService {
 
         let store = MboxStore::open(path).map_err(|e| AppError::MboxShell(e.to_string()))?;
 
-        let total_messages = entries.len();
-        let total_with_attachments = entries.iter().filter(|e| e.has_attachments).count();
-
         self.entries = entries;
         self.store = Some(store);
         self.mbox_path = Some(path.to_path_buf());
+        self.virtual_labels = Vec::new();
+        self.attachment_text = Arc::default();
+        self.attachment_catalog = Arc::default();
+        self.headers = Arc::default();
+        self.tfidf = Arc::default();
+        self.duplicates = Arc::default();
+        self.languages = Arc::default();
+        self.collapse = DuplicateCollapse::Off;
+        self.hidden = Arc::default();
+        // Spooled parts are only reused within one session of a mailbox
+        let _ = Spool::new(self.spool_dir()).clear();
 
-        let labels = self.count_labels();
+        // A corrupt sidecar must not prevent opening the mailbox itself
+        self.saved_searches = self
+            .saved_search_store()
+            .and_then(|store| store.load(path))
+            .unwrap_or_default();
 
-        Ok(MboxStats {
-            total_messages,
-            total_with_attachments,
-            labels,
-        })
+        Ok(self.stats())
     }
 
+    /// Number of emails, excluding collapsed duplicates.
     pub fn get_email_count(&self) -> usize {
-        self.entries.len()
+        self.entries.len() - self.hidden_count()
     }
 
     pub fn get_emails(&self, offset: usize, limit: usize) -> Result<Vec<EmailEntry>, AppError> {
@@ -78,16 +388,29 @@ impl MboxService {
             ));
         }
 
-        let end = (offset + limit).min(self.entries.len());
-        let result = self.entries[offset..end]
-            .iter()
-            .map(EmailEntry::from)
+        if self.hidden.is_empty() {
+            let end = (offset + limit).min(self.entries.len());
+            let result = (offset..end).map(|i| self.email_entry(i)).collect();
+            return Ok(result);
+        }
+
+        let result = self
+            .visible_indices()
+            .skip(offset)
+            .take(limit)
+            .map(|i| self.email_entry(i))
             .collect();
 
         Ok(result)
     }
 
-    pub fn get_email_body(&mut self, index: usize) -> Result<EmailBody, AppError> {
+    /// Load a message body with its HTML sanitized; remote images are only
+    /// kept when `load_remote` is set for this message.
+    pub fn get_email_body(
+        &mut self,
+        index: usize,
+        load_remote: bool,
+    ) -> Result<EmailBody, AppError> {
         if self.entries.is_empty() {
             return Err(AppError::Validation(
                 "No MBOX file is currently open".to_string(),
@@ -110,7 +433,57 @@ impl MboxService {
             .get_message(entry)
             .map_err(|e| AppError::MboxShell(e.to_string()))?;
 
-        Ok(EmailBody::from(body))
+        let mut email = EmailBody::from(body);
+        let attachments = body.attachments.clone();
+        let mut html = body.html.clone();
+
+        // winmail.dat parts are replaced by the files they carry
+        let (listed, unpacked) = unpack_tnef(store, entry)?;
+        let mut part_index = listed;
+        for (tnef_part, message) in unpacked {
+            email.attachments.retain(|a| a.part_index != tnef_part);
+            for file in message.attachments {
+                email.attachments.push(AttachmentInfo {
+                    filename: file.filename,
+                    content_type: file.content_type,
+                    size: file.data.len() as u64,
+                    part_index,
+                    url: String::new(),
+                });
+                part_index += 1;
+            }
+            // Outlook's rich body stands in for one the message lacks
+            email.text = email.text.or(message.text);
+            html = html.or(message.html);
+        }
+
+        email.segments = quoting::segment(&body_text::plain_text(
+            email.text.as_deref(),
+            html.as_deref(),
+        ));
+        if let Some(html) = html.as_deref() {
+            let (clean, remote_content) = sanitize::sanitize(html, load_remote);
+            email.html = Some(inline_images::rewrite(&clean, index));
+            email.remote_content = remote_content;
+        }
+        for attachment in &mut email.attachments {
+            attachment.url = scheme::attachment_url(index, attachment.part_index);
+        }
+        // Forwarded messages are decoded so they read like any other email
+        for (attachment_index, meta) in attachments.iter().enumerate() {
+            if !rfc822::is_message(&meta.content_type) || meta.size > mime::MAX_MESSAGE_BYTES {
+                continue;
+            }
+            if let Ok(data) = store.get_attachment(entry, meta) {
+                email.embedded.push(rfc822::embedded_message(
+                    &data,
+                    index,
+                    &[attachment_index],
+                    load_remote,
+                ));
+            }
+        }
+        Ok(email)
     }
 
     pub fn get_labels(&self) -> Vec<LabelCount> {
@@ -118,17 +491,43 @@ impl MboxService {
     }
 
     pub fn get_emails_by_label(&self, label: &str) -> Result<Vec<EmailEntry>, AppError> {
+        self.get_emails_by_label_kind(label, LabelKind::Gmail)
+    }
+
+    /// List the emails under a label of any kind, e.g. a saved-search smart folder.
+    pub fn get_emails_by_label_kind(
+        &self,
+        label: &str,
+        kind: LabelKind,
+    ) -> Result<Vec<EmailEntry>, AppError> {
         if self.entries.is_empty() {
             return Err(AppError::Validation(
                 "No MBOX file is currently open".to_string(),
             ));
         }
 
+        if kind != LabelKind::Gmail {
+            let virtual_label = self
+                .virtual_labels
+                .iter()
+                .find(|v| v.kind == kind && v.name == label)
+                .ok_or_else(|| AppError::NotFound(format!("label {label}")))?;
+            return Ok(virtual_label
+                .indices
+                .iter()
+                .filter(|&&i| !self.is_hidden(i))
+                .map(|&i| self.email_entry(i))
+                .collect());
+        }
+
         let results = self
             .entries
             .iter()
-            .filter(|e| e.labels.iter().any(|l| l.eq_ignore_ascii_case(label)))
-            .map(EmailEntry::from)
+            .enumerate()
+            .filter(|(i, e)| {
+                !self.is_hidden(*i) && e.labels.iter().any(|l| l.eq_ignore_ascii_case(label))
+            })
+            .map(|(i, _)| self.email_entry(i))
             .collect();
 
         Ok(results)
@@ -141,191 +540,1992 @@ impl MboxService {
             ));
         }
 
-        let mbox_path = self
-            .mbox_path
-            .as_ref()
-            .ok_or_else(|| AppError::Validation("No MBOX file path available".to_string()))?;
-
-        let (_parsed_query, matching_indices) =
-            search::execute(mbox_path, &self.entries, query, None)
-                .map_err(|e| AppError::MboxShell(e.to_string()))?;
+        let mut hits = self.run_query(query)?;
 
-        let total_count = matching_indices.len();
+        let total_count = hits.len();
         let max_results = limit.unwrap_or(500);
+        hits.truncate(max_results);
 
-        let emails = matching_indices
+        let emails = hits.iter().map(|hit| self.email_entry(hit.index)).collect();
+        let matches = hits
             .into_iter()
-            .take(max_results)
-            .map(|i| EmailEntry::from(&self.entries[i]))
+            .filter(|hit| !hit.matches.is_empty())
             .collect();
 
         Ok(SearchResults {
             emails,
             total_count,
+            matches,
         })
     }
 
-    pub fn get_attachment(
-        &mut self,
-        email_index: usize,
-        attachment_index: usize,
-    ) -> Result<Vec<u8>, AppError> {
+    /// Search in the requested mode, reporting matched substrings for regex and fuzzy.
+    ///
+    /// Every pattern term must match somewhere in the selected fields for a
+    /// message to be a hit. Searching bodies decodes every message, so this
+    /// opens its own store and is meant to run off the main thread.
+    pub fn search_pattern(
+        &self,
+        request: &PatternSearch,
+        limit: Option<usize>,
+    ) -> Result<SearchResults, AppError> {
+        if request.mode == SearchMode::Query {
+            return self.search(&request.pattern, limit);
+        }
         if self.entries.is_empty() {
             return Err(AppError::Validation(
                 "No MBOX file is currently open".to_string(),
             ));
         }
 
-        if email_index >= self.entries.len() {
-            return Err(AppError::Validation(format!(
-                "Invalid email index: {email_index}"
-            )));
+        let matcher = Matcher::new(
+            request.mode,
+            &request.pattern,
+            request.case_sensitive,
+            request.max_distance,
+        )?;
+        let fields = if request.fields.is_empty() {
+            vec![
+                MatchField::Subject,
+                MatchField::From,
+                MatchField::To,
+                MatchField::Body,
+            ]
+        } else {
+            request.fields.clone()
+        };
+
+        let mut store = match (fields.contains(&MatchField::Body), &self.mbox_path) {
+            (true, Some(path)) => {
+                Some(MboxStore::open(path).map_err(|e| AppError::MboxShell(e.to_string()))?)
+            }
+            _ => None,
+        };
+
+        let mut hits = Vec::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            if self.is_hidden(index) {
+                continue;
+            }
+            let mut matched_terms = vec![false; matcher.term_count()];
+            let mut matches = Vec::new();
+            for &field in &fields {
+                let value = match (field, store.as_mut()) {
+                    (MatchField::Subject, _) => entry.subject.clone(),
+                    (MatchField::From, _) => {
+                        format_address(&entry.from.display_name, &entry.from.address)
+                    }
+                    (MatchField::To, _) => entry
+                        .to
+                        .iter()
+                        .chain(&entry.cc)
+                        .map(|a| format_address(&a.display_name, &a.address))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    (MatchField::Body, Some(store)) => match self.body_text(store, index) {
+                        Ok(text) if request.exclude_quoted => quoting::fresh_text(&text),
+                        Ok(text) => text,
+                        // An undecodable body reads as empty; its headers still match
+                        Err(_) => String::new(),
+                    },
+                    (MatchField::Body, None) => String::new(),
+                    (MatchField::Attachment, _) => {
+                        for doc in self.attachment_text()?.documents_of(index) {
+                            let found = matcher.find(&doc.text);
+                            for m in &found {
+                                matched_terms[m.term] = true;
+                            }
+                            matches.extend(found.into_iter().take(MAX_MATCHES_PER_FIELD).map(
+                                |m| MatchedText {
+                                    field,
+                                    start: m.start,
+                                    end: m.end,
+                                    text: doc.text[m.start..m.end].to_string(),
+                                    attachment_index: Some(doc.attachment_index),
+                                },
+                            ));
+                        }
+                        continue;
+                    }
+                };
+                let found = matcher.find(&value);
+                for m in &found {
+                    matched_terms[m.term] = true;
+                }
+                matches.extend(found.into_iter().take(MAX_MATCHES_PER_FIELD).map(|m| {
+                    MatchedText {
+                        field,
+                        start: m.start,
+                        end: m.end,
+                        text: value[m.start..m.end].to_string(),
+                        attachment_index: None,
+                    }
+                }));
+            }
+            if matched_terms.iter().all(|&hit| hit) {
+                hits.push(MessageMatches { index, matches });
+            }
         }
 
-        let store = self
-            .store
-            .as_mut()
-            .ok_or_else(|| AppError::Validation("MBOX store not initialized".to_string()))?;
+        let total_count = hits.len();
+        hits.truncate(limit.unwrap_or(500));
+        let emails = hits.iter().map(|m| self.email_entry(m.index)).collect();
 
-        let entry = &self.entries[email_index];
-        let body = store
-            .get_message(entry)
-            .map_err(|e| AppError::MboxShell(e.to_string()))?;
+        Ok(SearchResults {
+            emails,
+            total_count,
+            matches: hits,
+        })
+    }
 
-        if attachment_index >= body.attachments.len() {
-            return Err(AppError::Validation(format!(
-                "Invalid attachment index: {attachment_index}"
-            )));
+    /// Search, narrow by facet selections, and count facets over the full result.
+    ///
+    /// Facets describe every message left after `filters`, not just the
+    /// returned page. Attachment types require reading message bodies, so
+    /// this opens its own store and is meant to run off the main thread.
+    pub fn search_faceted(
+        &self,
+        query: &str,
+        filters: &[FacetFilter],
+        limit: Option<usize>,
+    ) -> Result<FacetedSearchResults, AppError> {
+        if self.entries.is_empty() {
+            return Err(AppError::Validation(
+                "No MBOX file is currently open".to_string(),
+            ));
         }
 
-        let attachment_meta = body.attachments[attachment_index].clone();
+        let mbox_path = self
+            .mbox_path
+            .as_ref()
+            .ok_or_else(|| AppError::Validation("No MBOX file path available".to_string()))?;
+        let mut store =
+            MboxStore::open(mbox_path).map_err(|e| AppError::MboxShell(e.to_string()))?;
 
-        let attachment_data = store
-            .get_attachment(entry, &attachment_meta)
-            .map_err(|e| AppError::MboxShell(e.to_string()))?;
+        let mut indices = Vec::new();
+        let mut records = Vec::new();
+        for i in self.search_indices(query)? {
+            // One undecodable message does not fail the whole search
+            let Ok(record) = self.facet_record(&mut store, i) else {
+                continue;
+            };
+            if record.matches(filters) {
+                indices.push(i);
+                records.push(record);
+            }
+        }
 
-        Ok(attachment_data)
-    }
+        let facets = facets::compute(&records);
+        let total_count = indices.len();
+        let emails = indices
+            .into_iter()
+            .take(limit.unwrap_or(500))
+            .map(|i| self.email_entry(i))
+            .collect();
 
-    pub fn close(&mut self) {
-        self.mbox_path = None;
-        self.entries = Vec::new();
-        self.store = None;
+        Ok(FacetedSearchResults {
+            emails,
+            total_count,
+            facets,
+        })
     }
 
-    pub fn is_open(&self) -> bool {
-        self.store.is_some()
+    /// Extract and index the text of every attachment, returning how many
+    /// attachments yielded text. Searching with `attachment:` builds the
+    /// index on demand; calling this first just moves the cost up front.
+    pub fn index_attachment_text(&self) -> Result<usize, AppError> {
+        if self.entries.is_empty() {
+            return Err(AppError::Validation(
+                "No MBOX file is currently open".to_string(),
+            ));
+        }
+        Ok(self.attachment_text()?.len())
     }
 
-    /// Create a lightweight snapshot with entries and path for search.
-    ///
-    /// Search only needs entries and the mbox path (no store).
-    /// This allows releasing the main lock before `spawn_blocking`.
-    pub fn snapshot_for_search(source: &MboxService) -> Self {
-        Self {
-            mbox_path: source.mbox_path.clone(),
-            entries: source.entries.clone(),
-            store: None,
+    /// Count, for each search term, the messages and threads it hits, plus
+    /// the hits unique to that term and the deduplicated totals.
+    pub fn hit_report(&self, terms: &[String]) -> Result<HitReport, AppError> {
+        if self.entries.is_empty() {
+            return Err(AppError::Validation(
+                "No MBOX file is currently open".to_string(),
+            ));
         }
-    }
 
-    fn count_labels(&self) -> Vec<LabelCount> {
-        let mut label_counts: HashMap<String, usize> = HashMap::new();
-        for entry in &self.entries {
-            for label in &entry.labels {
-                *label_counts.entry(label.clone()).or_insert(0) += 1;
-            }
+        let terms: Vec<&str> = terms
+            .iter()
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .collect();
+        if terms.is_empty() {
+            return Err(AppError::Validation("No search terms given".to_string()));
         }
-        let mut labels: Vec<LabelCount> = label_counts
+
+        let term_hits = terms
             .into_iter()
-            .map(|(label, count)| LabelCount { label, count })
-            .collect();
-        labels.sort_by(|a, b| b.count.cmp(&a.count));
-        labels
+            .map(|term| Ok((term.to_string(), self.search_indices(term)?)))
+            .collect::<Result<Vec<_>, AppError>>()?;
+        let thread_ids = threads::thread_ids(self.message_headers()?);
+
+        // Collapsed duplicates are not searched, so they are not counted
+        Ok(hit_report::build(
+            &term_hits,
+            &thread_ids,
+            self.get_email_count(),
+        ))
     }
-}
 
-impl Default for MboxService {
-    fn default() -> Self {
-        Self::new()
+    /// Run a hit report and write it as CSV to `path`.
+    pub fn export_hit_report(&self, terms: &[String], path: &Path) -> Result<HitReport, AppError> {
+        let report = self.hit_report(terms)?;
+        std::fs::write(path, hit_report::to_csv(&report))?;
+        Ok(report)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::path::Path;
+    /// The messages whose bodies are most similar to message `index`,
+    /// excluding its own thread, with cosine similarity scores.
+    pub fn similar_messages(
+        &self,
+        index: usize,
+        limit: usize,
+    ) -> Result<Vec<SimilarMessage>, AppError> {
+        if self.entries.is_empty() {
+            return Err(AppError::Validation(
+                "No MBOX file is currently open".to_string(),
+            ));
+        }
+        if index >= self.entries.len() {
+            return Err(AppError::Validation(format!(
+                "Invalid email index: {index}"
+            )));
+        }
 
-    #[test]
-    fn new_has_no_mbox_path() {
-        let service = MboxService::new();
-        assert!(service.mbox_path.is_none());
-    }
+        let thread_ids = threads::thread_ids(self.message_headers()?);
+        let similar = self
+            .tfidf(&CancelToken::default())?
+            .most_similar(index, limit, |i| {
+                thread_ids[i] == thread_ids[index] || self.is_hidden(i)
+            });
 
-    #[test]
-    fn new_has_empty_entries() {
-        let service = MboxService::new();
-        assert!(service.entries.is_empty());
+        Ok(similar
+            .into_iter()
+            .map(|(i, score)| SimilarMessage {
+                email: self.email_entry(i),
+                score,
+            })
+            .collect())
     }
 
-    #[test]
-    fn new_has_no_store() {
-        let service = MboxService::new();
-        assert!(service.store.is_none());
+    /// Detect the language of every message.
+    ///
+    /// Decodes every body on first run, so this is meant to run off the main
+    /// thread; afterwards listings carry `language` and `stats` include counts.
+    pub fn detect_languages(&self) -> Result<(), AppError> {
+        if self.entries.is_empty() {
+            return Err(AppError::Validation(
+                "No MBOX file is currently open".to_string(),
+            ));
+        }
+        self.languages()?;
+        Ok(())
     }
 
-    #[test]
-    fn default_is_equivalent_to_new() {
-        let service = MboxService::default();
-        assert!(service.mbox_path.is_none());
-        assert!(service.entries.is_empty());
-        assert!(service.store.is_none());
-    }
+    /// List the emails in a language, given as `es`, `spa` or `spanish`.
+    pub fn get_emails_by_language(&self, language: &str) -> Result<Vec<EmailEntry>, AppError> {
+        if self.entries.is_empty() {
+            return Err(AppError::Validation(
+                "No MBOX file is currently open".to_string(),
+            ));
+        }
+        let code = if language.eq_ignore_ascii_case(UNKNOWN_LANGUAGE) {
+            None
+        } else {
+            Some(
+                language::parse(language)
+                    .ok_or_else(|| AppError::Validation(format!("Unknown language: {language}")))?,
+            )
+        };
 
-    #[test]
-    fn open_returns_not_found_for_nonexistent_path() {
-        let mut service = MboxService::new();
-        let result = service.open(Path::new("/nonexistent/file.mbox"));
-        assert!(result.is_err());
-        let err = result.unwrap_err();
-        assert_eq!(err.to_string(), "Not found: /nonexistent/file.mbox");
+        let languages = self.languages()?;
+        Ok(self
+            .visible_indices()
+            .filter(|&i| languages[i] == code)
+            .map(|i| self.email_entry(i))
+            .collect())
     }
 
-    #[test]
-    fn get_email_count_returns_zero_when_no_file_open() {
-        let service = MboxService::new();
-        assert_eq!(service.get_email_count(), 0);
+    /// Extractive summary of one message, ignoring quoted replies and the signature.
+    pub fn summarize_email(&self, index: usize, sentences: usize) -> Result<Summary, AppError> {
+        self.check_index(index)?;
+        self.summarize(vec![index], sentences)
     }
 
-    #[test]
-    fn get_emails_returns_validation_error_when_no_file_open() {
-        let service = MboxService::new();
-        let result = service.get_emails(0, 10);
-        assert!(result.is_err());
-        assert_eq!(
-            result.unwrap_err().to_string(),
-            "Validation error: No MBOX file is currently open"
-        );
+    /// Extractive summary over every message in the thread of `index`.
+    pub fn summarize_thread(&self, index: usize, sentences: usize) -> Result<Summary, AppError> {
+        self.check_index(index)?;
+        let thread_ids = threads::thread_ids(self.message_headers()?);
+        let mut members: Vec<usize> = (0..self.entries.len())
+            .filter(|&i| thread_ids[i] == thread_ids[index] && (i == index || !self.is_hidden(i)))
+            .collect();
+        members.sort_by_key(|&i| (self.entries[i].date, std::cmp::Reverse(i)));
+        self.summarize(members, sentences)
     }
 
-    #[test]
-    fn get_email_body_returns_validation_error_when_no_file_open() {
-        let mut service = MboxService::new();
-        let result = service.get_email_body(0);
-        assert!(result.is_err());
-        assert_eq!(
-            result.unwrap_err().to_string(),
-            "Validation error: No MBOX file is currently open"
-        );
-    }
+    fn summarize(&self, indices: Vec<usize>, sentences: usize) -> Result<Summary, AppError> {
+        let mbox_path = self
+            .mbox_path
+            .as_ref()
+            .ok_or_else(|| AppError::Validation("No MBOX file path available".to_string()))?;
+        let mut store =
+            MboxStore::open(mbox_path).map_err(|e| AppError::MboxShell(e.to_string()))?;
 
-    #[test]
+        // Undecodable messages are left out unless none can be read
+        let mut texts = Vec::with_capacity(indices.len());
+        let mut covered = Vec::with_capacity(indices.len());
+        let mut failure = None;
+        for i in indices {
+            match self.body_text(&mut store, i) {
+                Ok(text) => {
+                    texts.push(quoting::fresh_text(&text));
+                    covered.push(i);
+                }
+                Err(e) => failure = Some(e),
+            }
+        }
+        if let (true, Some(e)) = (covered.is_empty(), failure) {
+            return Err(e);
+        }
+        let indices = covered;
+        let all: Vec<summarize::Sentence> = texts
+            .iter()
+            .enumerate()
+            .flat_map(|(doc, text)| summarize::sentences(doc, text))
+            .take(MAX_SUMMARY_SENTENCES)
+            .collect();
+
+        Ok(Summary {
+            sentences: summarize::top_sentences(&all, sentences)
+                .into_iter()
+                .map(|(s, score)| SummarySentence {
+                    text: s.text.clone(),
+                    email_index: indices[s.doc],
+                    score,
+                })
+                .collect(),
+            keywords: summarize::keywords(texts.iter().map(String::as_str), SUMMARY_KEYWORDS),
+            email_indices: indices,
+        })
+    }
+
+    /// Detect exact duplicates (Message-ID or normalized content hash) and
+    /// near-duplicate bodies, returning every group with its representative.
+    pub fn find_duplicates(&self) -> Result<DuplicateReport, AppError> {
+        if self.entries.is_empty() {
+            return Err(AppError::Validation(
+                "No MBOX file is currently open".to_string(),
+            ));
+        }
+
+        let groups = self.duplicate_groups()?;
+        let to_group = |kind: DuplicateKind, members: &Vec<usize>| DuplicateGroup {
+            kind,
+            representative: members[0],
+            duplicates: members[1..].to_vec(),
+        };
+        let exact: Vec<DuplicateGroup> = groups
+            .exact
+            .iter()
+            .map(|g| to_group(DuplicateKind::Exact, g))
+            .collect();
+        let near: Vec<DuplicateGroup> = groups
+            .near
+            .iter()
+            .map(|g| to_group(DuplicateKind::Near, g))
+            .collect();
+
+        Ok(DuplicateReport {
+            exact_duplicates: exact.iter().map(|g| g.duplicates.len()).sum(),
+            near_duplicates: near.iter().map(|g| g.duplicates.len()).sum(),
+            groups: exact.into_iter().chain(near).collect(),
+        })
+    }
+
+    /// Cluster the visible messages into topics with k-means over TF-IDF.
+    ///
+    /// `clusters` defaults to a size-based estimate. Decoding bodies can take
+    /// a while on first use, so this is meant to run off the main thread and
+    /// stops with `AppError::Cancelled` once `cancel` is triggered. Apply the
+    /// result with `set_topic_labels`.
+    pub fn cluster_topics(
+        &self,
+        clusters: Option<usize>,
+        cancel: &CancelToken,
+    ) -> Result<TopicModel, AppError> {
+        if self.entries.is_empty() {
+            return Err(AppError::Validation(
+                "No MBOX file is currently open".to_string(),
+            ));
+        }
+        if let Some(n) = clusters.filter(|n| !(1..=clustering::MAX_CLUSTERS).contains(n)) {
+            return Err(AppError::Validation(format!(
+                "Cluster count must be between 1 and {}, got {n}",
+                clustering::MAX_CLUSTERS
+            )));
+        }
+
+        let index = self.tfidf(cancel)?;
+        let docs: Vec<usize> = self.visible_indices().collect();
+        let k = clusters.unwrap_or_else(|| clustering::default_k(docs.len()));
+        Ok(TopicModel {
+            mbox_path: self.mbox_path.clone(),
+            clusters: clustering::cluster(index, &docs, k, cancel)?,
+        })
+    }
+
+    /// Replace the topic virtual labels with a freshly computed model.
+    pub fn set_topic_labels(&mut self, model: TopicModel) -> Result<Vec<TopicCluster>, AppError> {
+        if model.mbox_path != self.mbox_path {
+            return Err(AppError::Validation(
+                "The MBOX file changed while clustering".to_string(),
+            ));
+        }
+
+        let topics: Vec<TopicCluster> = model
+            .clusters
+            .iter()
+            .enumerate()
+            .map(|(n, cluster)| TopicCluster {
+                label: topic_label(n + 1, &cluster.keywords),
+                keywords: cluster.keywords.clone(),
+                count: cluster.members.len(),
+            })
+            .collect();
+
+        self.virtual_labels.retain(|v| v.kind != LabelKind::Topic);
+        self.virtual_labels
+            .extend(
+                topics
+                    .iter()
+                    .zip(model.clusters)
+                    .map(|(topic, cluster)| VirtualLabel {
+                        name: topic.label.clone(),
+                        kind: LabelKind::Topic,
+                        indices: cluster.members,
+                    }),
+            );
+        Ok(topics)
+    }
+
+    /// Mailing lists in the archive with message counts and date span.
+    ///
+    /// Reads every header block on first use; meant to run off the main thread.
+    pub fn get_mailing_lists(&self) -> Result<Vec<MailingList>, AppError> {
+        if self.entries.is_empty() {
+            return Err(AppError::Validation(
+                "No MBOX file is currently open".to_string(),
+            ));
+        }
+
+        let groups = mailing_lists::group(self.message_headers()?, self.visible_indices(), |i| {
+            self.entries[i].date.timestamp()
+        });
+        Ok(groups
+            .into_iter()
+            .map(|g| {
+                let dates = g.members.iter().map(|&i| &self.entries[i].date);
+                let first = dates.clone().min().expect("lists have members");
+                let last = dates.max().expect("lists have members");
+                MailingList {
+                    list_id: g.list_id,
+                    name: g.name,
+                    count: g.members.len(),
+                    first_date: first.to_rfc3339(),
+                    last_date: last.to_rfc3339(),
+                    post: g.post,
+                    unsubscribe: g.unsubscribe,
+                    archive: g.archive,
+                }
+            })
+            .collect())
+    }
+
+    /// List the emails of one mailing list, by its exact `List-Id`.
+    pub fn get_emails_by_list(&self, list_id: &str) -> Result<Vec<EmailEntry>, AppError> {
+        if self.entries.is_empty() {
+            return Err(AppError::Validation(
+                "No MBOX file is currently open".to_string(),
+            ));
+        }
+
+        let list_id = list_id.trim().to_lowercase();
+        let headers = self.message_headers()?;
+        Ok(self
+            .visible_indices()
+            .filter(|&i| headers[i].list_id.as_deref() == Some(list_id.as_str()))
+            .map(|i| self.email_entry(i))
+            .collect())
+    }
+
+    /// Inbox category of every message, inferred from its headers.
+    ///
+    /// Reads every header block on first use, so run it off the main thread
+    /// before `set_category_labels` to keep that call cheap.
+    pub fn classify_categories(&self) -> Result<Vec<MailCategory>, AppError> {
+        if self.entries.is_empty() {
+            return Err(AppError::Validation(
+                "No MBOX file is currently open".to_string(),
+            ));
+        }
+        let headers = self.message_headers()?;
+        Ok(self
+            .entries
+            .iter()
+            .zip(headers)
+            .map(|(entry, h)| categories::classify(h, &entry.from.address))
+            .collect())
+    }
+
+    /// Expose the inbox categories as virtual labels and return their counts.
+    pub fn set_category_labels(&mut self) -> Result<Vec<LabelCount>, AppError> {
+        let categories = self.classify_categories()?;
+        self.virtual_labels
+            .retain(|v| v.kind != LabelKind::Category);
+        self.virtual_labels
+            .extend(MailCategory::ALL.iter().map(|&category| {
+                VirtualLabel {
+                    name: category.label().to_string(),
+                    kind: LabelKind::Category,
+                    indices: (0..categories.len())
+                        .filter(|&i| categories[i] == category)
+                        .collect(),
+                }
+            }));
+        Ok(self
+            .count_labels()
+            .into_iter()
+            .filter(|l| l.kind == LabelKind::Category)
+            .collect())
+    }
+
+    /// Switch the duplicate collapse mode and return the updated statistics.
+    ///
+    /// Collapsed duplicates are left out of listings, counts, label views,
+    /// searches and everything built on them (saved searches, reports, exports).
+    pub fn set_duplicate_collapse(
+        &mut self,
+        mode: DuplicateCollapse,
+    ) -> Result<MboxStats, AppError> {
+        if self.entries.is_empty() {
+            return Err(AppError::Validation(
+                "No MBOX file is currently open".to_string(),
+            ));
+        }
+
+        let hidden = match mode {
+            DuplicateCollapse::Off => Vec::new(),
+            _ => duplicates::hidden_mask(self.duplicate_groups()?, mode, self.entries.len()),
+        };
+        self.collapse = mode;
+        self.hidden = Arc::new(hidden);
+        Ok(self.stats())
+    }
+
+    /// Every other copy of message `index` (exact and near), including the
+    /// hidden ones, so a collapsed message can still be expanded.
+    pub fn get_duplicate_copies(&self, index: usize) -> Result<Vec<DuplicateCopy>, AppError> {
+        if self.entries.is_empty() {
+            return Err(AppError::Validation(
+                "No MBOX file is currently open".to_string(),
+            ));
+        }
+        if index >= self.entries.len() {
+            return Err(AppError::Validation(format!(
+                "Invalid email index: {index}"
+            )));
+        }
+
+        let groups = self.duplicate_groups()?;
+        let exact = groups.exact.iter().map(|g| (DuplicateKind::Exact, g));
+        let near = groups.near.iter().map(|g| (DuplicateKind::Near, g));
+        Ok(exact
+            .chain(near)
+            .filter(|(_, group)| group.contains(&index))
+            .flat_map(|(kind, group)| {
+                group
+                    .iter()
+                    .filter(|&&i| i != index)
+                    .map(move |&i| DuplicateCopy {
+                        email: self.email_entry(i),
+                        kind,
+                        is_representative: i == group[0],
+                        hidden: self.is_hidden(i),
+                    })
+            })
+            .collect())
+    }
+
+    /// What is needed to decode the parts of message `index` without the service.
+    pub fn message_source(&self, index: usize) -> Result<MessageSource, AppError> {
+        self.check_index(index)?;
+        let mbox_path = self
+            .mbox_path
+            .clone()
+            .ok_or_else(|| AppError::Validation("No MBOX file path available".to_string()))?;
+        Ok(MessageSource {
+            mbox_path,
+            entry: self.entries[index].clone(),
+            spool_dir: self.spool_dir(),
+        })
+    }
+
+    /// Write the attachments of the messages matching `request.query` into a
+    /// directory tree laid out by the request's template, with a manifest.
+    ///
+    /// Identical files are written once (by SHA-256); later copies are listed
+    /// in the manifest as duplicates of the first. A file that cannot be
+    /// written is counted as failed and the run goes on; a cancelled run still
+    /// writes the manifest for what was extracted, ending with a `cancelled`
+    /// line. A message that cannot be decoded is counted as failed too, with
+    /// a manifest row of its own. Meant to run on a snapshot: parts are
+    /// decoded with a store of its own.
+    pub fn extract_attachments(
+        &self,
+        request: &ExtractionRequest,
+        cancel: &CancelToken,
+        on_progress: &mut dyn FnMut(&ExtractionProgress),
+    ) -> Result<ExtractionSummary, AppError> {
+        if self.entries.is_empty() {
+            return Err(AppError::Validation(
+                "No MBOX file is currently open".to_string(),
+            ));
+        }
+        let root = PathBuf::from(&request.destination);
+        if !root.is_absolute() {
+            return Err(AppError::Validation(
+                "Destination must be an absolute path".to_string(),
+            ));
+        }
+        let template = request
+            .template
+            .as_deref()
+            .unwrap_or(extraction::DEFAULT_TEMPLATE);
+        extraction::validate(template)?;
+
+        let indices: Vec<usize> = if request.query.trim().is_empty() {
+            self.visible_indices().collect()
+        } else {
+            self.run_query(&request.query)?
+                .into_iter()
+                .map(|hit| hit.index)
+                .collect()
+        };
+        let indices: Vec<usize> = indices
+            .into_iter()
+            .filter(|&i| self.entries[i].has_attachments)
+            .collect();
+
+        let mbox_path = self
+            .mbox_path
+            .as_ref()
+            .ok_or_else(|| AppError::Validation("No MBOX file path available".to_string()))?;
+        let mut store =
+            MboxStore::open(mbox_path).map_err(|e| AppError::MboxShell(e.to_string()))?;
+        std::fs::create_dir_all(&root)?;
+
+        let mut progress = ExtractionProgress {
+            total_messages: indices.len(),
+            ..ExtractionProgress::default()
+        };
+        let mut bytes_written = 0;
+        let mut manifest = vec![extraction::MANIFEST_HEADER.to_string()];
+        // First file written per content hash, relative to `root`
+        let mut written_by_hash: HashMap<String, String> = HashMap::new();
+        let mut written_paths: HashSet<PathBuf> = HashSet::new();
+        let mut stopped = None;
+
+        for index in indices {
+            if let Err(e) = cancel.check() {
+                stopped = Some(e);
+                break;
+            }
+            let entry = &self.entries[index];
+            let date = entry.date.to_rfc3339();
+            let wanted = |part: &ListedPart| {
+                extraction::matches_type(&request.file_types, &part.filename, &part.content_type)
+            };
+            let visited = visit_attachments(&mut store, entry, wanted, |part, data| {
+                let mut file = String::new();
+                let mut duplicate_of = String::new();
+                let mut sha256 = String::new();
+                let mut size = 0;
+
+                let status = match data {
+                    Err(_) => {
+                        progress.failed += 1;
+                        "failed"
+                    }
+                    Ok(data) => {
+                        sha256 = attachment_files::sha256_hex(&data);
+                        size = data.len() as u64;
+                        if let Some(first) = written_by_hash.get(&sha256) {
+                            progress.duplicates += 1;
+                            duplicate_of = first.clone();
+                            "duplicate"
+                        } else {
+                            let fields = TemplateFields {
+                                date: &date,
+                                sender: &entry.from.address,
+                                sender_name: &entry.from.display_name,
+                                subject: &entry.subject,
+                                filename: &part.filename,
+                                index,
+                            };
+                            // Ok(None) when the target exists and is skipped
+                            let outcome = extraction::render(template, &fields).and_then(
+                                |relative| -> Result<(PathBuf, Option<PathBuf>), AppError> {
+                                    let target = root.join(&relative);
+                                    let dir = target.parent().unwrap_or(&root);
+                                    std::fs::create_dir_all(dir)?;
+                                    let name = target
+                                        .file_name()
+                                        .map(|n| n.to_string_lossy().into_owned())
+                                        .unwrap_or_default();
+                                    // Never replace a file written earlier in this run
+                                    let policy = if written_paths.contains(&target) {
+                                        ConflictPolicy::Rename
+                                    } else {
+                                        request.conflict
+                                    };
+                                    let path =
+                                        attachment_files::resolve_conflict(dir, &name, policy);
+                                    if let Some(path) = &path {
+                                        attachment_files::write_file(path, &data)?;
+                                    }
+                                    Ok((relative, path))
+                                },
+                            );
+
+                            match outcome {
+                                Err(_) => {
+                                    progress.failed += 1;
+                                    "failed"
+                                }
+                                Ok((relative, None)) => {
+                                    progress.skipped += 1;
+                                    file = relative.display().to_string();
+                                    "skipped"
+                                }
+                                Ok((_, Some(path))) => {
+                                    file = path
+                                        .strip_prefix(&root)
+                                        .unwrap_or(&path)
+                                        .display()
+                                        .to_string();
+                                    written_by_hash.insert(sha256.clone(), file.clone());
+                                    written_paths.insert(path);
+                                    progress.written += 1;
+                                    bytes_written += size;
+                                    "written"
+                                }
+                            }
+                        }
+                    }
+                };
+
+                manifest.push(
+                    ManifestRow {
+                        file: &file,
+                        status,
+                        duplicate_of: &duplicate_of,
+                        sha256: &sha256,
+                        size,
+                        email_index: index,
+                        date: &date,
+                        from: &entry.from.address,
+                        subject: &entry.subject,
+                        attachment_index: Some(part.part_index),
+                        original_filename: &part.filename,
+                    }
+                    .to_csv(),
+                );
+            });
+            // A message that fails to decode is one failed row, with no attachment
+            if visited.is_err() {
+                progress.failed += 1;
+                manifest.push(
+                    ManifestRow {
+                        file: "",
+                        status: "failed",
+                        duplicate_of: "",
+                        sha256: "",
+                        size: 0,
+                        email_index: index,
+                        date: &date,
+                        from: &entry.from.address,
+                        subject: &entry.subject,
+                        attachment_index: None,
+                        original_filename: "",
+                    }
+                    .to_csv(),
+                );
+            }
+
+            progress.processed_messages += 1;
+            on_progress(&progress);
+        }
+
+        if stopped.is_some() {
+            manifest.push(extraction::cancelled_row());
+        }
+        let manifest_path = attachment_files::resolve_conflict(
+            &root,
+            extraction::MANIFEST_FILENAME,
+            ConflictPolicy::Rename,
+        )
+        .unwrap_or_else(|| root.join(extraction::MANIFEST_FILENAME));
+        let mut csv = manifest.join("\n");
+        csv.push('\n');
+        attachment_files::write_file(&manifest_path, csv.as_bytes())?;
+        if let Some(e) = stopped {
+            return Err(e);
+        }
+
+        Ok(ExtractionSummary {
+            progress,
+            bytes_written,
+            manifest_path: manifest_path.display().to_string(),
+        })
+    }
+
+    /// List, filter and sort every attachment in the mailbox.
+    ///
+    /// Decodes and hashes every attachment on first run, so this is meant to
+    /// run off the main thread; later queries reuse the cached catalog.
+    pub fn get_attachment_catalog(
+        &self,
+        query: &CatalogQuery,
+    ) -> Result<AttachmentCatalog, AppError> {
+        if self.entries.is_empty() {
+            return Err(AppError::Validation(
+                "No MBOX file is currently open".to_string(),
+            ));
+        }
+        let items = self
+            .attachment_catalog()?
+            .iter()
+            .filter(|record| !self.is_hidden(record.email_index))
+            .map(|record| self.catalog_attachment(record))
+            .collect();
+        Ok(catalog::query(items, query))
+    }
+
+    /// Every other message carrying a byte-identical copy of an attachment,
+    /// oldest first, whatever the copies are named.
+    ///
+    /// Messages that fail to decode are left out of the catalog; asking for
+    /// one of their attachments reports the decode error.
+    pub fn find_attachment_copies(
+        &self,
+        email_index: usize,
+        attachment_index: usize,
+    ) -> Result<AttachmentCopies, AppError> {
+        self.check_index(email_index)?;
+        let records = self.attachment_catalog()?;
+        let source = records
+            .iter()
+            .find(|r| r.email_index == email_index && r.attachment_index == attachment_index);
+        let Some(source) = source else {
+            self.check_decodable(email_index)?;
+            return Err(AppError::Validation(format!(
+                "Invalid attachment index: {attachment_index}"
+            )));
+        };
+        if source.sha256.is_none() {
+            return Err(AppError::MboxShell(format!(
+                "Attachment {attachment_index} of email {email_index} could not be decoded"
+            )));
+        }
+
+        let copies = catalog::copies_of(
+            records,
+            source,
+            |index| self.is_hidden(index),
+            |index| self.entries[index].date.timestamp(),
+        )
+        .into_iter()
+        .map(|r| self.catalog_attachment(r))
+        .collect();
+
+        Ok(AttachmentCopies {
+            source: self.catalog_attachment(source),
+            copies,
+        })
+    }
+
+    /// Decode the part a `cid:` reference in a message's HTML points at.
+    pub fn get_inline_part(
+        &mut self,
+        index: usize,
+        content_id: &str,
+    ) -> Result<InlinePart, AppError> {
+        self.check_index(index)?;
+        let mbox_path = self
+            .mbox_path
+            .as_ref()
+            .ok_or_else(|| AppError::Validation("No MBOX file path available".to_string()))?;
+        let entry = &self.entries[index];
+        let raw = mime::read_message(mbox_path, entry.offset, entry.length)?;
+        let parts = mime::leaf_parts(&raw);
+
+        let store = self
+            .store
+            .as_mut()
+            .ok_or_else(|| AppError::Validation("MBOX store not initialized".to_string()))?;
+        let body = store
+            .get_message(entry)
+            .map_err(|e| AppError::MboxShell(e.to_string()))?;
+        let attachments: Vec<(&str, &str)> = body
+            .attachments
+            .iter()
+            .map(|a| (a.filename.as_str(), a.content_type.as_str()))
+            .collect();
+        let position = mime::attachment_for_content_id(&parts, &attachments, content_id)
+            .ok_or_else(|| AppError::NotFound(format!("Inline part <{content_id}>")))?;
+        let meta = body.attachments[position].clone();
+
+        let data = store
+            .get_attachment(entry, &meta)
+            .map_err(|e| AppError::MboxShell(e.to_string()))?;
+        Ok(InlinePart {
+            data,
+            content_type: meta.content_type,
+        })
+    }
+
+    pub fn get_saved_searches(&self) -> Vec<SavedSearch> {
+        self.saved_searches.clone()
+    }
+
+    /// Save (or replace by name) a search as a smart folder for the open mailbox.
+    ///
+    /// The query is executed immediately so invalid queries are rejected
+    /// and the returned label carries a live count.
+    pub fn save_search(&mut self, name: &str, query: &str) -> Result<LabelCount, AppError> {
+        let name = name.trim();
+        let query = query.trim();
+        if name.is_empty() {
+            return Err(AppError::Validation(
+                "Saved search name is empty".to_string(),
+            ));
+        }
+        if query.is_empty() {
+            return Err(AppError::Validation(
+                "Saved search query is empty".to_string(),
+            ));
+        }
+
+        let count = self.search_indices(query)?.len();
+        let mbox_path = self
+            .mbox_path
+            .clone()
+            .ok_or_else(|| AppError::Validation("No MBOX file path available".to_string()))?;
+
+        let search = SavedSearch {
+            name: name.to_string(),
+            query: query.to_string(),
+        };
+        let mut searches = self.saved_searches.clone();
+        match searches.iter_mut().find(|s| s.name == name) {
+            Some(existing) => *existing = search,
+            None => searches.push(search),
+        }
+        self.saved_search_store()?.save(&mbox_path, &searches)?;
+
+        self.saved_searches = searches;
+
+        Ok(LabelCount {
+            label: name.to_string(),
+            count,
+            kind: LabelKind::SavedSearch,
+        })
+    }
+
+    pub fn delete_saved_search(&mut self, name: &str) -> Result<(), AppError> {
+        let mbox_path = self
+            .mbox_path
+            .clone()
+            .ok_or_else(|| AppError::Validation("No MBOX file path available".to_string()))?;
+
+        let mut searches = self.saved_searches.clone();
+        let before = searches.len();
+        searches.retain(|s| s.name != name);
+        if searches.len() == before {
+            return Err(AppError::NotFound(format!("saved search {name}")));
+        }
+        self.saved_search_store()?.save(&mbox_path, &searches)?;
+
+        self.saved_searches = searches;
+        Ok(())
+    }
+
+    pub fn get_rules(&self) -> Vec<LabelRule> {
+        self.rules.clone()
+    }
+
+    /// Save (or replace by name) a labelling rule.
+    ///
+    /// Rules are validated first, so a bad pattern is rejected instead of
+    /// silently matching nothing. Labels keep their previous membership until
+    /// `rule_labels` results are applied.
+    pub fn save_rule(&mut self, rule: LabelRule) -> Result<LabelRule, AppError> {
+        CompiledRule::new(&rule)?;
+        let rule = LabelRule {
+            name: rule.name.trim().to_string(),
+            label: rule.label.trim().to_string(),
+            ..rule
+        };
+
+        let mut rules = self.rules.clone();
+        match rules.iter_mut().find(|r| r.name == rule.name) {
+            Some(existing) => *existing = rule.clone(),
+            None => rules.push(rule.clone()),
+        }
+        self.rule_store()?.save(&rules)?;
+
+        self.rules = rules;
+        Ok(rule)
+    }
+
+    pub fn delete_rule(&mut self, name: &str) -> Result<(), AppError> {
+        let mut rules = self.rules.clone();
+        let before = rules.len();
+        rules.retain(|r| r.name != name);
+        if rules.len() == before {
+            return Err(AppError::NotFound(format!("rule {name}")));
+        }
+        self.rule_store()?.save(&rules)?;
+
+        self.rules = rules;
+        Ok(())
+    }
+
+    /// Live count of a rule label.
+    pub fn rule_label_count(&self, label: &str) -> LabelCount {
+        LabelCount {
+            count: self
+                .count_labels()
+                .into_iter()
+                .find(|l| l.kind == LabelKind::Rule && l.label == label)
+                .map_or(0, |l| l.count),
+            label: label.to_string(),
+            kind: LabelKind::Rule,
+        }
+    }
+
+    pub fn close(&mut self) {
+        self.mbox_path = None;
+        self.entries = Vec::new();
+        self.store = None;
+        self.saved_searches = Vec::new();
+        self.virtual_labels = Vec::new();
+        self.rule_matches = Arc::default();
+        self.attachment_text = Arc::default();
+        self.attachment_catalog = Arc::default();
+        self.headers = Arc::default();
+        self.tfidf = Arc::default();
+        self.duplicates = Arc::default();
+        self.languages = Arc::default();
+        self.collapse = DuplicateCollapse::Off;
+        self.hidden = Arc::default();
+        let _ = Spool::new(self.spool_dir()).clear();
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.store.is_some()
+    }
+
+    /// Create a lightweight snapshot with entries and path for search.
+    ///
+    /// Search only needs entries and the mbox path (no store).
+    /// This allows releasing the main lock before `spawn_blocking`.
+    pub fn snapshot_for_search(source: &MboxService) -> Self {
+        Self {
+            mbox_path: source.mbox_path.clone(),
+            entries: source.entries.clone(),
+            store: None,
+            data_dir: None,
+            saved_searches: source.saved_searches.clone(),
+            rules: source.rules.clone(),
+            virtual_labels: Vec::new(),
+            rule_matches: Arc::clone(&source.rule_matches),
+            attachment_text: Arc::clone(&source.attachment_text),
+            attachment_catalog: Arc::clone(&source.attachment_catalog),
+            headers: Arc::clone(&source.headers),
+            tfidf: Arc::clone(&source.tfidf),
+            duplicates: Arc::clone(&source.duplicates),
+            languages: Arc::clone(&source.languages),
+            collapse: source.collapse,
+            hidden: Arc::clone(&source.hidden),
+        }
+    }
+
+    /// Entry indices matching a query, including viewer-side operators.
+    fn search_indices(&self, query: &str) -> Result<Vec<usize>, AppError> {
+        Ok(self
+            .run_query(query)?
+            .into_iter()
+            .map(|hit| hit.index)
+            .collect())
+    }
+
+    /// Run a query: the mboxshell part first, then viewer-side filters,
+    /// collecting the substrings those filters matched.
+    fn run_query(&self, query: &str) -> Result<Vec<MessageMatches>, AppError> {
+        let parsed = query::parse(query);
+        let indices = if parsed.base.trim().is_empty() && !parsed.filters.is_empty() {
+            (0..self.entries.len()).collect()
+        } else {
+            self.mboxshell_indices(&parsed.base)?
+        };
+        let mut hits: Vec<MessageMatches> = indices
+            .into_iter()
+            .filter(|&i| !self.is_hidden(i))
+            .map(|index| MessageMatches {
+                index,
+                matches: Vec::new(),
+            })
+            .collect();
+
+        for filter in &parsed.filters {
+            let mut found: HashMap<usize, Vec<MatchedText>> = HashMap::new();
+            match filter {
+                QueryFilter::Attachment(phrase) => {
+                    let index = self.attachment_text()?;
+                    for hit in index.find(phrase) {
+                        let text = index
+                            .text(hit.email_index, hit.attachment_index)
+                            .map(|t| t[hit.start..hit.end].to_string())
+                            .unwrap_or_default();
+                        found.entry(hit.email_index).or_default().push(MatchedText {
+                            field: MatchField::Attachment,
+                            start: hit.start,
+                            end: hit.end,
+                            text,
+                            attachment_index: Some(hit.attachment_index),
+                        });
+                    }
+                }
+                QueryFilter::Category(category) => {
+                    let categories = self.classify_categories()?;
+                    for hit in &hits {
+                        if categories[hit.index] == *category {
+                            found.insert(hit.index, Vec::new());
+                        }
+                    }
+                }
+                QueryFilter::List(value) => {
+                    let headers = self.message_headers()?;
+                    for hit in &hits {
+                        let list_id = headers[hit.index].list_id.as_deref();
+                        if list_id.is_some_and(|id| mailing_lists::matches(id, value)) {
+                            found.insert(hit.index, Vec::new());
+                        }
+                    }
+                }
+                QueryFilter::Language(code) => {
+                    // Messages whose language was not told are stored without a code
+                    let code = (code != UNKNOWN_LANGUAGE).then_some(code.as_str());
+                    let languages = self.languages()?;
+                    for hit in &hits {
+                        if languages[hit.index] == code {
+                            found.insert(hit.index, Vec::new());
+                        }
+                    }
+                }
+            }
+            hits.retain_mut(|hit| match found.remove(&hit.index) {
+                Some(matches) => {
+                    hit.matches.extend(matches);
+                    true
+                }
+                None => false,
+            });
+        }
+
+        Ok(hits)
+    }
+
+    /// Run a query through mboxshell and return matching entry indices.
+    fn mboxshell_indices(&self, query: &str) -> Result<Vec<usize>, AppError> {
+        let mbox_path = self
+            .mbox_path
+            .as_ref()
+            .ok_or_else(|| AppError::Validation("No MBOX file path available".to_string()))?;
+
+        let (_parsed_query, matching_indices) =
+            search::execute(mbox_path, &self.entries, query, None)
+                .map_err(|e| AppError::MboxShell(e.to_string()))?;
+
+        Ok(matching_indices)
+    }
+
+    fn facet_record(&self, store: &mut MboxStore, index: usize) -> Result<FacetRecord, AppError> {
+        let entry = &self.entries[index];
+        let mut attachment_types = Vec::new();
+        if entry.has_attachments {
+            let body = store
+                .get_message(entry)
+                .map_err(|e| AppError::MboxShell(e.to_string()))?;
+            attachment_types = body
+                .attachments
+                .iter()
+                .map(|a| facets::mime_essence(&a.content_type))
+                .collect();
+        }
+
+        Ok(FacetRecord {
+            sender: entry.from.address.clone(),
+            labels: entry.labels.clone(),
+            date: entry.date.to_rfc3339(),
+            has_attachment: entry.has_attachments,
+            attachment_types,
+        })
+    }
+
+    /// Raw headers of every entry, read from the MBOX file on first use.
+    fn message_headers(&self) -> Result<&[MessageHeaders], AppError> {
+        if let Some(headers) = self.headers.get() {
+            return Ok(headers);
+        }
+        let mbox_path = self
+            .mbox_path
+            .as_ref()
+            .ok_or_else(|| AppError::Validation("No MBOX file path available".to_string()))?;
+        let headers = headers::read_all(mbox_path, &self.entries)?;
+        Ok(self.headers.get_or_init(|| headers))
+    }
+
+    /// TF-IDF signatures of every body, decoding the whole mailbox on first use.
+    ///
+    /// Bodies that fail to decode get an empty signature; a cancelled build is
+    /// discarded rather than cached.
+    fn tfidf(&self, cancel: &CancelToken) -> Result<&TfIdfIndex, AppError> {
+        if let Some(index) = self.tfidf.get() {
+            return Ok(index);
+        }
+        let mbox_path = self
+            .mbox_path
+            .as_ref()
+            .ok_or_else(|| AppError::Validation("No MBOX file path available".to_string()))?;
+        let mut store =
+            MboxStore::open(mbox_path).map_err(|e| AppError::MboxShell(e.to_string()))?;
+
+        let index = TfIdfIndex::build(
+            (0..self.entries.len())
+                .take_while(|_| !cancel.is_cancelled())
+                .map(|i| quoting::fresh_text(&self.body_text(&mut store, i).unwrap_or_default())),
+        );
+        cancel.check()?;
+        Ok(self.tfidf.get_or_init(|| index))
+    }
+
+    /// Language of every message (subject plus unquoted body), detected on first use.
+    fn languages(&self) -> Result<&[Option<&'static str>], AppError> {
+        if let Some(languages) = self.languages.get() {
+            return Ok(languages);
+        }
+        let mbox_path = self
+            .mbox_path
+            .as_ref()
+            .ok_or_else(|| AppError::Validation("No MBOX file path available".to_string()))?;
+        let mut store =
+            MboxStore::open(mbox_path).map_err(|e| AppError::MboxShell(e.to_string()))?;
+
+        let detected = (0..self.entries.len())
+            .map(|i| {
+                let body = self.body_text(&mut store, i).unwrap_or_default();
+                let text = format!(
+                    "{}\n{}",
+                    self.entries[i].subject,
+                    quoting::fresh_text(&body)
+                );
+                language::detect(&text)
+            })
+            .collect();
+        Ok(self.languages.get_or_init(|| detected))
+    }
+
+    /// Visible messages per language, or nothing if detection has not run yet.
+    fn language_counts(&self) -> Vec<LanguageCount> {
+        let Some(languages) = self.languages.get() else {
+            return Vec::new();
+        };
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for i in self.visible_indices() {
+            *counts
+                .entry(languages[i].unwrap_or(UNKNOWN_LANGUAGE))
+                .or_insert(0) += 1;
+        }
+        let mut counts: Vec<LanguageCount> = counts
+            .into_iter()
+            .map(|(language, count)| LanguageCount {
+                language: language.to_string(),
+                count,
+            })
+            .collect();
+        counts.sort_by(|a, b| b.count.cmp(&a.count).then(a.language.cmp(&b.language)));
+        counts
+    }
+
+    /// Duplicate groups, fingerprinting every message on first use.
+    fn duplicate_groups(&self) -> Result<&DuplicateGroups, AppError> {
+        if let Some(groups) = self.duplicates.get() {
+            return Ok(groups);
+        }
+        let mbox_path = self
+            .mbox_path
+            .as_ref()
+            .ok_or_else(|| AppError::Validation("No MBOX file path available".to_string()))?;
+        let headers = self.message_headers()?;
+        let mut store =
+            MboxStore::open(mbox_path).map_err(|e| AppError::MboxShell(e.to_string()))?;
+
+        let prints: Vec<Fingerprint> = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let body = store.get_message(entry).ok();
+                Fingerprint::of(&DuplicateInput {
+                    message_id: headers[i].message_id.clone(),
+                    sender: entry.from.address.clone(),
+                    subject: entry.subject.clone(),
+                    body: body
+                        .as_ref()
+                        .map(|b| body_text::plain_text(b.text.as_deref(), b.html.as_deref())),
+                    date: entry.date.timestamp(),
+                    attachments: body
+                        .as_ref()
+                        .map(|b| {
+                            b.attachments
+                                .iter()
+                                .map(|a| (a.filename.clone(), a.size))
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    offset: entry.offset,
+                })
+            })
+            .collect();
+
+        let groups = duplicates::detect(&prints);
+        Ok(self.duplicates.get_or_init(|| groups))
+    }
+
+    /// The attachment text index, extracting it on first use.
+    fn attachment_text(&self) -> Result<&AttachmentTextIndex, AppError> {
+        if let Some(index) = self.attachment_text.get() {
+            return Ok(index);
+        }
+        let index = self.build_attachment_text_index()?;
+        Ok(self.attachment_text.get_or_init(|| index))
+    }
+
+    /// Extract text from every supported attachment through `MboxStore::get_attachment`.
+    ///
+    /// Messages or parts that fail to decode are skipped so one damaged
+    /// message does not block indexing the rest of the archive.
+    fn build_attachment_text_index(&self) -> Result<AttachmentTextIndex, AppError> {
+        let mbox_path = self
+            .mbox_path
+            .as_ref()
+            .ok_or_else(|| AppError::Validation("No MBOX file path available".to_string()))?;
+        let mut store =
+            MboxStore::open(mbox_path).map_err(|e| AppError::MboxShell(e.to_string()))?;
+
+        let mut documents = Vec::new();
+        for (email_index, entry) in self.entries.iter().enumerate() {
+            if !entry.has_attachments {
+                continue;
+            }
+            let wanted = |part: &ListedPart| {
+                part.size <= MAX_EXTRACT_BYTES
+                    && attachment_text::is_extractable(&part.filename, &part.content_type)
+            };
+            // Messages that fail to decode are left out
+            let _ = visit_attachments(&mut store, entry, wanted, |part, data| {
+                let Ok(data) = data else {
+                    return;
+                };
+                if let Some(text) =
+                    attachment_text::extract_text(&part.filename, &part.content_type, &data)
+                {
+                    documents.push(AttachmentText {
+                        email_index,
+                        attachment_index: part.part_index,
+                        text,
+                    });
+                }
+            });
+        }
+
+        Ok(AttachmentTextIndex::new(documents))
+    }
+
+    /// Every attachment with its content hash, decoded on first use.
+    ///
+    /// Parts that fail to decode are listed without a hash rather than
+    /// dropped, so the catalog still shows them.
+    fn attachment_catalog(&self) -> Result<&[CatalogRecord], AppError> {
+        if let Some(records) = self.attachment_catalog.get() {
+            return Ok(records);
+        }
+        let mbox_path = self
+            .mbox_path
+            .as_ref()
+            .ok_or_else(|| AppError::Validation("No MBOX file path available".to_string()))?;
+        let mut store =
+            MboxStore::open(mbox_path).map_err(|e| AppError::MboxShell(e.to_string()))?;
+
+        let mut records = Vec::new();
+        for (email_index, entry) in self.entries.iter().enumerate() {
+            if !entry.has_attachments {
+                continue;
+            }
+            // Messages that fail to decode are left out
+            let _ = visit_attachments(
+                &mut store,
+                entry,
+                |_| true,
+                |part, data| {
+                    let data = data.ok();
+                    records.push(CatalogRecord {
+                        email_index,
+                        attachment_index: part.part_index,
+                        size: data.as_ref().map_or(part.size, |d| d.len() as u64),
+                        sha256: data.as_deref().map(attachment_files::sha256_hex),
+                        filename: part.filename,
+                        content_type: part.content_type,
+                    });
+                },
+            );
+        }
+        Ok(self.attachment_catalog.get_or_init(|| records))
+    }
+
+    /// Decode a message with a store of its own, surfacing the error the
+    /// catalog skipped over.
+    fn check_decodable(&self, index: usize) -> Result<(), AppError> {
+        let mbox_path = self
+            .mbox_path
+            .as_ref()
+            .ok_or_else(|| AppError::Validation("No MBOX file path available".to_string()))?;
+        let mut store =
+            MboxStore::open(mbox_path).map_err(|e| AppError::MboxShell(e.to_string()))?;
+        store
+            .get_message(&self.entries[index])
+            .map(|_| ())
+            .map_err(|e| AppError::MboxShell(format!("Email {index} could not be decoded: {e}")))
+    }
+
+    /// A catalog record with its parent message's details.
+    fn catalog_attachment(&self, record: &CatalogRecord) -> CatalogAttachment {
+        let entry = &self.entries[record.email_index];
+        CatalogAttachment {
+            email_index: record.email_index,
+            attachment_index: record.attachment_index,
+            filename: record.filename.clone(),
+            content_type: record.content_type.clone(),
+            size: record.size,
+            sha256: record.sha256.clone(),
+            date: entry.date.to_rfc3339(),
+            from_name: entry.from.display_name.clone(),
+            from_address: entry.from.address.clone(),
+            subject: entry.subject.clone(),
+        }
+    }
+
+    /// Plain-text rendition of a message body.
+    fn body_text(&self, store: &mut MboxStore, index: usize) -> Result<String, AppError> {
+        let body = store
+            .get_message(&self.entries[index])
+            .map_err(|e| AppError::MboxShell(e.to_string()))?;
+        Ok(body_text::plain_text(
+            body.text.as_deref(),
+            body.html.as_deref(),
+        ))
+    }
+
+    /// Decoded attachments live under the data directory when one is set.
+    fn spool_dir(&self) -> PathBuf {
+        match &self.data_dir {
+            Some(dir) => dir.join("spool"),
+            None => std::env::temp_dir().join("mboxviewer-spool"),
+        }
+    }
+
+    fn rule_store(&self) -> Result<RuleStore, AppError> {
+        self.data_dir
+            .as_deref()
+            .map(RuleStore::new)
+            .ok_or_else(|| AppError::Validation("No data directory configured".to_string()))
+    }
+
+    /// Evaluate the enabled rules over the open mailbox.
+    ///
+    /// Bodies are decoded only when some rule has a body condition, so on a
+    /// live mailbox this runs on a snapshot off the main thread. Rules that
+    /// fail to compile (e.g. edited by hand) are skipped.
+    pub fn rule_labels(&self) -> RuleLabels {
+        let mut result = RuleLabels {
+            mbox_path: self.mbox_path.clone(),
+            rules: self.rules.clone(),
+            matches: HashMap::new(),
+            labels: Vec::new(),
+        };
+        let compiled: Vec<CompiledRule> = self
+            .rules
+            .iter()
+            .filter(|r| r.enabled)
+            .filter_map(|r| CompiledRule::new(r).ok())
+            .collect();
+        if compiled.is_empty() || self.entries.is_empty() {
+            return result;
+        }
+        let mut store = match (
+            compiled.iter().any(CompiledRule::needs_body),
+            &self.mbox_path,
+        ) {
+            (true, Some(path)) => MboxStore::open(path).ok(),
+            _ => None,
+        };
+
+        let mut matches: HashMap<usize, Vec<String>> = HashMap::new();
+        let mut labels: Vec<VirtualLabel> = Vec::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            let input = RuleInput {
+                from: format_address(&entry.from.display_name, &entry.from.address),
+                from_address: &entry.from.address,
+                to: entry
+                    .to
+                    .iter()
+                    .chain(&entry.cc)
+                    .map(|a| format_address(&a.display_name, &a.address))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                subject: &entry.subject,
+                labels: &entry.labels,
+                has_attachments: entry.has_attachments,
+            };
+            let mut body: Option<String> = None;
+            let mut read_body = || {
+                body.get_or_insert_with(|| match store.as_mut() {
+                    Some(store) => self.body_text(store, index).unwrap_or_default(),
+                    None => String::new(),
+                })
+                .clone()
+            };
+            for rule in &compiled {
+                let labelled = matches.get(&index).is_some_and(|l| l.contains(&rule.label));
+                if labelled || !rule.matches(&input, &mut read_body) {
+                    continue;
+                }
+                matches.entry(index).or_default().push(rule.label.clone());
+                match labels.iter_mut().find(|v| v.name == rule.label) {
+                    Some(label) => label.indices.push(index),
+                    None => labels.push(VirtualLabel {
+                        name: rule.label.clone(),
+                        kind: LabelKind::Rule,
+                        indices: vec![index],
+                    }),
+                }
+            }
+        }
+
+        result.matches = matches;
+        result.labels = labels;
+        result
+    }
+
+    /// Swap in rule labels from `rule_labels`, unless the mailbox or the
+    /// rules changed while they were evaluated.
+    pub fn apply_rule_labels(&mut self, labels: RuleLabels) {
+        if labels.mbox_path != self.mbox_path || labels.rules != self.rules {
+            return;
+        }
+        self.virtual_labels.retain(|v| v.kind != LabelKind::Rule);
+        self.virtual_labels.extend(labels.labels);
+        self.rule_matches = Arc::new(labels.matches);
+    }
+
+    fn saved_search_store(&self) -> Result<SavedSearchStore, AppError> {
+        self.data_dir
+            .as_deref()
+            .map(SavedSearchStore::new)
+            .ok_or_else(|| AppError::Validation("No data directory configured".to_string()))
+    }
+
+    /// Smart folder membership of the current saved searches.
+    ///
+    /// Queries may decode bodies, so on a live mailbox this runs on a snapshot
+    /// off the main thread. A query that fails to run yields an empty folder
+    /// rather than an error, so one bad saved search cannot break opening the
+    /// mailbox.
+    pub fn saved_search_labels(&self) -> SavedSearchLabels {
+        SavedSearchLabels {
+            mbox_path: self.mbox_path.clone(),
+            searches: self.saved_searches.clone(),
+            hidden: Arc::clone(&self.hidden),
+            labels: self
+                .saved_searches
+                .iter()
+                .map(|s| VirtualLabel {
+                    name: s.name.clone(),
+                    kind: LabelKind::SavedSearch,
+                    indices: self.search_indices(&s.query).unwrap_or_default(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Swap in smart folders from `saved_search_labels`, unless the mailbox,
+    /// the saved searches or the collapse mode changed while they were
+    /// evaluated.
+    pub fn apply_saved_search_labels(&mut self, labels: SavedSearchLabels) {
+        if labels.mbox_path != self.mbox_path
+            || labels.searches != self.saved_searches
+            || !Arc::ptr_eq(&labels.hidden, &self.hidden)
+        {
+            return;
+        }
+        self.virtual_labels
+            .retain(|v| v.kind != LabelKind::SavedSearch);
+        self.virtual_labels.extend(labels.labels);
+    }
+
+    /// Listing entry for message `index`, with its language once detected.
+    fn email_entry(&self, index: usize) -> EmailEntry {
+        let mut email = EmailEntry::from(&self.entries[index]);
+        email.language = self
+            .languages
+            .get()
+            .and_then(|languages| languages[index])
+            .map(str::to_string);
+        email.rule_labels = self.rule_matches.get(&index).cloned().unwrap_or_default();
+        email
+    }
+
+    fn check_index(&self, index: usize) -> Result<(), AppError> {
+        if self.entries.is_empty() {
+            return Err(AppError::Validation(
+                "No MBOX file is currently open".to_string(),
+            ));
+        }
+        if index >= self.entries.len() {
+            return Err(AppError::Validation(format!(
+                "Invalid email index: {index}"
+            )));
+        }
+        Ok(())
+    }
+
+    fn is_hidden(&self, index: usize) -> bool {
+        self.hidden.get(index).copied().unwrap_or(false)
+    }
+
+    fn hidden_count(&self) -> usize {
+        self.hidden.iter().filter(|&&h| h).count()
+    }
+
+    /// Indices of messages not hidden by the duplicate collapse mode.
+    fn visible_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.entries.len()).filter(|&i| !self.is_hidden(i))
+    }
+
+    /// Mailbox statistics over the visible messages.
+    pub fn stats(&self) -> MboxStats {
+        MboxStats {
+            total_messages: self.get_email_count(),
+            total_with_attachments: self
+                .visible_indices()
+                .filter(|&i| self.entries[i].has_attachments)
+                .count(),
+            labels: self.count_labels(),
+            hidden_duplicates: self.hidden_count(),
+            languages: self.language_counts(),
+        }
+    }
+
+    fn count_labels(&self) -> Vec<LabelCount> {
+        let mut label_counts: HashMap<String, usize> = HashMap::new();
+        for entry in self.visible_indices().map(|i| &self.entries[i]) {
+            for label in &entry.labels {
+                *label_counts.entry(label.clone()).or_insert(0) += 1;
+            }
+        }
+        let mut labels: Vec<LabelCount> = label_counts
+            .into_iter()
+            .map(|(label, count)| LabelCount {
+                label,
+                count,
+                kind: LabelKind::Gmail,
+            })
+            .collect();
+        labels.sort_by(|a, b| b.count.cmp(&a.count));
+
+        labels.extend(self.virtual_labels.iter().map(|v| LabelCount {
+            label: v.name.clone(),
+            count: v.indices.iter().filter(|&&i| !self.is_hidden(i)).count(),
+            kind: v.kind,
+        }));
+        labels
+    }
+}
+
+/// Topic clusters computed on a snapshot, tagged with the mailbox they belong to.
+pub struct TopicModel {
+    mbox_path: Option<PathBuf>,
+    clusters: Vec<Cluster>,
+}
+
+/// "Topic 2: invoice, payment, acme" — the first keywords make it recognizable.
+fn topic_label(number: usize, keywords: &[String]) -> String {
+    if keywords.is_empty() {
+        return format!("Topic {number}");
+    }
+    let shown: Vec<&str> = keywords.iter().take(3).map(String::as_str).collect();
+    format!("Topic {number}: {}", shown.join(", "))
+}
+
+/// The TNEF (winmail.dat) parts of a message, decoded, with their part
+/// indices, plus how many parts mboxshell lists.
+///
+/// Files unpacked from them are numbered after mboxshell's parts, in order.
+fn unpack_tnef(
+    store: &mut MboxStore,
+    entry: &MailEntry,
+) -> Result<(usize, Vec<(usize, TnefMessage)>), AppError> {
+    let attachments = store
+        .get_message(entry)
+        .map_err(|e| AppError::MboxShell(e.to_string()))?
+        .attachments
+        .clone();
+    let mut unpacked = Vec::new();
+    for (part, meta) in attachments.iter().enumerate() {
+        if !tnef::is_tnef(&meta.filename, &meta.content_type) || meta.size > mime::MAX_MESSAGE_BYTES
+        {
+            continue;
+        }
+        let Ok(data) = store.get_attachment(entry, meta) else {
+            continue;
+        };
+        if let Some(message) = tnef::parse(&data) {
+            unpacked.push((part, message));
+        }
+    }
+    Ok((attachments.len(), unpacked))
+}
+
+/// An attachment as the viewer lists it, before decoding.
+struct ListedPart {
+    part_index: usize,
+    filename: String,
+    content_type: String,
+    size: u64,
+}
+
+/// Decode a message's attachments one at a time, as `get_email_body` lists
+/// them: winmail.dat parts are replaced by the files they carry.
+///
+/// Only parts `wanted` accepts are decoded and passed to `visit`.
+fn visit_attachments(
+    store: &mut MboxStore,
+    entry: &MailEntry,
+    wanted: impl Fn(&ListedPart) -> bool,
+    mut visit: impl FnMut(ListedPart, Result<Vec<u8>, AppError>),
+) -> Result<(), AppError> {
+    let (listed, unpacked) = unpack_tnef(store, entry)?;
+    let attachments = store
+        .get_message(entry)
+        .map_err(|e| AppError::MboxShell(e.to_string()))?
+        .attachments
+        .clone();
+    for (part_index, meta) in attachments.iter().enumerate() {
+        if unpacked
+            .iter()
+            .any(|(tnef_part, _)| *tnef_part == part_index)
+        {
+            continue;
+        }
+        let part = ListedPart {
+            part_index,
+            filename: meta.filename.clone(),
+            content_type: meta.content_type.clone(),
+            size: meta.size,
+        };
+        if wanted(&part) {
+            let data = store
+                .get_attachment(entry, meta)
+                .map_err(|e| AppError::MboxShell(e.to_string()));
+            visit(part, data);
+        }
+    }
+
+    let files = unpacked
+        .into_iter()
+        .flat_map(|(_, message)| message.attachments);
+    for (part_index, file) in (listed..).zip(files) {
+        let part = ListedPart {
+            part_index,
+            filename: file.filename,
+            content_type: file.content_type,
+            size: file.data.len() as u64,
+        };
+        if wanted(&part) {
+            visit(part, Ok(file.data));
+        }
+    }
+    Ok(())
+}
+
+/// A file unpacked from a message's winmail.dat parts, by its part index.
+fn unpacked_attachment(
+    store: &mut MboxStore,
+    entry: &MailEntry,
+    attachment_index: usize,
+) -> Result<TnefAttachment, AppError> {
+    let (listed, unpacked) = unpack_tnef(store, entry)?;
+    attachment_index
+        .checked_sub(listed)
+        .and_then(|n| {
+            unpacked
+                .into_iter()
+                .flat_map(|(_, message)| message.attachments)
+                .nth(n)
+        })
+        .ok_or_else(|| {
+            AppError::Validation(format!("Invalid attachment index: {attachment_index}"))
+        })
+}
+
+/// An attachment of a message embedded in part `attachment_index`, found by
+/// following `nested` through the embedded messages.
+fn embedded_attachment(
+    store: &mut MboxStore,
+    entry: &MailEntry,
+    attachment_index: usize,
+    nested: &[usize],
+) -> Result<rfc822::ParsedPart, AppError> {
+    let body = store
+        .get_message(entry)
+        .map_err(|e| AppError::MboxShell(e.to_string()))?;
+    let meta = body
+        .attachments
+        .get(attachment_index)
+        .filter(|meta| rfc822::is_message(&meta.content_type))
+        .filter(|meta| meta.size <= mime::MAX_MESSAGE_BYTES)
+        .cloned()
+        .ok_or_else(|| {
+            AppError::Validation(format!(
+                "Attachment {attachment_index} is not an embedded message"
+            ))
+        })?;
+    let data = store
+        .get_attachment(entry, &meta)
+        .map_err(|e| AppError::MboxShell(e.to_string()))?;
+    rfc822::nested_part(&data, nested)
+        .ok_or_else(|| AppError::Validation(format!("Invalid nested attachment path: {nested:?}")))
+}
+
+fn format_address(name: &str, address: &str) -> String {
+    if name.is_empty() {
+        address.to_string()
+    } else {
+        format!("{name} <{address}>")
+    }
+}
+
+impl Default for MboxService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{RuleCondition, RuleField, RuleOperator};
+    use std::path::Path;
+
+    #[test]
+    fn new_has_no_mbox_path() {
+        let service = MboxService::new();
+        assert!(service.mbox_path.is_none());
+    }
+
+    #[test]
+    fn new_has_empty_entries() {
+        let service = MboxService::new();
+        assert!(service.entries.is_empty());
+    }
+
+    #[test]
+    fn new_has_no_store() {
+        let service = MboxService::new();
+        assert!(service.store.is_none());
+    }
+
+    #[test]
+    fn default_is_equivalent_to_new() {
+        let service = MboxService::default();
+        assert!(service.mbox_path.is_none());
+        assert!(service.entries.is_empty());
+        assert!(service.store.is_none());
+    }
+
+    #[test]
+    fn open_returns_not_found_for_nonexistent_path() {
+        let mut service = MboxService::new();
+        let result = service.open(Path::new("/nonexistent/file.mbox"));
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert_eq!(err.to_string(), "Not found: /nonexistent/file.mbox");
+    }
+
+    #[test]
+    fn get_email_count_returns_zero_when_no_file_open() {
+        let service = MboxService::new();
+        assert_eq!(service.get_email_count(), 0);
+    }
+
+    #[test]
+    fn get_emails_returns_validation_error_when_no_file_open() {
+        let service = MboxService::new();
+        let result = service.get_emails(0, 10);
+        assert!(result.is_err());
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Validation error: No MBOX file is currently open"
+        );
+    }
+
+    #[test]
+    fn get_email_body_returns_validation_error_when_no_file_open() {
+        let mut service = MboxService::new();
+        let result = service.get_email_body(0, false);
+        assert!(result.is_err());
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Validation error: No MBOX file is currently open"
+        );
+    }
+
+    #[test]
     fn get_email_body_returns_not_found_for_out_of_bounds_index() {
         let mut service = MboxService::new();
         // Simulate having entries but no store — set entries manually
         service.entries = vec![]; // empty, so any index is out of bounds
                                   // This should fail with validation since no file is open (empty entries)
-        let result = service.get_email_body(5);
+        let result = service.get_email_body(5, false);
         assert!(result.is_err());
     }
 
@@ -359,16 +2559,297 @@ mod tests {
     }
 
     #[test]
-    fn get_attachment_returns_validation_error_when_no_file_open() {
+    fn search_faceted_returns_validation_error_when_no_file_open() {
+        let service = MboxService::new();
+        let result = service.search_faceted("test", &[], None);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Validation error: No MBOX file is currently open"
+        );
+    }
+
+    #[test]
+    fn search_pattern_returns_validation_error_when_no_file_open() {
+        let service = MboxService::new();
+        let request = PatternSearch {
+            mode: SearchMode::Regex,
+            pattern: r"INV-\d+".to_string(),
+            fields: vec![MatchField::Subject],
+            case_sensitive: false,
+            max_distance: None,
+            exclude_quoted: false,
+        };
+        let result = service.search_pattern(&request, None);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Validation error: No MBOX file is currently open"
+        );
+    }
+
+    #[test]
+    fn index_attachment_text_returns_validation_error_when_no_file_open() {
+        let service = MboxService::new();
+        let result = service.index_attachment_text();
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Validation error: No MBOX file is currently open"
+        );
+    }
+
+    #[test]
+    fn get_attachment_catalog_returns_validation_error_when_no_file_open() {
+        let service = MboxService::new();
+        let result = service.get_attachment_catalog(&CatalogQuery::default());
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Validation error: No MBOX file is currently open"
+        );
+    }
+
+    #[test]
+    fn find_attachment_copies_returns_validation_error_when_no_file_open() {
+        let service = MboxService::new();
+        let result = service.find_attachment_copies(0, 0);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Validation error: No MBOX file is currently open"
+        );
+    }
+
+    #[test]
+    fn hit_report_returns_validation_error_when_no_file_open() {
+        let service = MboxService::new();
+        let result = service.hit_report(&["invoice".to_string()]);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Validation error: No MBOX file is currently open"
+        );
+    }
+
+    #[test]
+    fn similar_messages_returns_validation_error_when_no_file_open() {
+        let service = MboxService::new();
+        let result = service.similar_messages(0, 10);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Validation error: No MBOX file is currently open"
+        );
+    }
+
+    #[test]
+    fn find_duplicates_returns_validation_error_when_no_file_open() {
+        let service = MboxService::new();
+        let result = service.find_duplicates();
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Validation error: No MBOX file is currently open"
+        );
+    }
+
+    #[test]
+    fn cluster_topics_returns_validation_error_when_no_file_open() {
+        let service = MboxService::new();
+        let result = service.cluster_topics(None, &CancelToken::default());
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Validation error: No MBOX file is currently open"
+        );
+    }
+
+    #[test]
+    fn topic_label_shows_first_keywords() {
+        let keywords: Vec<String> = ["invoice", "payment", "acme", "due"]
+            .iter()
+            .map(|k| k.to_string())
+            .collect();
+        assert_eq!(topic_label(2, &keywords), "Topic 2: invoice, payment, acme");
+        assert_eq!(topic_label(1, &[]), "Topic 1");
+    }
+
+    #[test]
+    fn get_emails_by_language_returns_validation_error_when_no_file_open() {
+        let service = MboxService::new();
+        let result = service.get_emails_by_language("es");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Validation error: No MBOX file is currently open"
+        );
+    }
+
+    #[test]
+    fn get_mailing_lists_returns_validation_error_when_no_file_open() {
+        let service = MboxService::new();
+        let result = service.get_mailing_lists();
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Validation error: No MBOX file is currently open"
+        );
+    }
+
+    #[test]
+    fn save_rule_requires_data_dir() {
         let mut service = MboxService::new();
-        let result = service.get_attachment(0, 0);
-        assert!(result.is_err());
+        let rule = LabelRule {
+            name: "Invoices".to_string(),
+            label: "Invoices".to_string(),
+            conditions: vec![RuleCondition {
+                field: RuleField::Subject,
+                operator: RuleOperator::Contains,
+                value: "invoice".to_string(),
+            }],
+            match_any: false,
+            enabled: true,
+        };
+        assert_eq!(
+            service.save_rule(rule).unwrap_err().to_string(),
+            "Validation error: No data directory configured"
+        );
+        assert!(service.get_rules().is_empty());
+    }
+
+    #[test]
+    fn stale_rule_labels_are_not_applied() {
+        let mut service = MboxService::new();
+        service.virtual_labels.push(VirtualLabel {
+            name: "Invoices".to_string(),
+            kind: LabelKind::Rule,
+            indices: vec![0],
+        });
+        let stale = service.rule_labels();
+        service.rules.push(LabelRule {
+            name: "Invoices".to_string(),
+            label: "Invoices".to_string(),
+            conditions: vec![RuleCondition {
+                field: RuleField::Body,
+                operator: RuleOperator::Contains,
+                value: "invoice".to_string(),
+            }],
+            match_any: false,
+            enabled: true,
+        });
+
+        service.apply_rule_labels(stale);
+        assert_eq!(service.virtual_labels.len(), 1);
+        let fresh = service.rule_labels();
+        service.apply_rule_labels(fresh);
+        assert!(service.virtual_labels.is_empty());
+    }
+
+    #[test]
+    fn stale_saved_search_labels_are_not_applied() {
+        let mut service = MboxService::new();
+        service.saved_searches.push(SavedSearch {
+            name: "Billing".to_string(),
+            query: "from:billing@".to_string(),
+        });
+        let stale = service.saved_search_labels();
+        service.hidden = Arc::new(Vec::new());
+
+        service.apply_saved_search_labels(stale);
+        assert!(service.virtual_labels.is_empty());
+        let fresh = service.saved_search_labels();
+        service.apply_saved_search_labels(fresh);
+        assert_eq!(service.virtual_labels.len(), 1);
+        assert_eq!(service.virtual_labels[0].kind, LabelKind::SavedSearch);
+    }
+
+    #[test]
+    fn set_category_labels_returns_validation_error_when_no_file_open() {
+        let mut service = MboxService::new();
+        let result = service.set_category_labels();
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Validation error: No MBOX file is currently open"
+        );
+        assert!(service.get_labels().is_empty());
+    }
+
+    #[test]
+    fn summarize_thread_returns_validation_error_when_no_file_open() {
+        let service = MboxService::new();
+        let result = service.summarize_thread(0, 3);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Validation error: No MBOX file is currently open"
+        );
+    }
+
+    #[test]
+    fn set_duplicate_collapse_returns_validation_error_when_no_file_open() {
+        let mut service = MboxService::new();
+        let result = service.set_duplicate_collapse(DuplicateCollapse::Exact);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Validation error: No MBOX file is currently open"
+        );
+        assert_eq!(service.collapse, DuplicateCollapse::Off);
+    }
+
+    #[test]
+    fn message_source_returns_validation_error_when_no_file_open() {
+        let service = MboxService::new();
+        let result = service.message_source(0);
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Validation error: No MBOX file is currently open"
+        );
+    }
+
+    #[test]
+    fn extract_attachments_returns_validation_error_when_no_file_open() {
+        let service = MboxService::new();
+        let request = ExtractionRequest {
+            query: String::new(),
+            destination: std::env::temp_dir().display().to_string(),
+            template: None,
+            file_types: Vec::new(),
+            conflict: ConflictPolicy::default(),
+        };
+        let result = service.extract_attachments(&request, &CancelToken::default(), &mut |_| {});
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Validation error: No MBOX file is currently open"
+        );
+    }
+
+    #[test]
+    fn get_inline_part_returns_validation_error_when_no_file_open() {
+        let mut service = MboxService::new();
+        let result = service.get_inline_part(0, "logo@example");
         assert_eq!(
             result.unwrap_err().to_string(),
             "Validation error: No MBOX file is currently open"
         );
     }
 
+    #[test]
+    fn save_search_returns_validation_error_for_empty_name() {
+        let mut service = MboxService::new();
+        let result = service.save_search("  ", "from:billing@");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Validation error: Saved search name is empty"
+        );
+    }
+
+    #[test]
+    fn save_search_returns_validation_error_when_no_file_open() {
+        let mut service = MboxService::new();
+        let result = service.save_search("Billing", "from:billing@");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Validation error: No MBOX file path available"
+        );
+    }
+
+    #[test]
+    fn get_emails_by_label_kind_returns_not_found_for_unknown_smart_folder() {
+        let mut service = MboxService::new();
+        service.entries = Vec::new();
+        let result = service.get_emails_by_label_kind("Billing", LabelKind::SavedSearch);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn is_open_returns_false_when_no_file_open() {
         let service = MboxService::new();
@@ -0,0 +1,294 @@
+//! Lightweight MIME structure scanning of raw messages.
+//!
+//! mboxshell decodes bodies and attachments but does not expose part headers
+//! such as `Content-ID`. This walks the multipart tree of the raw message to
+//! recover them. Part bodies are decoded only for embedded messages, in
+//! `rfc822`.
+
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use super::headers;
+use crate::error::AppError;
+
+/// Messages larger than this are truncated before scanning.
+pub const MAX_MESSAGE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Multipart nesting deeper than this is not followed.
+pub const MAX_DEPTH: usize = 16;
+
+/// Headers of a single (non-multipart) MIME part.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MimePart {
+    /// Media type without parameters, lowercased (`image/png`)
+    pub content_type: String,
+    /// `Content-Disposition` type, lowercased (`inline`, `attachment`)
+    pub disposition: Option<String>,
+    /// Disposition `filename` or Content-Type `name` parameter
+    pub filename: Option<String>,
+    /// `Content-ID` without angle brackets
+    pub content_id: Option<String>,
+}
+
+impl MimePart {
+    /// Whether mail clients list this part as an attachment rather than
+    /// rendering it as the message body.
+    pub fn is_attachment(&self) -> bool {
+        let body_text = matches!(self.content_type.as_str(), "text/plain" | "text/html");
+        !body_text || self.filename.is_some() || self.disposition.as_deref() == Some("attachment")
+    }
+}
+
+/// Read the raw bytes of one message from the MBOX file.
+pub fn read_message(path: &Path, offset: u64, length: u64) -> Result<Vec<u8>, AppError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut raw = Vec::new();
+    reader
+        .take(length.min(MAX_MESSAGE_BYTES))
+        .read_to_end(&mut raw)?;
+    Ok(raw)
+}
+
+/// Leaf parts of a raw message, in document order.
+pub fn leaf_parts(raw: &[u8]) -> Vec<MimePart> {
+    let text = String::from_utf8_lossy(raw);
+    let mut parts = Vec::new();
+    collect_parts(&text, 0, &mut parts);
+    parts
+}
+
+/// The index into `attachments` (mboxshell's `(filename, content_type)`
+/// list) of the part with the given Content-ID.
+///
+/// Parts are matched by position when the attachment-like parts line up with
+/// mboxshell's list, and by filename otherwise.
+pub fn attachment_for_content_id(
+    parts: &[MimePart],
+    attachments: &[(&str, &str)],
+    content_id: &str,
+) -> Option<usize> {
+    let candidates: Vec<&MimePart> = parts.iter().filter(|p| p.is_attachment()).collect();
+    let position = candidates
+        .iter()
+        .position(|p| p.content_id.as_deref() == Some(content_id))?;
+
+    if candidates.len() == attachments.len() {
+        return Some(position);
+    }
+    let filename = candidates[position].filename.as_deref()?;
+    attachments.iter().position(|(name, _)| *name == filename)
+}
+
+fn collect_parts(entity: &str, depth: usize, parts: &mut Vec<MimePart>) {
+    let (header_block, body) = split_entity(entity);
+    let mut part = MimePart {
+        content_type: "text/plain".to_string(),
+        ..MimePart::default()
+    };
+    let mut boundary = None;
+    let mut name = None;
+
+    for (header, value) in headers::unfold(header_block) {
+        match header.to_ascii_lowercase().as_str() {
+            "content-type" => {
+                let (media_type, params) = split_params(&value);
+                part.content_type = media_type;
+                boundary = param(&params, "boundary");
+                name = param(&params, "name");
+            }
+            "content-disposition" => {
+                let (disposition, params) = split_params(&value);
+                part.disposition = Some(disposition);
+                part.filename = param(&params, "filename");
+            }
+            "content-id" => {
+                let id = value.trim().trim_start_matches('<').trim_end_matches('>');
+                part.content_id = Some(id.trim().to_string()).filter(|id| !id.is_empty());
+            }
+            _ => {}
+        }
+    }
+
+    match boundary {
+        Some(boundary) if part.content_type.starts_with("multipart/") && depth < MAX_DEPTH => {
+            for child in split_multipart(body, &boundary) {
+                collect_parts(child, depth + 1, parts);
+            }
+        }
+        _ => {
+            part.filename = part.filename.or(name);
+            parts.push(part);
+        }
+    }
+}
+
+/// Split an entity at the blank line ending its headers.
+pub fn split_entity(entity: &str) -> (&str, &str) {
+    let crlf = entity.find("\r\n\r\n").map(|i| (i, 4));
+    let lf = entity.find("\n\n").map(|i| (i, 2));
+    match crlf.into_iter().chain(lf).min_by_key(|&(i, _)| i) {
+        Some((i, len)) => (&entity[..i], &entity[i + len..]),
+        None => (entity, ""),
+    }
+}
+
+/// Bodies between `--boundary` delimiter lines, up to the closing one.
+pub fn split_multipart<'a>(body: &'a str, boundary: &str) -> Vec<&'a str> {
+    let delimiter = format!("--{boundary}");
+    let mut children = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut pos = 0;
+
+    for line in body.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if let Some(rest) = trimmed.strip_prefix(delimiter.as_str()) {
+            if rest.is_empty() || rest == "--" {
+                if let Some(start) = start {
+                    children.push(&body[start..pos]);
+                }
+                if rest == "--" {
+                    return children;
+                }
+                start = Some(pos + line.len());
+            }
+        }
+        pos += line.len();
+    }
+    if let Some(start) = start {
+        children.push(&body[start..]);
+    }
+    children
+}
+
+/// Split `type/subtype; a=b; c="d"` into the lowercased value and its parameters.
+pub fn split_params(value: &str) -> (String, Vec<(String, String)>) {
+    let mut pieces = value.split(';');
+    let head = pieces.next().unwrap_or_default().trim().to_lowercase();
+    let params = pieces
+        .filter_map(|piece| piece.split_once('='))
+        .map(|(k, v)| {
+            (
+                k.trim().to_lowercase(),
+                v.trim().trim_matches('"').to_string(),
+            )
+        })
+        .collect();
+    (head, params)
+}
+
+/// A parameter value, accepting the RFC 2231 `name*=charset''value` form.
+pub fn param(params: &[(String, String)], name: &str) -> Option<String> {
+    let extended = format!("{name}*");
+    params.iter().find_map(|(k, v)| {
+        if *k == name {
+            Some(v.clone())
+        } else if *k == extended {
+            let encoded = v.splitn(3, '\'').nth(2).unwrap_or(v);
+            Some(percent_decode(encoded))
+        } else {
+            None
+        }
+    })
+}
+
+pub fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = (bytes[i] == b'%')
+            .then(|| value.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match hex {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MESSAGE: &str = "From alice@example.com Mon Jan  1 00:00:00 2024\r\n\
+Subject: Newsletter\r\n\
+Content-Type: multipart/related; boundary=\"rel\"\r\n\
+\r\n\
+--rel\r\n\
+Content-Type: multipart/alternative; boundary=alt\r\n\
+\r\n\
+--alt\r\n\
+Content-Type: text/plain\r\n\
+\r\n\
+Hello\r\n\
+--alt\r\n\
+Content-Type: text/html; charset=utf-8\r\n\
+\r\n\
+<img src=\"cid:logo@example\">\r\n\
+--alt--\r\n\
+--rel\r\n\
+Content-Type: image/png; name=\"logo.png\"\r\n\
+Content-ID: <logo@example>\r\n\
+Content-Disposition: inline\r\n\
+\r\n\
+iVBORw0KGgo=\r\n\
+--rel\r\n\
+Content-Type: application/pdf\r\n\
+Content-Disposition: attachment; filename*=utf-8''Q3%20report.pdf\r\n\
+\r\n\
+JVBERi0=\r\n\
+--rel--\r\n";
+
+    #[test]
+    fn walks_nested_multiparts_in_order() {
+        let parts = leaf_parts(MESSAGE.as_bytes());
+        let types: Vec<&str> = parts.iter().map(|p| p.content_type.as_str()).collect();
+        assert_eq!(
+            types,
+            vec!["text/plain", "text/html", "image/png", "application/pdf"]
+        );
+        assert_eq!(parts[2].content_id.as_deref(), Some("logo@example"));
+        assert_eq!(parts[2].filename.as_deref(), Some("logo.png"));
+        assert_eq!(parts[3].filename.as_deref(), Some("Q3 report.pdf"));
+        assert!(!parts[1].is_attachment());
+        assert!(parts[2].is_attachment());
+    }
+
+    #[test]
+    fn finds_attachment_by_content_id() {
+        let parts = leaf_parts(MESSAGE.as_bytes());
+        let attachments = [
+            ("logo.png", "image/png"),
+            ("Q3 report.pdf", "application/pdf"),
+        ];
+        assert_eq!(
+            attachment_for_content_id(&parts, &attachments, "logo@example"),
+            Some(0)
+        );
+        assert_eq!(
+            attachment_for_content_id(&parts, &attachments[1..], "logo@example"),
+            None
+        );
+        assert_eq!(
+            attachment_for_content_id(&parts, &attachments, "missing@example"),
+            None
+        );
+    }
+
+    #[test]
+    fn single_part_message_is_one_leaf() {
+        let parts = leaf_parts(b"Subject: hi\n\nplain body\n");
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].content_type, "text/plain");
+    }
+}
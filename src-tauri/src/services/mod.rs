@@ -1,5 +1,40 @@
 //! Business logic layer (Tauri-independent).
 
+pub mod archive;
+pub mod attachment_files;
+pub mod attachment_text;
+pub mod body_text;
+pub mod byte_range;
+pub mod cancel;
+pub mod catalog;
+pub mod categories;
+pub mod clustering;
+pub mod csv;
+pub mod duplicates;
+pub mod extraction;
+pub mod facets;
+pub mod headers;
+pub mod hit_report;
+pub mod inline_images;
+pub mod language;
+pub mod mailing_lists;
 pub mod mbox_service;
+pub mod mime;
+pub mod pattern_search;
+pub mod query;
+pub mod quoting;
+pub mod rfc822;
+pub mod rtf;
+pub mod rules;
+pub mod sanitize;
+pub mod saved_searches;
+pub mod scheme;
+pub mod spool;
+pub mod summarize;
+pub mod tfidf;
+pub mod threads;
+pub mod tnef;
+pub mod tokenize;
+pub mod union_find;
 
 pub use mbox_service::MboxService;
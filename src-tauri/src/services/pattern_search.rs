@@ -0,0 +1,254 @@
+//! Regex and fuzzy (edit-distance) text matching.
+//!
+//! The `regex` crate runs in linear time, so there is no catastrophic
+//! backtracking; patterns are still bounded in length and compiled size so
+//! a pasted monster pattern cannot exhaust memory.
+
+use regex::{Regex, RegexBuilder};
+
+use crate::error::AppError;
+use crate::models::SearchMode;
+
+pub const MAX_PATTERN_LEN: usize = 1024;
+pub const MAX_REGEX_SIZE: usize = 1 << 20;
+pub const MAX_DFA_SIZE: usize = 4 << 20;
+
+/// Matches reported per field are capped; the message still counts as a hit.
+pub const MAX_MATCHES_PER_FIELD: usize = 20;
+
+/// A compiled regex or fuzzy pattern.
+pub enum Matcher {
+    Regex(Regex),
+    Fuzzy {
+        /// Lowercased (unless case-sensitive) pattern words
+        terms: Vec<String>,
+        max_distance: Option<usize>,
+        case_sensitive: bool,
+    },
+}
+
+/// A match in some text: which pattern term hit and where (byte offsets).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TermMatch {
+    pub term: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Matcher {
+    pub fn new(
+        mode: SearchMode,
+        pattern: &str,
+        case_sensitive: bool,
+        max_distance: Option<usize>,
+    ) -> Result<Self, AppError> {
+        if pattern.trim().is_empty() {
+            return Err(AppError::Validation("Search pattern is empty".to_string()));
+        }
+        if pattern.len() > MAX_PATTERN_LEN {
+            return Err(AppError::Validation(format!(
+                "Search pattern exceeds {MAX_PATTERN_LEN} characters"
+            )));
+        }
+
+        match mode {
+            SearchMode::Regex => RegexBuilder::new(pattern)
+                .case_insensitive(!case_sensitive)
+                .size_limit(MAX_REGEX_SIZE)
+                .dfa_size_limit(MAX_DFA_SIZE)
+                .build()
+                .map(Matcher::Regex)
+                .map_err(|e| AppError::Validation(format!("Invalid regex: {e}"))),
+            SearchMode::Fuzzy => {
+                let terms: Vec<String> = words(pattern)
+                    .map(|(_, _, w)| fold_case(w, case_sensitive))
+                    .collect();
+                // With no terms every message would vacuously match
+                if terms.is_empty() {
+                    return Err(AppError::Validation(
+                        "Fuzzy pattern contains no words".to_string(),
+                    ));
+                }
+                Ok(Matcher::Fuzzy {
+                    terms,
+                    max_distance,
+                    case_sensitive,
+                })
+            }
+            SearchMode::Query => Err(AppError::Validation(
+                "Query mode is handled by mboxshell search".to_string(),
+            )),
+        }
+    }
+
+    /// Number of terms that must all match for a message to be a hit.
+    pub fn term_count(&self) -> usize {
+        match self {
+            Matcher::Regex(_) => 1,
+            Matcher::Fuzzy { terms, .. } => terms.len(),
+        }
+    }
+
+    /// All matches in `text`, in order of appearance.
+    pub fn find(&self, text: &str) -> Vec<TermMatch> {
+        match self {
+            Matcher::Regex(re) => re
+                .find_iter(text)
+                .filter(|m| !m.is_empty())
+                .map(|m| TermMatch {
+                    term: 0,
+                    start: m.start(),
+                    end: m.end(),
+                })
+                .collect(),
+            Matcher::Fuzzy {
+                terms,
+                max_distance,
+                case_sensitive,
+            } => words(text)
+                .filter_map(|(start, end, word)| {
+                    let word = fold_case(word, *case_sensitive);
+                    terms
+                        .iter()
+                        .position(|term| {
+                            let limit = max_distance.unwrap_or_else(|| default_distance(term));
+                            within_distance(term, &word, limit)
+                        })
+                        .map(|term| TermMatch { term, start, end })
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Allowed typos for a word: none for short words, more for longer ones.
+fn default_distance(term: &str) -> usize {
+    match term.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+fn fold_case(word: &str, case_sensitive: bool) -> String {
+    if case_sensitive {
+        word.to_string()
+    } else {
+        word.to_lowercase()
+    }
+}
+
+/// Alphanumeric words with their byte ranges.
+fn words(text: &str) -> impl Iterator<Item = (usize, usize, &str)> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(move |w| {
+            let start = w.as_ptr() as usize - text.as_ptr() as usize;
+            (start, start + w.len(), w)
+        })
+}
+
+/// Levenshtein distance check with early exit once `limit` is exceeded.
+pub fn within_distance(a: &str, b: &str, limit: usize) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > limit {
+        return false;
+    }
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        if curr.iter().all(|&d| d > limit) {
+            return false;
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()] <= limit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regex_finds_all_matches_case_insensitively() {
+        let m = Matcher::new(SearchMode::Regex, r"inv-\d{4}", false, None).unwrap();
+        let hits = m.find("See INV-2019 and inv-2020.");
+        assert_eq!(hits.len(), 2);
+        assert_eq!((hits[0].start, hits[0].end), (4, 12));
+    }
+
+    #[test]
+    fn invalid_regex_is_a_validation_error() {
+        let err = Matcher::new(SearchMode::Regex, "(unclosed", false, None)
+            .err()
+            .unwrap();
+        assert!(err
+            .to_string()
+            .starts_with("Validation error: Invalid regex"));
+    }
+
+    #[test]
+    fn oversized_regex_is_rejected() {
+        let err = Matcher::new(SearchMode::Regex, r"\w{1000}{1000}", false, None)
+            .err()
+            .unwrap();
+        assert!(err
+            .to_string()
+            .starts_with("Validation error: Invalid regex"));
+    }
+
+    #[test]
+    fn overlong_pattern_is_rejected() {
+        let pattern = "a".repeat(MAX_PATTERN_LEN + 1);
+        assert!(Matcher::new(SearchMode::Fuzzy, &pattern, false, None).is_err());
+    }
+
+    #[test]
+    fn fuzzy_matches_misspelled_names_per_term() {
+        let m = Matcher::new(SearchMode::Fuzzy, "Jonathan Smith", false, None).unwrap();
+        let hits = m.find("Call jonathon smyth today");
+        assert_eq!(m.term_count(), 2);
+        assert_eq!(
+            hits,
+            vec![
+                TermMatch {
+                    term: 0,
+                    start: 5,
+                    end: 13
+                },
+                TermMatch {
+                    term: 1,
+                    start: 14,
+                    end: 19
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn fuzzy_respects_explicit_distance() {
+        let m = Matcher::new(SearchMode::Fuzzy, "invoice", false, Some(0)).unwrap();
+        assert!(m.find("invoise").is_empty());
+        assert_eq!(m.find("Invoice").len(), 1);
+    }
+
+    #[test]
+    fn fuzzy_pattern_without_words_is_rejected() {
+        let result = Matcher::new(SearchMode::Fuzzy, "!!!", false, None);
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn within_distance_counts_edits() {
+        assert!(within_distance("kitten", "sitting", 3));
+        assert!(!within_distance("kitten", "sitting", 2));
+        assert!(within_distance("", "ab", 2));
+    }
+}
@@ -0,0 +1,163 @@
+//! Viewer-side query operators layered on top of mboxshell's syntax.
+//!
+//! Operators mboxshell does not know about (e.g. `attachment:"purchase order"`,
+//! `lang:es`, `list:rust-lang.org` or `category:promotions`) are pulled out of
+//! the query and applied as filters on its result; the rest of the query is
+//! passed through untouched.
+
+use super::language;
+use crate::models::MailCategory;
+
+/// A filter applied after the mboxshell query has run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryFilter {
+    /// Phrase that must occur in the extracted text of an attachment
+    Attachment(String),
+    /// Detected body language, as a code from `language::parse` (or the raw
+    /// lowercased value when it names no known language)
+    Language(String),
+    /// Inbox category inferred from headers
+    Category(MailCategory),
+    /// Mailing list, by `List-Id` or a dot-separated part of it
+    List(String),
+}
+
+/// A query split into its mboxshell part and viewer-side filters.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtendedQuery {
+    pub base: String,
+    pub filters: Vec<QueryFilter>,
+}
+
+pub fn parse(query: &str) -> ExtendedQuery {
+    let mut parsed = ExtendedQuery::default();
+    let mut base = Vec::new();
+
+    for token in tokenize(query) {
+        match token.split_once(':') {
+            Some((op, value)) if op.eq_ignore_ascii_case("attachment") => {
+                let value = value.trim_matches('"');
+                if !value.is_empty() {
+                    parsed
+                        .filters
+                        .push(QueryFilter::Attachment(value.to_string()));
+                }
+            }
+            Some((op, value)) if op.eq_ignore_ascii_case("lang") && !value.is_empty() => {
+                let code =
+                    language::parse(value).map_or_else(|| value.to_lowercase(), str::to_string);
+                parsed.filters.push(QueryFilter::Language(code));
+            }
+            Some((op, value)) if op.eq_ignore_ascii_case("list") && !value.is_empty() => {
+                parsed
+                    .filters
+                    .push(QueryFilter::List(value.trim_matches('"').to_lowercase()));
+            }
+            Some((op, value)) if op.eq_ignore_ascii_case("category") => {
+                match MailCategory::ALL
+                    .into_iter()
+                    .find(|c| c.label().eq_ignore_ascii_case(value))
+                {
+                    Some(category) => parsed.filters.push(QueryFilter::Category(category)),
+                    None => base.push(token),
+                }
+            }
+            _ => base.push(token),
+        }
+    }
+
+    parsed.base = base.join(" ");
+    parsed
+}
+
+/// Split on whitespace, keeping double-quoted sections inside one token.
+fn tokenize(query: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in query.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_query_passes_through() {
+        let parsed = parse("from:billing@ has:attachment  newer_than:1y");
+        assert_eq!(parsed.base, "from:billing@ has:attachment newer_than:1y");
+        assert!(parsed.filters.is_empty());
+    }
+
+    #[test]
+    fn extracts_quoted_attachment_phrase() {
+        let parsed = parse(r#"from:acme attachment:"purchase order" subject:"Q3 report""#);
+        assert_eq!(parsed.base, r#"from:acme subject:"Q3 report""#);
+        assert_eq!(
+            parsed.filters,
+            vec![QueryFilter::Attachment("purchase order".to_string())]
+        );
+    }
+
+    #[test]
+    fn extracts_language_filter() {
+        let parsed = parse("lang:ES invoice lang:spanish lang:xx");
+        assert_eq!(parsed.base, "invoice");
+        assert_eq!(
+            parsed.filters,
+            vec![
+                QueryFilter::Language("es".to_string()),
+                QueryFilter::Language("es".to_string()),
+                QueryFilter::Language("xx".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn extracts_list_filter() {
+        let parsed = parse("list:Users.Rust-Lang.org from:alice");
+        assert_eq!(parsed.base, "from:alice");
+        assert_eq!(
+            parsed.filters,
+            vec![QueryFilter::List("users.rust-lang.org".to_string())]
+        );
+    }
+
+    #[test]
+    fn extracts_known_categories_only() {
+        let parsed = parse("category:Promotions category:work");
+        assert_eq!(parsed.base, "category:work");
+        assert_eq!(
+            parsed.filters,
+            vec![QueryFilter::Category(MailCategory::Promotions)]
+        );
+    }
+
+    #[test]
+    fn operator_name_is_case_insensitive_and_empty_values_are_dropped() {
+        let parsed = parse("Attachment:invoice attachment:");
+        assert_eq!(parsed.base, "");
+        assert_eq!(
+            parsed.filters,
+            vec![QueryFilter::Attachment("invoice".to_string())]
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! Segmenting message bodies into new content, quoted replies, forwarded
+//! messages and signatures.
+//!
+//! Works line by line on the plain-text rendition: `>` prefixes give quote
+//! depth, "On ... wrote:" lines are attached to the quote they introduce,
+//! and Outlook-style "Original Message" / "Forwarded message" banners start
+//! a block that runs to the end of the body.
+
+use crate::models::{BodySegment, SegmentKind};
+
+/// Split a plain-text body into segments, in order.
+pub fn segment(text: &str) -> Vec<BodySegment> {
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    let mut segments: Vec<BodySegment> = Vec::new();
+    let mut pending_attribution: Option<String> = None;
+    let mut in_signature = false;
+
+    for (i, &line) in lines.iter().enumerate() {
+        if let Some(kind) = banner_kind(line) {
+            flush_attribution(&mut segments, &mut pending_attribution);
+            let rest = lines[i + 1..].join("\n");
+            segments.push(BodySegment {
+                kind,
+                text: rest.trim().to_string(),
+                depth: 1,
+                attribution: Some(line.trim().to_string()),
+            });
+            break;
+        }
+
+        let (depth, unquoted) = quote_depth(line);
+        if depth > 0 {
+            in_signature = false;
+            let attribution = pending_attribution.take().map(|a| a.trim().to_string());
+            push_line(
+                &mut segments,
+                SegmentKind::Quote,
+                depth,
+                unquoted,
+                attribution,
+            );
+            continue;
+        }
+
+        if is_attribution(line, lines.get(i + 1).copied()) {
+            flush_attribution(&mut segments, &mut pending_attribution);
+            pending_attribution = Some(line.trim().to_string());
+            continue;
+        }
+        if line.trim().is_empty() {
+            // Blank lines stay with whatever they separate
+            if let Some(pending) = pending_attribution.as_mut() {
+                pending.push('\n');
+            } else if let Some(last) = segments.last_mut() {
+                last.text.push('\n');
+            }
+            continue;
+        }
+        flush_attribution(&mut segments, &mut pending_attribution);
+
+        if is_signature_delimiter(line) {
+            in_signature = true;
+            segments.push(BodySegment {
+                kind: SegmentKind::Signature,
+                text: String::new(),
+                depth: 0,
+                attribution: None,
+            });
+            continue;
+        }
+        let kind = if in_signature {
+            SegmentKind::Signature
+        } else {
+            SegmentKind::Content
+        };
+        push_line(&mut segments, kind, 0, line, None);
+    }
+    flush_attribution(&mut segments, &mut pending_attribution);
+
+    for segment in &mut segments {
+        segment.text = segment.text.trim().to_string();
+    }
+    segments.retain(|s| !s.text.is_empty() || s.attribution.is_some());
+    segments
+}
+
+/// The text the author actually wrote: content segments only.
+pub fn fresh_text(text: &str) -> String {
+    segment(text)
+        .into_iter()
+        .filter(|s| s.kind == SegmentKind::Content)
+        .map(|s| s.text)
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Append a line to the last segment when it continues it, else start one.
+fn push_line(
+    segments: &mut Vec<BodySegment>,
+    kind: SegmentKind,
+    depth: usize,
+    line: &str,
+    attribution: Option<String>,
+) {
+    match segments.last_mut() {
+        Some(last) if last.kind == kind && last.depth == depth && attribution.is_none() => {
+            last.text.push('\n');
+            last.text.push_str(line);
+        }
+        _ => segments.push(BodySegment {
+            kind,
+            text: line.to_string(),
+            depth,
+            attribution,
+        }),
+    }
+}
+
+/// An attribution not followed by a quote is ordinary content after all.
+fn flush_attribution(segments: &mut Vec<BodySegment>, pending: &mut Option<String>) {
+    if let Some(line) = pending.take() {
+        push_line(segments, SegmentKind::Content, 0, &line, None);
+    }
+}
+
+/// Number of leading `>` markers and the line without them.
+fn quote_depth(line: &str) -> (usize, &str) {
+    let mut depth = 0;
+    let mut rest = line.trim_start();
+    while let Some(stripped) = rest.strip_prefix('>') {
+        depth += 1;
+        rest = stripped.trim_start_matches(' ');
+    }
+    (depth, if depth > 0 { rest } else { line })
+}
+
+/// `-- ` (RFC 3676) and the common variant without the trailing space.
+pub fn is_signature_delimiter(line: &str) -> bool {
+    line == "--" || line == "-- "
+}
+
+/// Outlook-style "-----Original Message-----" and forwarded-message banners.
+fn banner_kind(line: &str) -> Option<SegmentKind> {
+    if !line.trim_start().starts_with("---") {
+        return None;
+    }
+    match line.trim().trim_matches('-').trim().to_lowercase().as_str() {
+        "original message" | "mensaje original" | "ursprüngliche nachricht" => {
+            Some(SegmentKind::Quote)
+        }
+        "forwarded message" | "mensaje reenviado" | "weitergeleitete nachricht" => {
+            Some(SegmentKind::Forward)
+        }
+        _ => None,
+    }
+}
+
+/// "On Mon, Jan 1, 2024, Alice wrote:" introducing a quoted block.
+pub fn is_attribution(line: &str, next: Option<&str>) -> bool {
+    let line = line.trim();
+    let introduces_quote =
+        next.is_none_or(|n| n.trim_start().starts_with('>') || n.trim().is_empty());
+    introduces_quote
+        && (line.ends_with("wrote:")
+            || line.ends_with("writes:")
+            || line.ends_with("escribió:")
+            || line.ends_with("schrieb:")
+            || line.ends_with("a écrit :")
+            || line.ends_with("a écrit:"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(segments: &[BodySegment]) -> Vec<(SegmentKind, usize)> {
+        segments.iter().map(|s| (s.kind, s.depth)).collect()
+    }
+
+    #[test]
+    fn strips_quotes_attribution_and_signature() {
+        let text = "Sounds good, see you there.\n\nOn Mon, Jan 1, 2024, Alice wrote:\n> Lunch at noon?\n> Cheers\n\n-- \nBob\nACME Corp";
+        assert_eq!(fresh_text(text), "Sounds good, see you there.");
+    }
+
+    #[test]
+    fn segments_nested_quotes_with_attribution() {
+        let text =
+            "Yes.\n\nOn Tue, Bob wrote:\n> Agreed?\n>> Proposal attached\n> Thanks\n\n-- \nAlice";
+        let segments = segment(text);
+        assert_eq!(
+            kinds(&segments),
+            vec![
+                (SegmentKind::Content, 0),
+                (SegmentKind::Quote, 1),
+                (SegmentKind::Quote, 2),
+                (SegmentKind::Quote, 1),
+                (SegmentKind::Signature, 0),
+            ]
+        );
+        assert_eq!(
+            segments[1].attribution.as_deref(),
+            Some("On Tue, Bob wrote:")
+        );
+        assert_eq!(segments[2].text, "Proposal attached");
+        assert_eq!(segments[4].text, "Alice");
+    }
+
+    #[test]
+    fn banners_start_a_block_to_the_end() {
+        let segments = segment("FYI\n---------- Forwarded message ---------\nFrom: Carol\nHello");
+        assert_eq!(
+            kinds(&segments),
+            vec![(SegmentKind::Content, 0), (SegmentKind::Forward, 1)]
+        );
+        assert_eq!(segments[1].text, "From: Carol\nHello");
+        assert_eq!(
+            fresh_text("Approved.\n-----Original Message-----\nFrom: Alice\nPlease approve."),
+            "Approved."
+        );
+    }
+
+    #[test]
+    fn keeps_lines_that_only_mention_writing() {
+        let text = "She wrote:\nthe report is done";
+        assert_eq!(fresh_text(text), text);
+        assert_eq!(
+            fresh_text("As Bob wrote:\n\nwe ship Friday"),
+            "As Bob wrote:\n\nwe ship Friday"
+        );
+    }
+}
@@ -0,0 +1,469 @@
+//! Parsing of embedded `message/rfc822` parts (emails forwarded as attachments).
+//!
+//! mboxshell lists an embedded message as an opaque attachment whose decoded
+//! bytes are a complete message. This parses those bytes into headers,
+//! bodies and attachments, following further embedded messages.
+//!
+//! The message is read as Latin-1 so every byte maps to one `char`: MIME
+//! structure is ASCII, and part bodies are turned back into their exact
+//! bytes before transfer and charset decoding.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::body_text;
+use super::facets::mime_essence;
+use super::headers;
+use super::mime::{self, MimePart};
+use super::quoting;
+use super::sanitize;
+use super::scheme;
+use crate::models::{AttachmentInfo, EmailBody, EmbeddedMessage, RemoteContent};
+
+/// Embedded messages nested deeper than this are left as plain attachments.
+pub const MAX_DEPTH: usize = 8;
+
+/// Filename for embedded messages whose part has none.
+const DEFAULT_FILENAME: &str = "message.eml";
+
+static ENCODED_WORD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"=\?([^?\s]+)\?([bBqQ])\?([^?\s]*)\?=").unwrap());
+
+/// A message decoded from its raw bytes.
+#[derive(Debug, Default)]
+pub struct ParsedMessage {
+    pub raw_headers: String,
+    pub from: String,
+    pub to: Vec<String>,
+    pub subject: String,
+    pub date: String,
+    pub text: Option<String>,
+    pub html: Option<String>,
+    pub attachments: Vec<ParsedPart>,
+}
+
+/// A decoded attachment of a parsed message.
+#[derive(Debug)]
+pub struct ParsedPart {
+    pub filename: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Whether a part holds a complete embedded message.
+pub fn is_message(content_type: &str) -> bool {
+    mime_essence(content_type) == "message/rfc822"
+}
+
+/// Decode a raw message.
+pub fn parse(data: &[u8]) -> ParsedMessage {
+    let latin1: String = data.iter().map(|&b| char::from(b)).collect();
+    let (header_block, _) = mime::split_entity(&latin1);
+
+    let mut message = ParsedMessage {
+        raw_headers: String::from_utf8_lossy(&latin1_bytes(header_block)).into_owned(),
+        ..ParsedMessage::default()
+    };
+    for (name, value) in headers::unfold(header_block) {
+        let value = decode_header(&String::from_utf8_lossy(&latin1_bytes(&value)));
+        match name.to_ascii_lowercase().as_str() {
+            "from" => message.from = value.trim().to_string(),
+            "to" | "cc" => message.to.extend(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|a| !a.is_empty())
+                    .map(str::to_string),
+            ),
+            "subject" => message.subject = value.trim().to_string(),
+            "date" => message.date = value.trim().to_string(),
+            _ => {}
+        }
+    }
+    collect(&latin1, 0, &mut message);
+    message
+}
+
+/// The message as an embedded body, sanitized like top-level bodies, with
+/// its own embedded messages parsed up to `MAX_DEPTH`.
+///
+/// `path` leads to the message from email `email_index`: the part holding
+/// it, then its index in each enclosing embedded message. Attachment URLs
+/// are built from it.
+pub fn embedded_message(
+    data: &[u8],
+    email_index: usize,
+    path: &[usize],
+    load_remote: bool,
+) -> EmbeddedMessage {
+    let parsed = parse(data);
+    let (&part, nested) = path.split_first().unwrap_or((&0, &[]));
+    let url = |i: usize| scheme::nested_attachment_url(email_index, part, &[nested, &[i]].concat());
+    let mut body = EmailBody {
+        text: parsed.text.clone(),
+        html: None,
+        raw_headers: parsed.raw_headers,
+        attachments: parsed
+            .attachments
+            .iter()
+            .enumerate()
+            .map(|(i, part)| AttachmentInfo {
+                filename: part.filename.clone(),
+                content_type: part.content_type.clone(),
+                size: part.data.len() as u64,
+                part_index: i,
+                url: url(i),
+            })
+            .collect(),
+        segments: quoting::segment(&body_text::plain_text(
+            parsed.text.as_deref(),
+            parsed.html.as_deref(),
+        )),
+        remote_content: RemoteContent::default(),
+        embedded: Vec::new(),
+    };
+    if let Some(html) = parsed.html.as_deref() {
+        let (clean, remote_content) = sanitize::sanitize(html, load_remote);
+        body.html = Some(clean);
+        body.remote_content = remote_content;
+    }
+    if path.len() < MAX_DEPTH {
+        body.embedded = parsed
+            .attachments
+            .iter()
+            .enumerate()
+            .filter(|(_, part)| is_message(&part.content_type))
+            .map(|(i, part)| {
+                embedded_message(&part.data, email_index, &[path, &[i]].concat(), load_remote)
+            })
+            .collect();
+    }
+
+    EmbeddedMessage {
+        attachment_index: path.last().copied().unwrap_or_default(),
+        from: parsed.from,
+        to: parsed.to,
+        subject: parsed.subject,
+        date: parsed.date,
+        body,
+    }
+}
+
+/// An attachment of an embedded message, following `nested` (one attachment
+/// index per level) down from the message in `data`.
+pub fn nested_part(data: &[u8], nested: &[usize]) -> Option<ParsedPart> {
+    let (&first, rest) = nested.split_first()?;
+    if nested.len() > MAX_DEPTH {
+        return None;
+    }
+    let part = parse(data).attachments.into_iter().nth(first)?;
+    if rest.is_empty() {
+        Some(part)
+    } else if is_message(&part.content_type) {
+        nested_part(&part.data, rest)
+    } else {
+        None
+    }
+}
+
+/// Searchable text of an embedded message: its main headers and body,
+/// followed by those of the messages it embeds.
+pub fn plain_text(data: &[u8]) -> String {
+    plain_text_at(data, 0)
+}
+
+fn plain_text_at(data: &[u8], depth: usize) -> String {
+    let parsed = parse(data);
+    let mut text = format!(
+        "From: {}\nTo: {}\nSubject: {}\n\n{}",
+        parsed.from,
+        parsed.to.join(", "),
+        parsed.subject,
+        body_text::plain_text(parsed.text.as_deref(), parsed.html.as_deref())
+    );
+    if depth < MAX_DEPTH {
+        for part in parsed
+            .attachments
+            .iter()
+            .filter(|p| is_message(&p.content_type))
+        {
+            text.push_str("\n\n");
+            text.push_str(&plain_text_at(&part.data, depth + 1));
+        }
+    }
+    text
+}
+
+fn collect(entity: &str, depth: usize, message: &mut ParsedMessage) {
+    let (header_block, body) = mime::split_entity(entity);
+    let mut part = MimePart {
+        content_type: "text/plain".to_string(),
+        ..MimePart::default()
+    };
+    let mut boundary = None;
+    let mut name = None;
+    let mut charset = None;
+    let mut transfer_encoding = String::new();
+
+    for (header, value) in headers::unfold(header_block) {
+        let value = String::from_utf8_lossy(&latin1_bytes(&value)).into_owned();
+        match header.to_ascii_lowercase().as_str() {
+            "content-type" => {
+                let (media_type, params) = mime::split_params(&value);
+                part.content_type = media_type;
+                boundary = mime::param(&params, "boundary");
+                name = mime::param(&params, "name");
+                charset = mime::param(&params, "charset");
+            }
+            "content-disposition" => {
+                let (disposition, params) = mime::split_params(&value);
+                part.disposition = Some(disposition);
+                part.filename = mime::param(&params, "filename");
+            }
+            "content-transfer-encoding" => transfer_encoding = value.trim().to_lowercase(),
+            _ => {}
+        }
+    }
+
+    if let Some(boundary) = boundary.filter(|_| part.content_type.starts_with("multipart/")) {
+        if depth < mime::MAX_DEPTH {
+            for child in mime::split_multipart(body, &boundary) {
+                collect(child, depth + 1, message);
+            }
+        }
+        return;
+    }
+
+    let data = decode_transfer(body, &transfer_encoding);
+    part.filename = part.filename.or(name).map(|f| decode_header(&f));
+    let is_body = !part.is_attachment() && !is_message(&part.content_type);
+    if is_body && part.content_type == "text/plain" && message.text.is_none() {
+        message.text = Some(decode_charset(&data, charset.as_deref()));
+    } else if is_body && part.content_type == "text/html" && message.html.is_none() {
+        message.html = Some(decode_charset(&data, charset.as_deref()));
+    } else {
+        let fallback = if is_message(&part.content_type) {
+            DEFAULT_FILENAME
+        } else {
+            "attachment"
+        };
+        message.attachments.push(ParsedPart {
+            filename: part.filename.unwrap_or_else(|| fallback.to_string()),
+            content_type: part.content_type,
+            data,
+        });
+    }
+}
+
+/// Decode RFC 2047 encoded words (`=?utf-8?B?...?=`) in a header value.
+pub fn decode_header(value: &str) -> String {
+    let mut out = String::new();
+    let mut last = 0;
+    for caps in ENCODED_WORD.captures_iter(value) {
+        let whole = caps.get(0).unwrap();
+        let between = &value[last..whole.start()];
+        // Whitespace between two encoded words is not part of the text
+        if last == 0 || !between.trim().is_empty() {
+            out.push_str(between);
+        }
+        let bytes = if caps[2].eq_ignore_ascii_case("b") {
+            decode_base64(&caps[3])
+        } else {
+            decode_quoted_printable(caps[3].replace('_', " ").as_bytes())
+        };
+        // RFC 2231 allows a language suffix: `utf-8*en`
+        let charset = caps[1].split('*').next().unwrap_or_default();
+        out.push_str(&decode_charset(&bytes, Some(charset)));
+        last = whole.end();
+    }
+    out.push_str(&value[last..]);
+    out
+}
+
+/// Decode text in `charset`, falling back to UTF-8 for unknown labels.
+fn decode_charset(data: &[u8], charset: Option<&str>) -> String {
+    let encoding = charset
+        .and_then(|label| encoding_rs::Encoding::for_label(label.trim().as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    encoding.decode(data).0.into_owned()
+}
+
+fn decode_transfer(body: &str, encoding: &str) -> Vec<u8> {
+    match encoding {
+        "base64" => decode_base64(body),
+        "quoted-printable" => decode_quoted_printable(&latin1_bytes(body)),
+        _ => latin1_bytes(body),
+    }
+}
+
+/// Lenient base64: characters outside the alphabet are skipped.
+fn decode_base64(input: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in input.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ => continue,
+        };
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    out
+}
+
+fn decode_quoted_printable(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        if input[i] == b'=' {
+            let rest = &input[i + 1..];
+            if rest.starts_with(b"\r\n") {
+                i += 3;
+                continue;
+            }
+            if rest.starts_with(b"\n") {
+                i += 2;
+                continue;
+            }
+            if let [high, low, ..] = rest {
+                if high.is_ascii_hexdigit() && low.is_ascii_hexdigit() {
+                    out.push(hex_value(*high) << 4 | hex_value(*low));
+                    i += 3;
+                    continue;
+                }
+            }
+        }
+        out.push(input[i]);
+        i += 1;
+    }
+    out
+}
+
+fn hex_value(digit: u8) -> u8 {
+    match digit {
+        b'0'..=b'9' => digit - b'0',
+        b'a'..=b'f' => digit - b'a' + 10,
+        _ => digit - b'A' + 10,
+    }
+}
+
+/// The original bytes of text read as Latin-1.
+fn latin1_bytes(text: &str) -> Vec<u8> {
+    text.chars().map(|c| c as u8).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORWARD: &str = "From: Bob <bob@example.com>\r\n\
+Subject: Fwd: contract\r\n\
+Content-Type: multipart/mixed; boundary=\"outer\"\r\n\
+\r\n\
+--outer\r\n\
+Content-Type: text/plain; charset=utf-8\r\n\
+\r\n\
+See below.\r\n\
+--outer\r\n\
+Content-Type: message/rfc822\r\n\
+Content-Disposition: attachment; filename=\"original.eml\"\r\n\
+\r\n\
+From: =?utf-8?Q?Ana_Mar=C3=ADa?= <ana@example.com>\r\n\
+To: bob@example.com, carol@example.com\r\n\
+Subject: =?utf-8?B?Q29udHJhdG8=?= =?utf-8?B?IGZpbmFs?=\r\n\
+Date: Tue, 5 Mar 2024 10:00:00 +0100\r\n\
+Content-Type: multipart/mixed; boundary=\"inner\"\r\n\
+\r\n\
+--inner\r\n\
+Content-Type: text/plain; charset=iso-8859-1\r\n\
+Content-Transfer-Encoding: quoted-printable\r\n\
+\r\n\
+Hola, aqu=ED est=E1 el contrato.=\r\n\
+\r\n\
+--inner\r\n\
+Content-Type: application/pdf\r\n\
+Content-Disposition: attachment; filename*=utf-8''contrato%20a%C3%B1o.pdf\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+JVBERi0xLjQ=\r\n\
+--inner--\r\n\
+--outer--\r\n";
+
+    #[test]
+    fn decodes_headers_bodies_and_attachments() {
+        let outer = parse(FORWARD.as_bytes());
+        assert_eq!(outer.subject, "Fwd: contract");
+        assert_eq!(outer.text.as_deref(), Some("See below.\r\n"));
+        assert_eq!(outer.attachments.len(), 1);
+        assert_eq!(outer.attachments[0].filename, "original.eml");
+        assert!(is_message(&outer.attachments[0].content_type));
+
+        let inner = parse(&outer.attachments[0].data);
+        assert_eq!(inner.from, "Ana María <ana@example.com>");
+        assert_eq!(inner.to, vec!["bob@example.com", "carol@example.com"]);
+        assert_eq!(inner.subject, "Contrato final");
+        assert_eq!(inner.date, "Tue, 5 Mar 2024 10:00:00 +0100");
+        assert_eq!(
+            inner.text.as_deref(),
+            Some("Hola, aquí está el contrato.\r\n")
+        );
+        assert_eq!(inner.attachments[0].filename, "contrato año.pdf");
+        assert_eq!(inner.attachments[0].data, b"%PDF-1.4");
+    }
+
+    #[test]
+    fn builds_nested_embedded_bodies() {
+        let outer = embedded_message(FORWARD.as_bytes(), 7, &[2], false);
+        assert_eq!(outer.attachment_index, 2);
+        assert_eq!(outer.body.embedded.len(), 1);
+        let inner = &outer.body.embedded[0];
+        assert_eq!(inner.attachment_index, 0);
+        assert_eq!(inner.subject, "Contrato final");
+        assert_eq!(inner.body.attachments[0].size, 8);
+        assert_eq!(
+            inner.body.attachments[0].url,
+            scheme::nested_attachment_url(7, 2, &[0, 0])
+        );
+        assert!(inner.body.embedded.is_empty());
+
+        let text = plain_text(FORWARD.as_bytes());
+        assert!(text.contains("See below."));
+        assert!(text.contains("Subject: Contrato final"));
+        assert!(text.contains("aquí está el contrato"));
+    }
+
+    #[test]
+    fn follows_nested_attachment_paths() {
+        let pdf = nested_part(FORWARD.as_bytes(), &[0, 0]).unwrap();
+        assert_eq!(pdf.filename, "contrato año.pdf");
+        assert_eq!(pdf.data, b"%PDF-1.4");
+        assert_eq!(
+            nested_part(FORWARD.as_bytes(), &[0]).unwrap().filename,
+            "original.eml"
+        );
+        assert!(nested_part(FORWARD.as_bytes(), &[0, 0, 0]).is_none());
+        assert!(nested_part(FORWARD.as_bytes(), &[1]).is_none());
+        assert!(nested_part(FORWARD.as_bytes(), &[]).is_none());
+    }
+
+    #[test]
+    fn leaves_plain_header_text_alone() {
+        assert_eq!(decode_header("Plain = text?"), "Plain = text?");
+        assert_eq!(
+            decode_header("Re: =?ISO-8859-1?Q?caf=E9?= tomorrow"),
+            "Re: café tomorrow"
+        );
+    }
+}
@@ -0,0 +1,329 @@
+//! Compressed RTF (MS-OXRTFCP) and text extraction from RTF bodies.
+//!
+//! Outlook stores message bodies as LZFu-compressed RTF. Bodies written in
+//! HTML are encapsulated in that RTF (`\fromhtml1`) and are recovered as
+//! HTML; anything else is reduced to plain text.
+
+/// Dictionary contents every compressed stream starts from.
+const PREBUF: &[u8] = b"{\\rtf1\\ansi\\mac\\deff0\\deftab720{\\fonttbl;}{\\f0\\fnil \\froman \\fswiss \\fmodern \\fscript \\fdecor MS Sans SerifSymbolArialTimes New RomanCourier{\\colortbl\\red0\\green0\\blue0\r\n\\par \\pard\\plain\\f0\\fs20\\b\\i\\u\\tab\\tx";
+
+const DICTIONARY_SIZE: usize = 4096;
+
+/// `LZFu`: the stream is compressed
+const COMPRESSED: u32 = 0x7546_5A4C;
+/// `MELA`: the stream is stored as is
+const UNCOMPRESSED: u32 = 0x414C_454D;
+
+/// Destinations whose text is never part of the body.
+const SKIPPED_DESTINATIONS: &[&str] = &[
+    "fonttbl",
+    "colortbl",
+    "stylesheet",
+    "info",
+    "pict",
+    "object",
+    "header",
+    "footer",
+    "listtable",
+    "listoverridetable",
+    "rsidtbl",
+    "themedata",
+    "colorschememapping",
+    "datastore",
+    "latentstyles",
+];
+
+/// The body recovered from an RTF document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RtfBody {
+    /// HTML de-encapsulated from `\fromhtml1` RTF
+    Html(String),
+    Text(String),
+}
+
+/// Decompress a `PR_RTF_COMPRESSED` stream.
+pub fn decompress(data: &[u8]) -> Option<Vec<u8>> {
+    let header = data.get(..16)?;
+    let field =
+        |i: usize| u32::from_le_bytes([header[i], header[i + 1], header[i + 2], header[i + 3]]);
+    let (compressed_size, raw_size, kind) = (field(0) as usize, field(4) as usize, field(8));
+    // The compressed size counts the header fields after itself
+    let body = data.get(16..data.len().min(compressed_size.saturating_add(4)))?;
+
+    match kind {
+        UNCOMPRESSED => Some(body[..raw_size.min(body.len())].to_vec()),
+        COMPRESSED => Some(decompress_lzfu(body)),
+        _ => None,
+    }
+}
+
+fn decompress_lzfu(body: &[u8]) -> Vec<u8> {
+    let mut dictionary = [0u8; DICTIONARY_SIZE];
+    dictionary[..PREBUF.len()].copy_from_slice(PREBUF);
+    let mut write = PREBUF.len();
+    let mut out = Vec::new();
+    let mut i = 0;
+
+    while let Some(&control) = body.get(i) {
+        i += 1;
+        for bit in 0..8 {
+            if control & (1 << bit) == 0 {
+                let Some(&byte) = body.get(i) else {
+                    return out;
+                };
+                i += 1;
+                out.push(byte);
+                dictionary[write] = byte;
+                write = (write + 1) % DICTIONARY_SIZE;
+                continue;
+            }
+            let Some(&[high, low]) = body.get(i..i + 2) else {
+                return out;
+            };
+            i += 2;
+            let reference = usize::from(u16::from_be_bytes([high, low]));
+            let offset = reference >> 4;
+            // A reference to the write position marks the end of the stream
+            if offset == write {
+                return out;
+            }
+            for k in 0..(reference & 0xF) + 2 {
+                let byte = dictionary[(offset + k) % DICTIONARY_SIZE];
+                out.push(byte);
+                dictionary[write] = byte;
+                write = (write + 1) % DICTIONARY_SIZE;
+            }
+        }
+    }
+    out
+}
+
+#[derive(Clone, Copy)]
+struct Group {
+    /// Text in this group is not part of the body
+    skip: bool,
+    /// Inside `\htmlrtf`: RTF-only formatting in encapsulated HTML
+    html_rtf: bool,
+    /// Characters following `\uN` that stand in for it (`\ucN`)
+    unicode_skip: usize,
+}
+
+/// Recover the body of an RTF document.
+pub fn extract(rtf: &[u8]) -> RtfBody {
+    let mut out = Output::default();
+    let mut groups = vec![Group {
+        skip: false,
+        html_rtf: false,
+        unicode_skip: 1,
+    }];
+    let mut from_html = false;
+    let mut ignorable = false;
+    let mut pending_skip = 0;
+    let mut i = 0;
+
+    while i < rtf.len() {
+        let group = *groups.last().unwrap();
+        let writing = !group.skip && !group.html_rtf;
+        match rtf[i] {
+            b'{' => {
+                groups.push(group);
+                i += 1;
+            }
+            b'}' => {
+                if groups.len() > 1 {
+                    groups.pop();
+                }
+                i += 1;
+            }
+            b'\r' | b'\n' => i += 1,
+            b'\\' => {
+                let Some(&next) = rtf.get(i + 1) else {
+                    break;
+                };
+                if next == b'\'' {
+                    let hex = rtf
+                        .get(i + 2..i + 4)
+                        .and_then(|h| std::str::from_utf8(h).ok());
+                    if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                        if pending_skip > 0 {
+                            pending_skip -= 1;
+                        } else if writing {
+                            out.byte(byte);
+                        }
+                    }
+                    i += 4;
+                    continue;
+                }
+                if !next.is_ascii_alphabetic() {
+                    match next {
+                        b'*' => ignorable = true,
+                        b'\\' | b'{' | b'}' if writing => out.byte(next),
+                        b'~' if writing => out.text("\u{a0}"),
+                        _ => {}
+                    }
+                    i += 2;
+                    continue;
+                }
+
+                let start = i + 1;
+                let mut end = start;
+                while rtf.get(end).is_some_and(u8::is_ascii_alphabetic) {
+                    end += 1;
+                }
+                let word = std::str::from_utf8(&rtf[start..end]).unwrap_or_default();
+                let digits_start = end;
+                if rtf.get(end) == Some(&b'-') {
+                    end += 1;
+                }
+                while rtf.get(end).is_some_and(u8::is_ascii_digit) {
+                    end += 1;
+                }
+                let param = std::str::from_utf8(&rtf[digits_start..end])
+                    .ok()
+                    .and_then(|p| p.parse::<i32>().ok());
+                if rtf.get(end) == Some(&b' ') {
+                    end += 1;
+                }
+                i = end;
+
+                let current = groups.last_mut().unwrap();
+                if std::mem::take(&mut ignorable) && !(from_html && word == "htmltag") {
+                    current.skip = true;
+                    continue;
+                }
+                match word {
+                    "fromhtml" => from_html = true,
+                    "htmlrtf" => current.html_rtf = param != Some(0),
+                    "htmltag" => current.html_rtf = false,
+                    "uc" => current.unicode_skip = param.unwrap_or(1).max(0) as usize,
+                    "ansicpg" => out.codepage = param.map(|p| p as u16),
+                    w if SKIPPED_DESTINATIONS.contains(&w) => current.skip = true,
+                    _ if !writing => {}
+                    "par" | "line" => out.text(if from_html { "\r\n" } else { "\n" }),
+                    "tab" => out.text("\t"),
+                    "lquote" => out.text("\u{2018}"),
+                    "rquote" => out.text("\u{2019}"),
+                    "ldblquote" => out.text("\u{201c}"),
+                    "rdblquote" => out.text("\u{201d}"),
+                    "bullet" => out.text("\u{2022}"),
+                    "endash" => out.text("\u{2013}"),
+                    "emdash" => out.text("\u{2014}"),
+                    "u" => {
+                        let code = param.unwrap_or_default();
+                        let code = if code < 0 { code + 0x10000 } else { code };
+                        if let Some(c) = char::from_u32(code as u32) {
+                            out.text(c.encode_utf8(&mut [0; 4]));
+                        }
+                        pending_skip = group.unicode_skip;
+                    }
+                    _ => {}
+                }
+            }
+            byte => {
+                if pending_skip > 0 {
+                    pending_skip -= 1;
+                } else if writing {
+                    out.byte(byte);
+                }
+                i += 1;
+            }
+        }
+    }
+
+    let text = out.finish();
+    if from_html {
+        RtfBody::Html(text)
+    } else {
+        RtfBody::Text(text.trim().to_string())
+    }
+}
+
+/// Body text, decoding runs of raw bytes with the document's code page.
+#[derive(Default)]
+struct Output {
+    text: String,
+    pending: Vec<u8>,
+    codepage: Option<u16>,
+}
+
+impl Output {
+    fn byte(&mut self, byte: u8) {
+        self.pending.push(byte);
+    }
+
+    fn text(&mut self, text: &str) {
+        self.flush();
+        self.text.push_str(text);
+    }
+
+    fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let label = format!("windows-{}", self.codepage.unwrap_or(1252));
+        let encoding =
+            encoding_rs::Encoding::for_label(label.as_bytes()).unwrap_or(encoding_rs::WINDOWS_1252);
+        self.text.push_str(&encoding.decode(&self.pending).0);
+        self.pending.clear();
+    }
+
+    fn finish(mut self) -> String {
+        self.flush();
+        self.text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decompresses_the_specification_example() {
+        // MS-OXRTFCP section 4.1
+        let compressed = [
+            0x2d, 0x00, 0x00, 0x00, 0x2b, 0x00, 0x00, 0x00, 0x4c, 0x5a, 0x46, 0x75, 0xf1, 0xc5,
+            0xc7, 0xa7, 0x03, 0x00, 0x0a, 0x00, 0x72, 0x63, 0x70, 0x67, 0x31, 0x32, 0x35, 0x42,
+            0x32, 0x0a, 0xf3, 0x20, 0x68, 0x65, 0x6c, 0x09, 0x00, 0x20, 0x62, 0x77, 0x05, 0xb0,
+            0x6c, 0x64, 0x7d, 0x0a, 0x80, 0x0f, 0xa0,
+        ];
+        assert_eq!(PREBUF.len(), 207);
+        assert_eq!(
+            decompress(&compressed).unwrap(),
+            b"{\\rtf1\\ansi\\ansicpg1252\\pard hello world}\r\n"
+        );
+    }
+
+    #[test]
+    fn rejects_a_size_shorter_than_the_header() {
+        let mut header = [0u8; 20];
+        header[4] = 4;
+        header[8..12].copy_from_slice(&UNCOMPRESSED.to_le_bytes());
+        for compressed_size in [0u8, 11] {
+            header[0] = compressed_size;
+            assert_eq!(decompress(&header), None);
+        }
+        header[0] = 12;
+        assert_eq!(decompress(&header), Some(Vec::new()));
+    }
+
+    #[test]
+    fn extracts_plain_text() {
+        let rtf = b"{\\rtf1\\ansi\\ansicpg1252{\\fonttbl{\\f0 Arial;}}\
+{\\*\\generator Riched20;}\\pard Caf\\'e9 \\u8364?5\\par\\tab Done}";
+        assert_eq!(
+            extract(rtf),
+            RtfBody::Text("Caf\u{e9} \u{20ac}5\n\tDone".to_string())
+        );
+    }
+
+    #[test]
+    fn de_encapsulates_html() {
+        let rtf = b"{\\rtf1\\ansi\\fromhtml1 {\\*\\htmltag19 <html>}\
+{\\*\\htmltag50 <p>}\\htmlrtf {\\b\\htmlrtf0 Hi \\{there\\}\\htmlrtf }\\htmlrtf0 \
+{\\*\\htmltag58 </p>}\\htmlrtf\\par\\htmlrtf0 {\\*\\htmltag27 </html>}}";
+        assert_eq!(
+            extract(rtf),
+            RtfBody::Html("<html><p>Hi {there}</p></html>".to_string())
+        );
+    }
+}
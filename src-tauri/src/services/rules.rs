@@ -0,0 +1,242 @@
+//! User-defined labelling rules: persistence and evaluation.
+//!
+//! Rules are per user rather than per mailbox, so they live in one JSON file
+//! in the app data directory and apply to whichever MBOX is open.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use regex::{Regex, RegexBuilder};
+
+use super::facets;
+use super::pattern_search::{MAX_DFA_SIZE, MAX_PATTERN_LEN, MAX_REGEX_SIZE};
+use crate::error::AppError;
+use crate::models::{LabelRule, RuleCondition, RuleField, RuleOperator};
+
+const FILE_NAME: &str = "rules.json";
+
+/// Reads and writes the rules file.
+pub struct RuleStore {
+    path: PathBuf,
+}
+
+impl RuleStore {
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            path: data_dir.join(FILE_NAME),
+        }
+    }
+
+    /// Load all rules, or an empty list if none were saved yet.
+    pub fn load(&self) -> Result<Vec<LabelRule>, AppError> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let json = fs::read_to_string(&self.path)?;
+        serde_json::from_str(&json)
+            .map_err(|e| AppError::Validation(format!("Corrupt rules file: {e}")))
+    }
+
+    pub fn save(&self, rules: &[LabelRule]) -> Result<(), AppError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(rules)
+            .map_err(|e| AppError::Validation(format!("Cannot encode rules: {e}")))?;
+        fs::write(&self.path, json)?;
+        Ok(())
+    }
+}
+
+/// The message attributes rules can look at; the body is fetched on demand.
+#[derive(Debug, Clone, Default)]
+pub struct RuleInput<'a> {
+    pub from: String,
+    pub from_address: &'a str,
+    pub to: String,
+    pub subject: &'a str,
+    pub labels: &'a [String],
+    pub has_attachments: bool,
+}
+
+/// A validated rule, ready to run over many messages.
+pub struct CompiledRule {
+    pub label: String,
+    match_any: bool,
+    conditions: Vec<(RuleField, Test)>,
+}
+
+enum Test {
+    Text(RuleOperator, String),
+    Regex(Regex),
+}
+
+impl CompiledRule {
+    pub fn new(rule: &LabelRule) -> Result<Self, AppError> {
+        if rule.name.trim().is_empty() {
+            return Err(AppError::Validation("Rule name is empty".to_string()));
+        }
+        if rule.label.trim().is_empty() {
+            return Err(AppError::Validation(format!(
+                "Rule {} has no label",
+                rule.name
+            )));
+        }
+        if rule.conditions.is_empty() {
+            return Err(AppError::Validation(format!(
+                "Rule {} has no conditions",
+                rule.name
+            )));
+        }
+        let conditions = rule
+            .conditions
+            .iter()
+            .map(|c| Ok((c.field, Test::new(c)?)))
+            .collect::<Result<_, AppError>>()?;
+        Ok(Self {
+            label: rule.label.trim().to_string(),
+            match_any: rule.match_any,
+            conditions,
+        })
+    }
+
+    pub fn needs_body(&self) -> bool {
+        self.conditions.iter().any(|(f, _)| *f == RuleField::Body)
+    }
+
+    /// Evaluate against a message; `body` is only called for body conditions.
+    pub fn matches(&self, input: &RuleInput, body: &mut dyn FnMut() -> String) -> bool {
+        let mut check = |(field, test): &(RuleField, Test)| match field {
+            RuleField::From => test.matches(&input.from),
+            RuleField::FromDomain => test.matches(&facets::sender_domain(input.from_address)),
+            RuleField::To => test.matches(&input.to),
+            RuleField::Subject => test.matches(input.subject),
+            RuleField::Label => input.labels.iter().any(|l| test.matches(l)),
+            RuleField::HasAttachments => test.matches(&input.has_attachments.to_string()),
+            RuleField::Body => test.matches(&body()),
+        };
+        if self.match_any {
+            self.conditions.iter().any(&mut check)
+        } else {
+            self.conditions.iter().all(&mut check)
+        }
+    }
+}
+
+impl Test {
+    fn new(condition: &RuleCondition) -> Result<Self, AppError> {
+        if condition.value.len() > MAX_PATTERN_LEN {
+            return Err(AppError::Validation(format!(
+                "Rule pattern exceeds {MAX_PATTERN_LEN} characters"
+            )));
+        }
+        match condition.operator {
+            RuleOperator::Matches => RegexBuilder::new(&condition.value)
+                .case_insensitive(true)
+                .size_limit(MAX_REGEX_SIZE)
+                .dfa_size_limit(MAX_DFA_SIZE)
+                .build()
+                .map(Test::Regex)
+                .map_err(|e| AppError::Validation(format!("Invalid rule pattern: {e}"))),
+            op => Ok(Test::Text(op, condition.value.trim().to_lowercase())),
+        }
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        match self {
+            Test::Regex(re) => re.is_match(value),
+            Test::Text(op, needle) => {
+                let value = value.to_lowercase();
+                match op {
+                    RuleOperator::Contains => value.contains(needle.as_str()),
+                    RuleOperator::Equals => value.trim() == needle,
+                    RuleOperator::StartsWith => value.starts_with(needle.as_str()),
+                    RuleOperator::EndsWith => value.ends_with(needle.as_str()),
+                    RuleOperator::Matches => unreachable!("compiled as a regex"),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn condition(field: RuleField, operator: RuleOperator, value: &str) -> RuleCondition {
+        RuleCondition {
+            field,
+            operator,
+            value: value.to_string(),
+        }
+    }
+
+    fn invoices_rule(match_any: bool) -> LabelRule {
+        LabelRule {
+            name: "Vendor invoices".to_string(),
+            label: "Invoices".to_string(),
+            conditions: vec![
+                condition(RuleField::FromDomain, RuleOperator::Equals, "vendor.com"),
+                condition(RuleField::Subject, RuleOperator::Contains, "Invoice"),
+            ],
+            match_any,
+            enabled: true,
+        }
+    }
+
+    fn input<'a>(address: &'a str, subject: &'a str) -> RuleInput<'a> {
+        RuleInput {
+            from: format!("Billing <{address}>"),
+            from_address: address,
+            subject,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn all_conditions_must_hold_by_default() {
+        let rule = CompiledRule::new(&invoices_rule(false)).unwrap();
+        let mut no_body = || unreachable!();
+        assert!(rule.matches(&input("ar@Vendor.com", "INVOICE 42"), &mut no_body));
+        assert!(!rule.matches(&input("ar@other.com", "Invoice 42"), &mut no_body));
+        assert!(!rule.needs_body());
+    }
+
+    #[test]
+    fn match_any_needs_one_condition() {
+        let rule = CompiledRule::new(&invoices_rule(true)).unwrap();
+        let mut no_body = || unreachable!();
+        assert!(rule.matches(&input("ar@other.com", "Invoice 42"), &mut no_body));
+    }
+
+    #[test]
+    fn body_is_read_only_for_body_conditions() {
+        let mut rule = invoices_rule(false);
+        rule.conditions = vec![condition(RuleField::Body, RuleOperator::Matches, r"PO-\d+")];
+        let rule = CompiledRule::new(&rule).unwrap();
+        assert!(rule.needs_body());
+        assert!(rule.matches(&input("a@b.c", "Hi"), &mut || "see po-123".to_string()));
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        let mut rule = invoices_rule(false);
+        rule.conditions[0] = condition(RuleField::Subject, RuleOperator::Matches, "(");
+        assert!(CompiledRule::new(&rule).is_err());
+        rule.conditions[0] = condition(RuleField::Body, RuleOperator::Matches, r"\w{1000}{1000}");
+        assert!(CompiledRule::new(&rule).is_err());
+        rule.conditions.clear();
+        assert!(CompiledRule::new(&rule).is_err());
+    }
+
+    #[test]
+    fn store_round_trips() {
+        let dir = std::env::temp_dir().join(format!("mboxviewer-rules-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let store = RuleStore::new(&dir);
+        assert!(store.load().unwrap().is_empty());
+        store.save(&[invoices_rule(false)]).unwrap();
+        assert_eq!(store.load().unwrap(), vec![invoices_rule(false)]);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,303 @@
+//! Sanitizing HTML message bodies before they reach the webview.
+//!
+//! Markup is cleaned by ammonia against an allow-list of tags, attributes
+//! and CSS properties, so scripts, forms, frames and event handlers never
+//! survive. Remote images and CSS `url()`s are dropped unless the caller
+//! allows remote content; tracking pixels are dropped either way.
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock};
+
+use ammonia::{Builder, UrlRelative};
+use regex::{Captures, Regex};
+
+use crate::models::RemoteContent;
+
+/// Presentational attributes mail clients still rely on for layout.
+const GENERIC_ATTRIBUTES: &[&str] = &[
+    "align",
+    "bgcolor",
+    "border",
+    "cellpadding",
+    "cellspacing",
+    "color",
+    "dir",
+    "face",
+    "height",
+    "size",
+    "style",
+    "valign",
+    "width",
+];
+
+/// CSS properties kept in `style` attributes; anything that can position
+/// content over the viewer chrome (`position`, `z-index`, ...) is left out.
+const STYLE_PROPERTIES: &[&str] = &[
+    "background",
+    "background-color",
+    "background-image",
+    "background-position",
+    "background-repeat",
+    "border",
+    "border-bottom",
+    "border-collapse",
+    "border-color",
+    "border-left",
+    "border-radius",
+    "border-right",
+    "border-spacing",
+    "border-style",
+    "border-top",
+    "border-width",
+    "color",
+    "display",
+    "font",
+    "font-family",
+    "font-size",
+    "font-style",
+    "font-weight",
+    "height",
+    "letter-spacing",
+    "line-height",
+    "list-style",
+    "list-style-type",
+    "margin",
+    "margin-bottom",
+    "margin-left",
+    "margin-right",
+    "margin-top",
+    "max-width",
+    "min-width",
+    "padding",
+    "padding-bottom",
+    "padding-left",
+    "padding-right",
+    "padding-top",
+    "text-align",
+    "text-decoration",
+    "text-indent",
+    "text-transform",
+    "vertical-align",
+    "white-space",
+    "width",
+    "word-break",
+];
+
+/// URL fragments used by common open-tracking services.
+const TRACKER_PATTERNS: &[&str] = &[
+    "/track/open",
+    "/wf/open",
+    "/open.php",
+    "/open.aspx",
+    "/pixel",
+    "/beacon",
+    "google-analytics.com/collect",
+    "list-manage.com/track",
+    "mandrillapp.com/track",
+    "mailtrack.io",
+    "yesware.com",
+];
+
+static IMG_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<img\b[^>]*>").unwrap());
+static ATTRIBUTE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)\b([a-z-]+)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#).unwrap()
+});
+static CSS_URL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)url\(\s*['"]?([^'")]*)['"]?\s*\)"#).unwrap());
+
+/// Clean an HTML body, reporting what remote content was removed.
+pub fn sanitize(html: &str, allow_remote: bool) -> (String, RemoteContent) {
+    let (html, trackers) = strip_trackers(html);
+    let blocked = Arc::new(AtomicUsize::new(0));
+
+    let mut builder = Builder::default();
+    builder
+        .add_tags(["font"])
+        .add_generic_attributes(GENERIC_ATTRIBUTES)
+        .filter_style_properties(STYLE_PROPERTIES.iter().copied().collect::<HashSet<_>>())
+        .add_url_schemes(["cid", "data"])
+        .url_relative(UrlRelative::Deny);
+
+    let counter = Arc::clone(&blocked);
+    builder.attribute_filter(move |element, attribute, value| match attribute {
+        "src" if is_remote(value) => {
+            if allow_remote {
+                Some(value.into())
+            } else {
+                counter.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+        "src" | "href" if value.trim_start().to_lowercase().starts_with("data:") => {
+            // Inline images only; a data: link could open a document
+            let inline_image = value.trim_start().to_lowercase().starts_with("data:image/");
+            (element == "img" && inline_image).then(|| value.into())
+        }
+        "style" if !allow_remote => {
+            let mut removed = 0;
+            let style = CSS_URL.replace_all(value, |caps: &Captures| {
+                if is_remote(&caps[1]) {
+                    removed += 1;
+                    "none".to_string()
+                } else {
+                    caps[0].to_string()
+                }
+            });
+            counter.fetch_add(removed, Ordering::Relaxed);
+            Some(style.into_owned().into())
+        }
+        _ => Some(value.into()),
+    });
+
+    let clean = builder.clean(&html).to_string();
+    let report = RemoteContent {
+        allowed: allow_remote,
+        blocked: blocked.load(Ordering::Relaxed),
+        trackers,
+    };
+    (clean, report)
+}
+
+/// Remove `<img>` tags that look like open-tracking pixels.
+fn strip_trackers(html: &str) -> (String, usize) {
+    let mut trackers = 0;
+    let html = IMG_TAG.replace_all(html, |caps: &Captures| {
+        if is_tracker(&caps[0]) {
+            trackers += 1;
+            String::new()
+        } else {
+            caps[0].to_string()
+        }
+    });
+    (html.into_owned(), trackers)
+}
+
+/// A remote image that is tiny, hidden or served by a known tracking service.
+fn is_tracker(tag: &str) -> bool {
+    let mut src = None;
+    let mut tiny = false;
+    for caps in ATTRIBUTE.captures_iter(tag) {
+        let value = caps
+            .get(2)
+            .or_else(|| caps.get(3))
+            .or_else(|| caps.get(4))
+            .map_or("", |m| m.as_str());
+        match caps[1].to_lowercase().as_str() {
+            "src" => src = Some(value.replace("&amp;", "&")),
+            "width" | "height" => tiny |= is_tiny_length(value),
+            "style" => {
+                for declaration in value.split(';') {
+                    let Some((property, value)) = declaration.split_once(':') else {
+                        continue;
+                    };
+                    let value = value.trim().to_lowercase();
+                    tiny |= match property.trim().to_lowercase().as_str() {
+                        "display" => value == "none",
+                        "visibility" => value == "hidden",
+                        "width" | "height" => is_tiny_length(&value),
+                        _ => false,
+                    };
+                }
+            }
+            _ => {}
+        }
+    }
+    let Some(src) = src.filter(|s| is_remote(s)) else {
+        return false;
+    };
+    let src = src.to_lowercase();
+    tiny || TRACKER_PATTERNS.iter().any(|p| src.contains(p))
+}
+
+/// A `width` or `height` of at most one pixel.
+fn is_tiny_length(value: &str) -> bool {
+    value
+        .trim()
+        .trim_end_matches("px")
+        .trim()
+        .parse::<u32>()
+        .is_ok_and(|n| n <= 1)
+}
+
+fn is_remote(url: &str) -> bool {
+    let url = url.trim_start().to_lowercase();
+    url.starts_with("http://") || url.starts_with("https://") || url.starts_with("//")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removes_scripts_handlers_and_forms() {
+        let (html, _) = sanitize(
+            r#"<p onclick="steal()">Hi<script>alert(1)</script></p><form action="https://evil"><input name="pw"></form><a href="javascript:x()">link</a>"#,
+            false,
+        );
+        assert!(!html.contains("script"));
+        assert!(!html.contains("onclick"));
+        assert!(!html.contains("<form"));
+        assert!(!html.contains("<input"));
+        assert!(!html.contains("javascript"));
+        assert!(html.contains("<p>Hi</p>"));
+    }
+
+    #[test]
+    fn keeps_layout_attributes_and_allowed_css() {
+        let (html, _) = sanitize(
+            r##"<table width="600" bgcolor="#fff"><tr><td style="color: red; position: fixed">x</td></tr></table>"##,
+            false,
+        );
+        assert!(html.contains(r#"width="600""#));
+        assert!(html.contains(r##"bgcolor="#fff""##));
+        assert!(html.contains("color:red"));
+        assert!(!html.contains("position"));
+    }
+
+    #[test]
+    fn blocks_remote_images_unless_allowed() {
+        let body = r#"<img src="https://cdn.example.com/logo.png" alt="Logo"><div style="background: url('https://cdn.example.com/bg.png')">x</div><img src="cid:logo@example">"#;
+
+        let (html, report) = sanitize(body, false);
+        assert!(!html.contains("cdn.example.com"));
+        assert!(html.contains(r#"alt="Logo""#));
+        assert!(html.contains("cid:logo@example"));
+        assert_eq!(report.blocked, 2);
+        assert!(!report.allowed);
+
+        let (html, report) = sanitize(body, true);
+        assert!(html.contains("https://cdn.example.com/logo.png"));
+        assert_eq!(report.blocked, 0);
+    }
+
+    #[test]
+    fn strips_tracking_pixels_even_when_remote_content_is_allowed() {
+        let body = r#"<p>Hello</p><img src="https://t.example.com/x.gif?id=1&amp;u=2" width="1" height="1"><img src="https://sendgrid.net/wf/open?upn=abc"><img src="https://example.com/photo.jpg" width="400">"#;
+        let (html, report) = sanitize(body, true);
+        assert_eq!(report.trackers, 2);
+        assert!(!html.contains("t.example.com"));
+        assert!(!html.contains("sendgrid"));
+        assert!(html.contains("photo.jpg"));
+    }
+
+    #[test]
+    fn only_exact_width_and_height_styles_make_a_pixel() {
+        let body = r#"<img src="https://example.com/a.jpg" style="border-width:1px; line-height:0"><img src="https://example.com/b.jpg" style="max-width:1px;min-width:0"><img src="https://example.com/c.gif" style="color:red; WIDTH: 1px">"#;
+        let (html, report) = sanitize(body, true);
+        assert_eq!(report.trackers, 1);
+        assert!(html.contains("a.jpg"));
+        assert!(html.contains("b.jpg"));
+        assert!(!html.contains("c.gif"));
+    }
+
+    #[test]
+    fn only_inline_image_data_urls_are_kept() {
+        let (html, _) = sanitize(
+            r#"<img src="data:image/png;base64,AAAA"><a href="data:text/html,<script>x</script>">x</a>"#,
+            false,
+        );
+        assert!(html.contains("data:image/png;base64,AAAA"));
+        assert!(!html.contains("data:text/html"));
+    }
+}
@@ -0,0 +1,122 @@
+//! Persistence for saved searches (smart folders).
+//!
+//! Saved searches live in a single JSON sidecar file in the app data
+//! directory, keyed by mailbox path so each MBOX keeps its own folders.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+use crate::models::SavedSearch;
+
+const FILE_NAME: &str = "saved_searches.json";
+
+/// Reads and writes the saved-search sidecar file.
+pub struct SavedSearchStore {
+    path: PathBuf,
+}
+
+impl SavedSearchStore {
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            path: data_dir.join(FILE_NAME),
+        }
+    }
+
+    /// Load the saved searches for `mailbox`, or an empty list if none exist.
+    pub fn load(&self, mailbox: &Path) -> Result<Vec<SavedSearch>, AppError> {
+        let mut all = self.read_all()?;
+        Ok(all.remove(&mailbox_key(mailbox)).unwrap_or_default())
+    }
+
+    /// Replace the saved searches for `mailbox`, leaving other mailboxes untouched.
+    pub fn save(&self, mailbox: &Path, searches: &[SavedSearch]) -> Result<(), AppError> {
+        let mut all = self.read_all()?;
+        if searches.is_empty() {
+            all.remove(&mailbox_key(mailbox));
+        } else {
+            all.insert(mailbox_key(mailbox), searches.to_vec());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(&all)
+            .map_err(|e| AppError::Validation(format!("Cannot encode saved searches: {e}")))?;
+        fs::write(&self.path, json)?;
+        Ok(())
+    }
+
+    fn read_all(&self) -> Result<BTreeMap<String, Vec<SavedSearch>>, AppError> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let json = fs::read_to_string(&self.path)?;
+        serde_json::from_str(&json)
+            .map_err(|e| AppError::Validation(format!("Corrupt saved searches file: {e}")))
+    }
+}
+
+fn mailbox_key(mailbox: &Path) -> String {
+    fs::canonicalize(mailbox)
+        .unwrap_or_else(|_| mailbox.to_path_buf())
+        .display()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "mboxviewer-saved-searches-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn search(name: &str, query: &str) -> SavedSearch {
+        SavedSearch {
+            name: name.to_string(),
+            query: query.to_string(),
+        }
+    }
+
+    #[test]
+    fn load_returns_empty_when_file_missing() {
+        let store = SavedSearchStore::new(&temp_dir("missing"));
+        let searches = store.load(Path::new("/mail/a.mbox")).unwrap();
+        assert!(searches.is_empty());
+    }
+
+    #[test]
+    fn save_then_load_round_trips_per_mailbox() {
+        let dir = temp_dir("roundtrip");
+        let store = SavedSearchStore::new(&dir);
+        let billing = vec![search("Billing", "from:billing@ has:attachment")];
+        let news = vec![search("News", "subject:newsletter")];
+
+        store.save(Path::new("/mail/a.mbox"), &billing).unwrap();
+        store.save(Path::new("/mail/b.mbox"), &news).unwrap();
+
+        assert_eq!(store.load(Path::new("/mail/a.mbox")).unwrap(), billing);
+        assert_eq!(store.load(Path::new("/mail/b.mbox")).unwrap(), news);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn saving_empty_list_removes_mailbox_entry() {
+        let dir = temp_dir("remove");
+        let store = SavedSearchStore::new(&dir);
+        store
+            .save(Path::new("/mail/a.mbox"), &[search("X", "x")])
+            .unwrap();
+        store.save(Path::new("/mail/a.mbox"), &[]).unwrap();
+
+        assert!(store.load(Path::new("/mail/a.mbox")).unwrap().is_empty());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
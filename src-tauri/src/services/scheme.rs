@@ -0,0 +1,142 @@
+//! URLs of the `mbox` URI scheme the webview loads message parts from.
+//!
+//! - `mbox://localhost/message/<index>/cid/<content-id>`: inline images
+//! - `mbox://localhost/attachment/<index>/<part>`: attachments, with Range support
+//! - `mbox://localhost/attachment/<index>/<part>/<nested>...`: attachments of
+//!   messages embedded in part `<part>`, one index per level of nesting
+//!
+//! On Windows custom schemes are reached over `http://mbox.localhost` instead.
+
+use super::mime::percent_decode;
+
+/// Custom URI scheme registered with the webview.
+pub const SCHEME: &str = "mbox";
+
+#[cfg(windows)]
+const BASE_URL: &str = "http://mbox.localhost";
+#[cfg(not(windows))]
+const BASE_URL: &str = "mbox://localhost";
+
+/// What a scheme URL refers to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemeRequest {
+    InlinePart {
+        index: usize,
+        content_id: String,
+    },
+    Attachment {
+        email_index: usize,
+        attachment_index: usize,
+        /// Path through embedded messages; empty for the message's own parts
+        nested: Vec<usize>,
+    },
+}
+
+pub fn inline_part_url(index: usize, content_id: &str) -> String {
+    format!(
+        "{BASE_URL}/message/{index}/cid/{}",
+        percent_encode(content_id.trim())
+    )
+}
+
+pub fn attachment_url(email_index: usize, attachment_index: usize) -> String {
+    nested_attachment_url(email_index, attachment_index, &[])
+}
+
+/// URL of an attachment of a message embedded in part `attachment_index`.
+pub fn nested_attachment_url(
+    email_index: usize,
+    attachment_index: usize,
+    nested: &[usize],
+) -> String {
+    let mut url = format!("{BASE_URL}/attachment/{email_index}/{attachment_index}");
+    for index in nested {
+        url.push_str(&format!("/{index}"));
+    }
+    url
+}
+
+/// Parse the path of a request made to the scheme.
+pub fn parse_path(path: &str) -> Option<SchemeRequest> {
+    if let Some(rest) = path.strip_prefix("/message/") {
+        let (index, content_id) = rest.split_once("/cid/")?;
+        let content_id = percent_decode(content_id);
+        return (!content_id.is_empty()).then_some(SchemeRequest::InlinePart {
+            index: index.parse().ok()?,
+            content_id,
+        });
+    }
+    let rest = path.strip_prefix("/attachment/")?;
+    let indices = rest
+        .split('/')
+        .map(|index| index.parse().ok())
+        .collect::<Option<Vec<usize>>>()?;
+    match indices.as_slice() {
+        [email_index, attachment_index, nested @ ..] => Some(SchemeRequest::Attachment {
+            email_index: *email_index,
+            attachment_index: *attachment_index,
+            nested: nested.to_vec(),
+        }),
+        _ => None,
+    }
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters and `@`.
+pub fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'@' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(url: &str) -> &str {
+        url.strip_prefix(BASE_URL).unwrap()
+    }
+
+    #[test]
+    fn parses_paths_built_by_the_url_helpers() {
+        assert_eq!(
+            parse_path(path(&inline_part_url(3, "part/1@example"))),
+            Some(SchemeRequest::InlinePart {
+                index: 3,
+                content_id: "part/1@example".to_string()
+            })
+        );
+        assert_eq!(
+            parse_path(path(&attachment_url(12, 0))),
+            Some(SchemeRequest::Attachment {
+                email_index: 12,
+                attachment_index: 0,
+                nested: Vec::new()
+            })
+        );
+        assert_eq!(
+            parse_path(path(&nested_attachment_url(12, 3, &[0, 2]))),
+            Some(SchemeRequest::Attachment {
+                email_index: 12,
+                attachment_index: 3,
+                nested: vec![0, 2]
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_paths() {
+        assert_eq!(parse_path("/message/x/cid/a"), None);
+        assert_eq!(parse_path("/message/1/cid/"), None);
+        assert_eq!(parse_path("/attachment/1"), None);
+        assert_eq!(parse_path("/attachment/1/-2"), None);
+        assert_eq!(parse_path("/attachment/1/2/"), None);
+        assert_eq!(parse_path("/attachment/1/2/x"), None);
+        assert_eq!(parse_path("/other"), None);
+    }
+}
@@ -0,0 +1,147 @@
+//! On-disk spool of decoded attachments.
+//!
+//! mboxshell decodes an attachment into memory in one go. Large parts are
+//! decoded once into the spool directory so the `mbox` scheme can serve
+//! byte ranges from a file instead of holding or re-decoding the whole part.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::AppError;
+
+/// A decoded attachment on disk, ready to be served in ranges.
+#[derive(Debug, Clone)]
+pub struct SpooledAttachment {
+    pub path: PathBuf,
+    pub filename: String,
+    pub content_type: String,
+    pub size: u64,
+}
+
+pub struct Spool {
+    dir: PathBuf,
+}
+
+impl Spool {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Stable file name for a part of a message in a given mailbox; `nested`
+    /// follows embedded messages down from that part.
+    pub fn key(mbox_path: &Path, offset: u64, part: usize, nested: &[usize]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(mbox_path.to_string_lossy().as_bytes());
+        hasher.update(offset.to_le_bytes());
+        hasher.update(part.to_le_bytes());
+        for index in nested {
+            hasher.update(index.to_le_bytes());
+        }
+        hasher.finalize()[..16]
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+
+    /// Stable file name for an entry of the archive spooled as `part_key`;
+    /// `path` names one entry per nesting level.
+    pub fn archive_entry_key(part_key: &str, path: &[String]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(part_key.as_bytes());
+        for name in path {
+            hasher.update(name.len().to_le_bytes());
+            hasher.update(name.as_bytes());
+        }
+        hasher.finalize()[..16]
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+
+    /// The spooled file for `key`, decoding it with `decode` on first use.
+    ///
+    /// Files are written under a temporary name and renamed, so a failed
+    /// decode never leaves a truncated part behind. Each writer has its own
+    /// temporary file, so concurrent requests for one part cannot interleave.
+    pub fn get_or_write(
+        &self,
+        key: &str,
+        decode: impl FnOnce() -> Result<Vec<u8>, AppError>,
+    ) -> Result<PathBuf, AppError> {
+        let path = self.dir.join(key);
+        if path.is_file() {
+            return Ok(path);
+        }
+        let data = decode()?;
+        fs::create_dir_all(&self.dir)?;
+        static WRITERS: AtomicUsize = AtomicUsize::new(0);
+        let writer = WRITERS.fetch_add(1, Ordering::Relaxed);
+        let partial = self.dir.join(format!("{key}.{writer}.part"));
+        let mut file = fs::File::create(&partial)?;
+        file.write_all(&data)?;
+        file.sync_all()?;
+        fs::rename(&partial, &path)?;
+        Ok(path)
+    }
+
+    /// Remove every spooled file.
+    pub fn clear(&self) -> Result<(), AppError> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_once_and_clears() {
+        let dir =
+            std::env::temp_dir().join(format!("mboxviewer-spool-test-{}", std::process::id()));
+        let spool = Spool::new(&dir);
+        let key = Spool::key(Path::new("/mail/inbox.mbox"), 42, 1, &[]);
+
+        let path = spool
+            .get_or_write(&key, || Ok(b"payload".to_vec()))
+            .unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"payload");
+        let again = spool
+            .get_or_write(&key, || panic!("already spooled"))
+            .unwrap();
+        assert_eq!(again, path);
+
+        assert!(spool
+            .get_or_write("failed", || Err(AppError::MboxShell(
+                "bad part".to_string()
+            )))
+            .is_err());
+        assert!(!dir.join("failed").exists());
+
+        spool.clear().unwrap();
+        assert!(!dir.exists());
+        spool.clear().unwrap();
+    }
+
+    #[test]
+    fn keys_differ_per_part_and_mailbox() {
+        let a = Spool::key(Path::new("/a.mbox"), 0, 0, &[]);
+        assert_ne!(a, Spool::key(Path::new("/a.mbox"), 0, 1, &[]));
+        assert_ne!(a, Spool::key(Path::new("/b.mbox"), 0, 0, &[]));
+        assert_ne!(a, Spool::key(Path::new("/a.mbox"), 0, 0, &[0]));
+
+        let entry = |path: &[&str]| {
+            let path: Vec<String> = path.iter().map(|s| s.to_string()).collect();
+            Spool::archive_entry_key(&a, &path)
+        };
+        assert_ne!(entry(&["ab", "c"]), entry(&["a", "bc"]));
+        assert_ne!(entry(&["a"]), a);
+        assert_eq!(a.len(), 32);
+    }
+}
@@ -0,0 +1,178 @@
+//! Extractive summaries and keywords with TextRank.
+//!
+//! Sentences are ranked by PageRank over a graph weighted by shared terms
+//! (normalized by sentence length); keywords by PageRank over a term
+//! co-occurrence graph. Everything runs locally.
+
+use std::collections::{HashMap, HashSet};
+
+use super::tokenize;
+
+const DAMPING: f32 = 0.85;
+const ITERATIONS: usize = 30;
+
+/// Sentences with fewer terms than this are skipped (greetings, sign-offs).
+const MIN_TERMS: usize = 3;
+
+/// Terms within this distance of each other are linked in the keyword graph.
+const WINDOW: usize = 3;
+
+/// A sentence of the input, with the document it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sentence {
+    pub text: String,
+    pub doc: usize,
+    pub position: usize,
+}
+
+/// Split a document into sentences on terminal punctuation and blank lines.
+pub fn sentences(doc: usize, text: &str) -> Vec<Sentence> {
+    let mut result = Vec::new();
+    for paragraph in text.split("\n\n") {
+        let paragraph = paragraph.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mut start = 0;
+        let chars: Vec<(usize, char)> = paragraph.char_indices().collect();
+        for (n, &(i, c)) in chars.iter().enumerate() {
+            let at_boundary = chars.get(n + 1).is_none_or(|&(_, next)| next == ' ');
+            if matches!(c, '.' | '!' | '?') && at_boundary {
+                push_sentence(&mut result, doc, &paragraph[start..i + c.len_utf8()]);
+                start = i + c.len_utf8();
+            }
+        }
+        push_sentence(&mut result, doc, &paragraph[start..]);
+    }
+    result
+}
+
+fn push_sentence(out: &mut Vec<Sentence>, doc: usize, text: &str) {
+    let text = text.trim();
+    if !text.is_empty() {
+        out.push(Sentence {
+            text: text.to_string(),
+            doc,
+            position: out.len(),
+        });
+    }
+}
+
+/// The `n` highest-ranked sentences with their scores, in reading order.
+pub fn top_sentences(sentences: &[Sentence], n: usize) -> Vec<(&Sentence, f32)> {
+    let candidates: Vec<(&Sentence, HashSet<String>)> = sentences
+        .iter()
+        .map(|s| (s, tokenize::terms(&s.text).collect::<HashSet<_>>()))
+        .filter(|(_, terms)| terms.len() >= MIN_TERMS)
+        .collect();
+
+    let mut graph = vec![Vec::new(); candidates.len()];
+    for (i, (_, a)) in candidates.iter().enumerate() {
+        for (j, (_, b)) in candidates.iter().enumerate().skip(i + 1) {
+            let shared = a.intersection(b).count() as f32;
+            if shared > 0.0 {
+                let w = shared / ((a.len() as f32).ln() + (b.len() as f32).ln());
+                graph[i].push((j, w));
+                graph[j].push((i, w));
+            }
+        }
+    }
+    let scores = pagerank(&graph);
+
+    let mut ranked: Vec<usize> = (0..candidates.len()).collect();
+    ranked.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]).then(a.cmp(&b)));
+    ranked.truncate(n);
+    ranked.sort_by_key(|&i| (candidates[i].0.doc, candidates[i].0.position));
+    ranked
+        .into_iter()
+        .map(|i| (candidates[i].0, scores[i]))
+        .collect()
+}
+
+/// The `n` most central terms of the texts.
+pub fn keywords<'a>(texts: impl IntoIterator<Item = &'a str>, n: usize) -> Vec<String> {
+    let mut ids: HashMap<String, usize> = HashMap::new();
+    let mut edges: HashMap<(usize, usize), f32> = HashMap::new();
+    for text in texts {
+        let terms: Vec<usize> = tokenize::terms(text)
+            .map(|t| {
+                let next = ids.len();
+                *ids.entry(t).or_insert(next)
+            })
+            .collect();
+        for (i, &a) in terms.iter().enumerate() {
+            for &b in terms.iter().skip(i + 1).take(WINDOW - 1) {
+                if a != b {
+                    *edges.entry((a.min(b), a.max(b))).or_insert(0.0) += 1.0;
+                }
+            }
+        }
+    }
+
+    let mut graph = vec![Vec::new(); ids.len()];
+    for ((a, b), w) in edges {
+        graph[a].push((b, w));
+        graph[b].push((a, w));
+    }
+    let scores = pagerank(&graph);
+
+    let mut terms: Vec<(String, usize)> = ids.into_iter().collect();
+    terms.sort_by(|a, b| scores[b.1].total_cmp(&scores[a.1]).then(a.1.cmp(&b.1)));
+    terms.into_iter().take(n).map(|(t, _)| t).collect()
+}
+
+/// Weighted PageRank over an undirected graph given as adjacency lists.
+fn pagerank(graph: &[Vec<(usize, f32)>]) -> Vec<f32> {
+    let out: Vec<f32> = graph
+        .iter()
+        .map(|edges| edges.iter().map(|&(_, w)| w).sum())
+        .collect();
+    let mut scores = vec![1.0; graph.len()];
+    for _ in 0..ITERATIONS {
+        scores = graph
+            .iter()
+            .map(|edges| {
+                let incoming: f32 = edges.iter().map(|&(j, w)| w / out[j] * scores[j]).sum();
+                (1.0 - DAMPING) + DAMPING * incoming
+            })
+            .collect();
+    }
+    scores
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sentences_split_on_punctuation_and_paragraphs() {
+        let split = sentences(0, "Hi Bob.\n\nThe budget (v2.1) is final! Is it?\nYes");
+        let texts: Vec<&str> = split.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec!["Hi Bob.", "The budget (v2.1) is final!", "Is it?", "Yes"]
+        );
+        assert_eq!(split[3].position, 3);
+    }
+
+    #[test]
+    fn central_sentences_rank_first_and_keep_reading_order() {
+        let text = "The budget review meeting moved to Friday afternoon. \
+            Lunch options near the office are limited. \
+            Please prepare the budget figures before the review meeting. \
+            The budget review covers marketing and travel figures.";
+        let all = sentences(0, text);
+        let top = top_sentences(&all, 3);
+        let positions: Vec<usize> = top.iter().map(|(s, _)| s.position).collect();
+        assert_eq!(positions, vec![0, 2, 3]);
+    }
+
+    #[test]
+    fn keywords_favor_connected_terms() {
+        let texts = [
+            "budget review meeting on friday",
+            "the budget review needs figures",
+            "lunch is at noon",
+        ];
+        let mut top = keywords(texts, 2);
+        top.sort();
+        assert_eq!(top, vec!["budget", "review"]);
+    }
+}
@@ -0,0 +1,171 @@
+//! TF-IDF term vectors over message bodies and cosine similarity.
+
+use std::collections::HashMap;
+
+use super::tokenize;
+
+/// Terms kept per document; the long tail adds memory but little signal.
+const MAX_TERMS_PER_DOC: usize = 200;
+
+/// A sparse, L2-normalized term vector sorted by term id.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TermVector(pub Vec<(u32, f32)>);
+
+impl TermVector {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Cosine similarity (vectors are already normalized).
+    pub fn cosine(&self, other: &TermVector) -> f32 {
+        let (mut i, mut j, mut dot) = (0, 0, 0.0);
+        while i < self.0.len() && j < other.0.len() {
+            let (a, b) = (self.0[i], other.0[j]);
+            match a.0.cmp(&b.0) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    dot += a.1 * b.1;
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        dot
+    }
+}
+
+/// TF-IDF signatures of every message body.
+#[derive(Debug, Default)]
+pub struct TfIdfIndex {
+    pub vocabulary: Vec<String>,
+    pub vectors: Vec<TermVector>,
+}
+
+impl TfIdfIndex {
+    /// Build vectors for `texts` (one per message, in entry order).
+    pub fn build<I, S>(texts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut vocabulary: Vec<String> = Vec::new();
+        let mut ids: HashMap<String, u32> = HashMap::new();
+        let mut doc_freq: Vec<u32> = Vec::new();
+
+        let counts: Vec<HashMap<u32, u32>> = texts
+            .into_iter()
+            .map(|text| {
+                let mut tf: HashMap<u32, u32> = HashMap::new();
+                for term in tokenize::terms(text.as_ref()) {
+                    let id = *ids.entry(term).or_insert_with_key(|term| {
+                        vocabulary.push(term.clone());
+                        doc_freq.push(0);
+                        (vocabulary.len() - 1) as u32
+                    });
+                    *tf.entry(id).or_insert(0) += 1;
+                }
+                for id in tf.keys() {
+                    doc_freq[*id as usize] += 1;
+                }
+                tf
+            })
+            .collect();
+
+        let docs = counts.len() as f32;
+        let vectors = counts
+            .into_iter()
+            .map(|tf| {
+                let mut weights: Vec<(u32, f32)> = tf
+                    .into_iter()
+                    .map(|(id, n)| {
+                        let idf = (docs / doc_freq[id as usize] as f32).ln() + 1.0;
+                        (id, (1.0 + (n as f32).ln()) * idf)
+                    })
+                    .collect();
+                weights.sort_by(|a, b| b.1.total_cmp(&a.1));
+                weights.truncate(MAX_TERMS_PER_DOC);
+                normalize(weights)
+            })
+            .collect();
+
+        Self {
+            vocabulary,
+            vectors,
+        }
+    }
+
+    /// The `limit` documents most similar to `doc`, best first, skipping
+    /// `doc` itself, zero scores, and anything `exclude` rejects.
+    pub fn most_similar(
+        &self,
+        doc: usize,
+        limit: usize,
+        exclude: impl Fn(usize) -> bool,
+    ) -> Vec<(usize, f32)> {
+        let Some(target) = self.vectors.get(doc) else {
+            return Vec::new();
+        };
+        let mut scored: Vec<(usize, f32)> = self
+            .vectors
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != doc && !exclude(i))
+            .map(|(i, v)| (i, target.cosine(v)))
+            .filter(|&(_, score)| score > 0.0)
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.truncate(limit);
+        scored
+    }
+}
+
+/// Sort by term id and scale to unit length.
+pub fn normalize(mut weights: Vec<(u32, f32)>) -> TermVector {
+    let norm = weights.iter().map(|(_, w)| w * w).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for (_, w) in &mut weights {
+            *w /= norm;
+        }
+    }
+    weights.sort_by_key(|&(id, _)| id);
+    TermVector(weights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> TfIdfIndex {
+        TfIdfIndex::build([
+            "quarterly invoice for the acme contract",
+            "acme contract renewal and invoice terms",
+            "team lunch on friday at the pizza place",
+            "",
+        ])
+    }
+
+    #[test]
+    fn vectors_are_normalized() {
+        let idx = index();
+        let v = &idx.vectors[0];
+        assert!((v.cosine(v) - 1.0).abs() < 1e-5);
+        assert!(idx.vectors[3].is_empty());
+    }
+
+    #[test]
+    fn most_similar_ranks_shared_topics_first() {
+        let idx = index();
+        let similar = idx.most_similar(0, 5, |_| false);
+        assert_eq!(similar.len(), 1);
+        assert_eq!(similar[0].0, 1);
+        assert!(similar[0].1 > 0.0 && similar[0].1 < 1.0);
+    }
+
+    #[test]
+    fn most_similar_honours_exclusions_and_bounds() {
+        let idx = index();
+        assert!(idx.most_similar(0, 5, |i| i == 1).is_empty());
+        assert!(idx.most_similar(99, 5, |_| false).is_empty());
+    }
+}
@@ -0,0 +1,75 @@
+//! Conversation threading from Message-ID / In-Reply-To / References.
+//!
+//! Messages are grouped with a union-find over message ids, so replies whose
+//! parent is missing from the archive still join siblings that reference
+//! the same ancestor.
+
+use std::collections::HashMap;
+
+use super::headers::MessageHeaders;
+use super::union_find::DisjointSets;
+
+/// Assign a dense thread id to every message (`result[i]` is message `i`'s thread).
+pub fn thread_ids(headers: &[MessageHeaders]) -> Vec<usize> {
+    let mut sets = DisjointSets::default();
+    let mut nodes: HashMap<&str, usize> = HashMap::new();
+    let mut message_nodes = Vec::with_capacity(headers.len());
+
+    for h in headers {
+        let own = match &h.message_id {
+            Some(id) => *nodes.entry(id.as_str()).or_insert_with(|| sets.add()),
+            None => sets.add(),
+        };
+        for parent in h.in_reply_to.iter().chain(&h.references) {
+            let node = *nodes.entry(parent.as_str()).or_insert_with(|| sets.add());
+            sets.union(own, node);
+        }
+        message_nodes.push(own);
+    }
+
+    let mut dense: HashMap<usize, usize> = HashMap::new();
+    message_nodes
+        .into_iter()
+        .map(|node| {
+            let root = sets.find(node);
+            let next = dense.len();
+            *dense.entry(root).or_insert(next)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(id: Option<&str>, refs: &[&str]) -> MessageHeaders {
+        MessageHeaders {
+            message_id: id.map(str::to_string),
+            references: refs.iter().map(|r| r.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn replies_share_thread_with_parent() {
+        let ids = thread_ids(&[
+            msg(Some("a"), &[]),
+            msg(Some("b"), &["a"]),
+            msg(Some("c"), &[]),
+            msg(Some("d"), &["a", "b"]),
+        ]);
+        assert_eq!(ids, vec![0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn siblings_of_missing_parent_are_grouped() {
+        let ids = thread_ids(&[msg(Some("x"), &["gone"]), msg(Some("y"), &["gone"])]);
+        assert_eq!(ids[0], ids[1]);
+    }
+
+    #[test]
+    fn messages_without_id_are_their_own_thread() {
+        let ids = thread_ids(&[msg(None, &[]), msg(None, &[])]);
+        assert_ne!(ids[0], ids[1]);
+    }
+}
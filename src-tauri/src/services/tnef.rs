@@ -0,0 +1,407 @@
+//! Decoding of TNEF (`winmail.dat`, `application/ms-tnef`) parts.
+//!
+//! Outlook wraps the real attachments and the rich body of a message in a
+//! single TNEF stream: a sequence of attributes, some of which carry MAPI
+//! property lists. Only what the viewer shows is read: attachment names,
+//! types and data, and the message body.
+
+use super::facets::mime_essence;
+use super::rtf::{self, RtfBody};
+
+const SIGNATURE: u32 = 0x223E_9F78;
+
+const LEVEL_MESSAGE: u8 = 1;
+const LEVEL_ATTACHMENT: u8 = 2;
+
+// Attribute ids (low word of the attribute tag)
+const ATT_BODY: u16 = 0x800C;
+const ATT_ATTACH_DATA: u16 = 0x800F;
+const ATT_ATTACH_TITLE: u16 = 0x8010;
+const ATT_ATTACH_REND_DATA: u16 = 0x9002;
+const ATT_MSG_PROPS: u16 = 0x9003;
+const ATT_ATTACHMENT: u16 = 0x9005;
+const ATT_OEM_CODEPAGE: u16 = 0x9007;
+
+// MAPI property ids
+const PR_BODY: u16 = 0x1000;
+const PR_RTF_COMPRESSED: u16 = 0x1009;
+const PR_HTML: u16 = 0x1013;
+const PR_ATTACH_DATA: u16 = 0x3701;
+const PR_ATTACH_LONG_FILENAME: u16 = 0x3707;
+const PR_ATTACH_MIME_TAG: u16 = 0x370E;
+
+// MAPI property types
+const PT_STRING8: u16 = 0x001E;
+const PT_UNICODE: u16 = 0x001F;
+const PT_BINARY: u16 = 0x0102;
+const PT_OBJECT: u16 = 0x000D;
+const MV_FLAG: u16 = 0x1000;
+
+/// Fallback name for attachments without one.
+const DEFAULT_FILENAME: &str = "attachment";
+
+/// What a TNEF stream contains.
+#[derive(Debug, Default)]
+pub struct TnefMessage {
+    pub text: Option<String>,
+    pub html: Option<String>,
+    pub attachments: Vec<TnefAttachment>,
+}
+
+#[derive(Debug, Default)]
+pub struct TnefAttachment {
+    pub filename: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Whether an attachment is a TNEF stream.
+pub fn is_tnef(filename: &str, content_type: &str) -> bool {
+    matches!(
+        mime_essence(content_type).as_str(),
+        "application/ms-tnef" | "application/vnd.ms-tnef"
+    ) || filename.eq_ignore_ascii_case("winmail.dat")
+}
+
+/// Decode a TNEF stream, or `None` if it does not start like one.
+///
+/// A truncated stream yields whatever was read before the damage.
+pub fn parse(data: &[u8]) -> Option<TnefMessage> {
+    let mut reader = Reader::new(data);
+    if reader.u32()? != SIGNATURE {
+        return None;
+    }
+    reader.u16()?; // legacy key
+
+    let mut message = TnefMessage::default();
+    let mut rtf = None;
+    let mut codepage = None;
+    while let Some((level, id, value)) = reader.attribute() {
+        match (level, id) {
+            (LEVEL_ATTACHMENT, ATT_ATTACH_REND_DATA) => {
+                message.attachments.push(TnefAttachment::default())
+            }
+            (LEVEL_ATTACHMENT, _) => {
+                let Some(attachment) = message.attachments.last_mut() else {
+                    continue;
+                };
+                match id {
+                    ATT_ATTACH_TITLE if attachment.filename.is_empty() => {
+                        attachment.filename = ansi_string(value, codepage);
+                    }
+                    ATT_ATTACH_DATA => attachment.data = value.to_vec(),
+                    ATT_ATTACHMENT => {
+                        for property in properties(value) {
+                            match property.id {
+                                PR_ATTACH_LONG_FILENAME => {
+                                    attachment.filename = property_string(&property, codepage)
+                                }
+                                PR_ATTACH_MIME_TAG => {
+                                    attachment.content_type = property_string(&property, codepage)
+                                }
+                                PR_ATTACH_DATA
+                                    if attachment.data.is_empty() && property.kind == PT_BINARY =>
+                                {
+                                    attachment.data = property.value.to_vec()
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            (LEVEL_MESSAGE, ATT_OEM_CODEPAGE) => {
+                codepage = value
+                    .get(..4)
+                    .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+            }
+            (LEVEL_MESSAGE, ATT_BODY) => message.text = Some(ansi_string(value, codepage)),
+            (LEVEL_MESSAGE, ATT_MSG_PROPS) => {
+                for property in properties(value) {
+                    match property.id {
+                        PR_BODY if message.text.is_none() => {
+                            message.text = Some(property_string(&property, codepage))
+                        }
+                        PR_HTML => message.html = Some(property_string(&property, codepage)),
+                        PR_RTF_COMPRESSED => rtf = rtf::decompress(property.value),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if message.html.is_none() {
+        match rtf.as_deref().map(rtf::extract) {
+            Some(RtfBody::Html(html)) => message.html = Some(html),
+            Some(RtfBody::Text(text)) if message.text.is_none() => message.text = Some(text),
+            _ => {}
+        }
+    }
+    for attachment in &mut message.attachments {
+        if attachment.filename.trim().is_empty() {
+            attachment.filename = DEFAULT_FILENAME.to_string();
+        }
+        if attachment.content_type.is_empty() {
+            attachment.content_type = "application/octet-stream".to_string();
+        }
+    }
+    Some(message)
+}
+
+/// One value of a MAPI property (the first, for multi-valued ones).
+struct Property<'a> {
+    id: u16,
+    kind: u16,
+    value: &'a [u8],
+}
+
+/// Parse a MAPI property list, stopping at the first property that cannot
+/// be read.
+fn properties(data: &[u8]) -> Vec<Property<'_>> {
+    let mut reader = Reader::new(data);
+    let mut properties = Vec::new();
+    let Some(count) = reader.u32() else {
+        return properties;
+    };
+    for _ in 0..count {
+        match reader.property() {
+            Some(Some(property)) => properties.push(property),
+            Some(None) => {}
+            None => break,
+        }
+    }
+    properties
+}
+
+/// A string property in the type it was stored as.
+fn property_string(property: &Property, codepage: Option<u32>) -> String {
+    match property.kind {
+        PT_UNICODE => {
+            let units: Vec<u16> = property
+                .value
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+                .trim_end_matches('\0')
+                .to_string()
+        }
+        _ => ansi_string(property.value, codepage),
+    }
+}
+
+/// An 8-bit string in the message's code page (Windows-1252 by default).
+fn ansi_string(data: &[u8], codepage: Option<u32>) -> String {
+    let label = format!("windows-{}", codepage.unwrap_or(1252));
+    let encoding =
+        encoding_rs::Encoding::for_label(label.as_bytes()).unwrap_or(encoding_rs::WINDOWS_1252);
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    encoding.decode(&data[..end]).0.into_owned()
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    /// `len` bytes followed by padding to a multiple of four.
+    fn padded(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.bytes(len)?;
+        self.bytes((4 - len % 4) % 4)?;
+        Some(bytes)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.bytes(2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.bytes(4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// Level, id and value of the next attribute.
+    fn attribute(&mut self) -> Option<(u8, u16, &'a [u8])> {
+        let level = self.bytes(1)?[0];
+        let tag = self.u32()?;
+        let len = self.u32()? as usize;
+        let value = self.bytes(len)?;
+        self.u16()?; // checksum
+        Some((level, (tag & 0xFFFF) as u16, value))
+    }
+
+    /// The next property: `None` when the list cannot be read further,
+    /// `Some(None)` for a property without a usable value.
+    fn property(&mut self) -> Option<Option<Property<'a>>> {
+        let kind = self.u16()?;
+        let id = self.u16()?;
+        if id >= 0x8000 {
+            // Named property: GUID, then a numeric id or a UTF-16 name
+            self.bytes(16)?;
+            if self.u32()? == 0 {
+                self.u32()?;
+            } else {
+                let len = self.u32()? as usize;
+                self.padded(len)?;
+            }
+        }
+
+        let base = kind & !MV_FLAG;
+        let variable = matches!(base, PT_STRING8 | PT_UNICODE | PT_BINARY | PT_OBJECT);
+        let count = if kind & MV_FLAG != 0 || variable {
+            self.u32()? as usize
+        } else {
+            1
+        };
+        let mut first = None;
+        for _ in 0..count {
+            let value = if variable {
+                let len = self.u32()? as usize;
+                self.padded(len)?
+            } else {
+                self.bytes(fixed_size(base)?)?
+            };
+            first.get_or_insert(value);
+        }
+        Some(first.map(|value| Property {
+            id,
+            kind: base,
+            value,
+        }))
+    }
+}
+
+/// Size of a fixed-width property value, padded as stored in TNEF.
+fn fixed_size(kind: u16) -> Option<usize> {
+    match kind {
+        0x0001..=0x0004 | 0x000A | 0x000B => Some(4),
+        0x0005..=0x0007 | 0x0014 | 0x0040 => Some(8),
+        0x0048 => Some(16),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute(level: u8, tag: u32, value: &[u8]) -> Vec<u8> {
+        let mut out = vec![level];
+        out.extend(tag.to_le_bytes());
+        out.extend((value.len() as u32).to_le_bytes());
+        out.extend(value);
+        let checksum = value.iter().map(|&b| u32::from(b)).sum::<u32>() as u16;
+        out.extend(checksum.to_le_bytes());
+        out
+    }
+
+    fn string_property(kind: u16, id: u16, value: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend(kind.to_le_bytes());
+        out.extend(id.to_le_bytes());
+        out.extend(1u32.to_le_bytes());
+        out.extend((value.len() as u32).to_le_bytes());
+        out.extend(value);
+        out.resize(out.len() + (4 - value.len() % 4) % 4, 0);
+        out
+    }
+
+    fn property_list(properties: &[Vec<u8>]) -> Vec<u8> {
+        let mut out = (properties.len() as u32).to_le_bytes().to_vec();
+        for property in properties {
+            out.extend(property);
+        }
+        out
+    }
+
+    fn winmail() -> Vec<u8> {
+        let long_name: Vec<u8> = "Presupuesto año.xlsx\0"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let mut long_int = Vec::new();
+        long_int.extend(0x0003u16.to_le_bytes());
+        long_int.extend(0x0E21u16.to_le_bytes());
+        long_int.extend(7u32.to_le_bytes());
+
+        let mut data = SIGNATURE.to_le_bytes().to_vec();
+        data.extend(0x0001u16.to_le_bytes());
+        data.extend(attribute(
+            LEVEL_MESSAGE,
+            0x0007_9007,
+            &1252u32.to_le_bytes(),
+        ));
+        data.extend(attribute(
+            LEVEL_MESSAGE,
+            0x0006_9003,
+            &property_list(&[string_property(PT_BINARY, PR_HTML, b"<p>Hello</p>")]),
+        ));
+        data.extend(attribute(LEVEL_ATTACHMENT, 0x0006_9002, &[0; 14]));
+        data.extend(attribute(LEVEL_ATTACHMENT, 0x0001_8010, b"PRESUP~1.XLS\0"));
+        data.extend(attribute(LEVEL_ATTACHMENT, 0x0006_800F, b"sheet-bytes"));
+        data.extend(attribute(
+            LEVEL_ATTACHMENT,
+            0x0006_9005,
+            &property_list(&[
+                long_int,
+                string_property(PT_UNICODE, PR_ATTACH_LONG_FILENAME, &long_name),
+                string_property(
+                    PT_STRING8,
+                    PR_ATTACH_MIME_TAG,
+                    b"application/vnd.ms-excel\0",
+                ),
+            ]),
+        ));
+        data.extend(attribute(LEVEL_ATTACHMENT, 0x0006_9002, &[0; 14]));
+        data.extend(attribute(LEVEL_ATTACHMENT, 0x0001_8010, b"notes.txt\0"));
+        data.extend(attribute(LEVEL_ATTACHMENT, 0x0006_800F, b"caf\xe9"));
+        data
+    }
+
+    #[test]
+    fn unpacks_attachments_and_body() {
+        let message = parse(&winmail()).unwrap();
+        assert_eq!(message.html.as_deref(), Some("<p>Hello</p>"));
+        assert_eq!(message.attachments.len(), 2);
+
+        let sheet = &message.attachments[0];
+        assert_eq!(sheet.filename, "Presupuesto año.xlsx");
+        assert_eq!(sheet.content_type, "application/vnd.ms-excel");
+        assert_eq!(sheet.data, b"sheet-bytes");
+
+        let notes = &message.attachments[1];
+        assert_eq!(notes.filename, "notes.txt");
+        assert_eq!(notes.content_type, "application/octet-stream");
+        assert_eq!(notes.data, b"caf\xe9");
+    }
+
+    #[test]
+    fn keeps_what_was_read_from_a_truncated_stream() {
+        let data = winmail();
+        let message = parse(&data[..data.len() - 5]).unwrap();
+        assert_eq!(message.attachments.len(), 2);
+        assert!(message.attachments[1].data.is_empty());
+        assert!(parse(b"not tnef").is_none());
+    }
+
+    #[test]
+    fn recognizes_tnef_parts() {
+        assert!(is_tnef("winmail.dat", "application/octet-stream"));
+        assert!(is_tnef("x.dat", "application/ms-tnef; name=x.dat"));
+        assert!(!is_tnef("report.pdf", "application/pdf"));
+    }
+}
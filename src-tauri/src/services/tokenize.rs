@@ -0,0 +1,62 @@
+//! Word tokenization shared by the text-analysis features.
+
+/// Very common words in the archive's languages that carry no topical signal.
+const STOPWORDS: &[&[&str]] = &[ENGLISH, SPANISH, GERMAN, FRENCH];
+
+const ENGLISH: &[&str] = &[
+    "a", "about", "after", "all", "also", "am", "an", "and", "any", "are", "as", "at", "be",
+    "been", "but", "by", "can", "could", "did", "do", "does", "for", "from", "had", "has", "have",
+    "he", "her", "him", "his", "how", "i", "if", "in", "into", "is", "it", "its", "just", "me",
+    "more", "my", "no", "not", "of", "on", "or", "our", "out", "please", "re", "she", "so", "some",
+    "than", "that", "the", "their", "them", "then", "there", "these", "they", "this", "to", "up",
+    "us", "was", "we", "were", "what", "when", "which", "who", "will", "with", "would", "you",
+    "your",
+];
+
+const SPANISH: &[&str] = &[
+    "al", "como", "con", "de", "del", "el", "en", "es", "la", "las", "lo", "los", "mas", "muy",
+    "para", "pero", "por", "que", "se", "si", "sin", "su", "sus", "un", "una", "y",
+];
+
+const GERMAN: &[&str] = &[
+    "auf", "aus", "bei", "das", "dem", "den", "der", "die", "ein", "eine", "einen", "es", "für",
+    "ich", "ist", "mit", "nicht", "sie", "sind", "und", "von", "wir", "zu",
+];
+
+const FRENCH: &[&str] = &[
+    "au", "aux", "ce", "dans", "des", "du", "est", "et", "il", "je", "le", "les", "mais", "ne",
+    "nous", "ou", "pas", "pour", "qui", "sur", "une", "vous",
+];
+
+/// Lowercased alphabetic words of at least two characters.
+pub fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 2 && !w.chars().all(|c| c.is_numeric()))
+        .map(str::to_lowercase)
+}
+
+/// Words with stopwords removed, for topical analysis.
+pub fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    words(text).filter(|w| !is_stopword(w))
+}
+
+pub fn is_stopword(word: &str) -> bool {
+    STOPWORDS.iter().any(|list| list.contains(&word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_are_lowercased_and_skip_numbers_and_single_chars() {
+        let words: Vec<String> = words("Invoice #4711 for ACME, a Müller-GmbH").collect();
+        assert_eq!(words, vec!["invoice", "for", "acme", "müller", "gmbh"]);
+    }
+
+    #[test]
+    fn terms_drop_stopwords() {
+        let terms: Vec<String> = terms("The invoice and la factura").collect();
+        assert_eq!(terms, vec!["invoice", "factura"]);
+    }
+}
@@ -0,0 +1,72 @@
+//! Disjoint-set forest used to group messages (threads, duplicates...).
+
+#[derive(Debug, Default)]
+pub struct DisjointSets {
+    parent: Vec<usize>,
+}
+
+impl DisjointSets {
+    pub fn with_len(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+        }
+    }
+
+    /// Add a singleton set and return its node.
+    pub fn add(&mut self) -> usize {
+        self.parent.push(self.parent.len());
+        self.parent.len() - 1
+    }
+
+    pub fn find(&mut self, mut node: usize) -> usize {
+        while self.parent[node] != node {
+            self.parent[node] = self.parent[self.parent[node]];
+            node = self.parent[node];
+        }
+        node
+    }
+
+    pub fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent[b] = a;
+        }
+    }
+
+    /// Sets with more than one member, each sorted, in order of first member.
+    pub fn groups(&mut self) -> Vec<Vec<usize>> {
+        let mut by_root: std::collections::HashMap<usize, Vec<usize>> =
+            std::collections::HashMap::new();
+        for node in 0..self.parent.len() {
+            let root = self.find(node);
+            by_root.entry(root).or_default().push(node);
+        }
+        let mut groups: Vec<Vec<usize>> = by_root.into_values().filter(|g| g.len() > 1).collect();
+        groups.sort_by_key(|g| g[0]);
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn union_merges_sets_and_groups_lists_them() {
+        let mut sets = DisjointSets::with_len(5);
+        sets.union(0, 3);
+        sets.union(4, 1);
+        sets.union(3, 4);
+        assert_eq!(sets.find(1), sets.find(0));
+        assert_eq!(sets.groups(), vec![vec![0, 1, 3, 4]]);
+    }
+
+    #[test]
+    fn add_creates_singletons() {
+        let mut sets = DisjointSets::default();
+        let a = sets.add();
+        let b = sets.add();
+        assert_ne!(sets.find(a), sets.find(b));
+        assert!(sets.groups().is_empty());
+    }
+}
@@ -2,19 +2,25 @@
 
 use std::sync::Mutex;
 
+use crate::services::cancel::CancelToken;
 use crate::services::MboxService;
 
 /// Application state wrapping the MBOX service in a thread-safe mutex.
 ///
-/// Commands lock `service` to access all MBOX operations.
+/// Commands lock `service` to access all MBOX operations. Cancellable jobs
+/// keep the token of their latest run next to it.
 pub struct AppState {
     pub service: Mutex<MboxService>,
+    pub clustering: Mutex<CancelToken>,
+    pub extraction: Mutex<CancelToken>,
 }
 
 impl Default for AppState {
     fn default() -> Self {
         Self {
             service: Mutex::new(MboxService::new()),
+            clustering: Mutex::new(CancelToken::default()),
+            extraction: Mutex::new(CancelToken::default()),
         }
     }
 }
@@ -21,6 +21,8 @@ export interface EmailEntry {
   readonly subject: string;
   readonly has_attachments: boolean;
   readonly labels: readonly string[];
+  readonly language: string | null;
+  readonly rule_labels: readonly string[];
 }
 
 export interface EmailBody {
@@ -28,6 +30,33 @@ export interface EmailBody {
   readonly html: string | null;
   readonly raw_headers: string;
   readonly attachments: readonly AttachmentInfo[];
+  readonly segments: readonly BodySegment[];
+  readonly remote_content: RemoteContent;
+  readonly embedded: readonly EmbeddedMessage[];
+}
+
+export interface EmbeddedMessage {
+  readonly attachment_index: number;
+  readonly from: string;
+  readonly to: readonly string[];
+  readonly subject: string;
+  readonly date: string;
+  readonly body: EmailBody;
+}
+
+export interface RemoteContent {
+  readonly allowed: boolean;
+  readonly blocked: number;
+  readonly trackers: number;
+}
+
+export type SegmentKind = 'content' | 'quote' | 'forward' | 'signature';
+
+export interface BodySegment {
+  readonly kind: SegmentKind;
+  readonly text: string;
+  readonly depth: number;
+  readonly attribution: string | null;
 }
 
 export interface AttachmentInfo {
@@ -35,20 +64,293 @@ export interface AttachmentInfo {
   readonly content_type: string;
   readonly size: number;
   readonly part_index: number;
+  readonly url: string;
+}
+
+export type ConflictPolicy = 'rename' | 'overwrite' | 'skip';
+
+export interface SavedAttachment {
+  readonly path: string;
+  readonly size: number;
+  readonly skipped: boolean;
+}
+
+export interface ExtractionRequest {
+  readonly query: string;
+  readonly destination: string;
+  readonly template?: string;
+  readonly file_types?: readonly string[];
+  readonly conflict?: ConflictPolicy;
+}
+
+export interface ExtractionProgress {
+  readonly processed_messages: number;
+  readonly total_messages: number;
+  readonly written: number;
+  readonly duplicates: number;
+  readonly skipped: number;
+  readonly failed: number;
+}
+
+export interface ExtractionSummary {
+  readonly progress: ExtractionProgress;
+  readonly bytes_written: number;
+  readonly manifest_path: string;
+}
+
+export interface CatalogAttachment {
+  readonly email_index: number;
+  readonly attachment_index: number;
+  readonly filename: string;
+  readonly content_type: string;
+  readonly size: number;
+  readonly sha256: string | null;
+  readonly date: string;
+  readonly from_name: string;
+  readonly from_address: string;
+  readonly subject: string;
+}
+
+export type CatalogSort =
+  | 'date'
+  | 'filename'
+  | 'size'
+  | 'content_type'
+  | 'sender';
+
+export interface CatalogQuery {
+  readonly filename?: string;
+  readonly file_types?: readonly string[];
+  readonly sender?: string;
+  readonly min_size?: number;
+  readonly max_size?: number;
+  readonly sort?: CatalogSort;
+  readonly descending?: boolean;
+  readonly offset?: number;
+  readonly limit?: number;
+}
+
+export interface AttachmentCopies {
+  readonly source: CatalogAttachment;
+  readonly copies: readonly CatalogAttachment[];
+}
+
+export type ArchiveFormat = 'zip' | 'tar' | 'tar_gz' | 'seven_z';
+
+export interface ArchiveEntry {
+  readonly path: string;
+  readonly size: number;
+  readonly compressed_size: number | null;
+  readonly modified: string | null;
+  readonly is_dir: boolean;
+  readonly is_archive: boolean;
+}
+
+export interface ArchiveListing {
+  readonly format: ArchiveFormat;
+  readonly entries: readonly ArchiveEntry[];
+  readonly truncated: boolean;
 }
 
+export interface AttachmentCatalog {
+  readonly total: number;
+  readonly total_size: number;
+  readonly attachments: readonly CatalogAttachment[];
+}
+
+export type LabelKind =
+  | 'gmail'
+  | 'saved_search'
+  | 'topic'
+  | 'category'
+  | 'rule';
+
 export interface LabelCount {
   readonly label: string;
   readonly count: number;
+  readonly kind: LabelKind;
+}
+
+export interface SavedSearch {
+  readonly name: string;
+  readonly query: string;
 }
 
 export interface MboxStats {
   readonly total_messages: number;
   readonly total_with_attachments: number;
   readonly labels: readonly LabelCount[];
+  readonly hidden_duplicates: number;
+  readonly languages: readonly LanguageCount[];
+}
+
+export interface LanguageCount {
+  readonly language: string;
+  readonly count: number;
 }
 
 export interface SearchResults {
   readonly emails: readonly EmailEntry[];
   readonly total_count: number;
+  readonly matches?: readonly MessageMatches[];
+}
+
+export type SearchMode = 'query' | 'regex' | 'fuzzy';
+
+export type MatchField = 'subject' | 'from' | 'to' | 'body' | 'attachment';
+
+export interface PatternSearch {
+  readonly mode?: SearchMode;
+  readonly pattern: string;
+  readonly fields?: readonly MatchField[];
+  readonly case_sensitive?: boolean;
+  readonly max_distance?: number | null;
+  readonly exclude_quoted?: boolean;
+}
+
+export interface MatchedText {
+  readonly field: MatchField;
+  readonly start: number;
+  readonly end: number;
+  readonly text: string;
+  readonly attachment_index?: number;
+}
+
+export interface MessageMatches {
+  readonly index: number;
+  readonly matches: readonly MatchedText[];
+}
+
+export type FacetField =
+  | 'sender'
+  | 'sender_domain'
+  | 'label'
+  | 'year'
+  | 'month'
+  | 'attachment_type'
+  | 'has_attachment';
+
+export interface FacetFilter {
+  readonly field: FacetField;
+  readonly value: string;
+}
+
+export interface FacetCount {
+  readonly value: string;
+  readonly count: number;
+}
+
+export interface SearchFacets {
+  readonly senders: readonly FacetCount[];
+  readonly sender_domains: readonly FacetCount[];
+  readonly labels: readonly FacetCount[];
+  readonly years: readonly FacetCount[];
+  readonly months: readonly FacetCount[];
+  readonly attachment_types: readonly FacetCount[];
+  readonly has_attachment: readonly FacetCount[];
+}
+
+export interface FacetedSearchResults extends SearchResults {
+  readonly facets: SearchFacets;
+}
+
+export interface TermHits {
+  readonly term: string;
+  readonly messages: number;
+  readonly threads: number;
+  readonly unique_messages: number;
+}
+
+export interface HitReport {
+  readonly terms: readonly TermHits[];
+  readonly total_messages: number;
+  readonly total_threads: number;
+  readonly messages_searched: number;
+}
+
+export interface SimilarMessage {
+  readonly email: EmailEntry;
+  readonly score: number;
+}
+
+export interface SummarySentence {
+  readonly text: string;
+  readonly email_index: number;
+  readonly score: number;
+}
+
+export interface Summary {
+  readonly sentences: readonly SummarySentence[];
+  readonly keywords: readonly string[];
+  readonly email_indices: readonly number[];
+}
+
+export type DuplicateKind = 'exact' | 'near';
+
+export interface DuplicateGroup {
+  readonly kind: DuplicateKind;
+  readonly representative: number;
+  readonly duplicates: readonly number[];
+}
+
+export interface DuplicateReport {
+  readonly groups: readonly DuplicateGroup[];
+  readonly exact_duplicates: number;
+  readonly near_duplicates: number;
+}
+
+export type DuplicateCollapse = 'off' | 'exact' | 'exact_and_near';
+
+export interface DuplicateCopy {
+  readonly email: EmailEntry;
+  readonly kind: DuplicateKind;
+  readonly is_representative: boolean;
+  readonly hidden: boolean;
+}
+
+export interface TopicCluster {
+  readonly label: string;
+  readonly keywords: readonly string[];
+  readonly count: number;
+}
+
+export interface MailingList {
+  readonly list_id: string;
+  readonly name: string | null;
+  readonly count: number;
+  readonly first_date: string;
+  readonly last_date: string;
+  readonly post: string | null;
+  readonly unsubscribe: string | null;
+  readonly archive: string | null;
+}
+
+export type RuleField =
+  | 'from'
+  | 'from_domain'
+  | 'to'
+  | 'subject'
+  | 'label'
+  | 'has_attachments'
+  | 'body';
+
+export type RuleOperator =
+  | 'contains'
+  | 'equals'
+  | 'starts_with'
+  | 'ends_with'
+  | 'matches';
+
+export interface RuleCondition {
+  readonly field: RuleField;
+  readonly operator: RuleOperator;
+  readonly value: string;
+}
+
+export interface LabelRule {
+  readonly name: string;
+  readonly label: string;
+  readonly conditions: readonly RuleCondition[];
+  readonly match_any: boolean;
+  readonly enabled: boolean;
 }
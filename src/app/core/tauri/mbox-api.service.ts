@@ -1,11 +1,35 @@
 import { Injectable } from '@angular/core';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import type {
+  ArchiveListing,
+  AttachmentCatalog,
+  AttachmentCopies,
+  CatalogQuery,
+  ConflictPolicy,
+  DuplicateCollapse,
+  DuplicateCopy,
+  DuplicateReport,
   EmailBody,
   EmailEntry,
+  ExtractionProgress,
+  ExtractionRequest,
+  ExtractionSummary,
+  FacetedSearchResults,
+  FacetFilter,
+  HitReport,
   LabelCount,
+  LabelKind,
+  LabelRule,
+  MailingList,
   MboxStats,
+  PatternSearch,
+  SavedAttachment,
+  SavedSearch,
   SearchResults,
+  SimilarMessage,
+  Summary,
+  TopicCluster,
 } from '../models/mbox.models';
 
 @Injectable({
@@ -24,32 +48,229 @@ export class MboxApiService {
     return invoke<number>('get_email_count');
   }
 
-  async getEmailBody(index: number): Promise<EmailBody> {
-    return invoke<EmailBody>('get_email_body', { index });
+  async getEmailBody(index: number, loadRemote = false): Promise<EmailBody> {
+    return invoke<EmailBody>('get_email_body', { index, loadRemote });
   }
 
-  async searchEmails(query: string, limit: number): Promise<SearchResults> {
-    return invoke<SearchResults>('search_emails', { query, limit });
+  async searchEmails(
+    search: PatternSearch,
+    limit: number,
+  ): Promise<SearchResults> {
+    return invoke<SearchResults>('search_emails', { search, limit });
   }
 
-  async getEmailsByLabel(label: string): Promise<EmailEntry[]> {
-    return invoke<EmailEntry[]>('get_emails_by_label', { label });
+  async searchEmailsFaceted(
+    query: string,
+    filters: readonly FacetFilter[],
+    limit: number,
+  ): Promise<FacetedSearchResults> {
+    return invoke<FacetedSearchResults>('search_emails_faceted', {
+      query,
+      filters,
+      limit,
+    });
+  }
+
+  async getEmailsByLabel(
+    label: string,
+    kind: LabelKind = 'gmail',
+  ): Promise<EmailEntry[]> {
+    return invoke<EmailEntry[]>('get_emails_by_label', { label, kind });
   }
 
   async getAttachment(
     emailIndex: number,
     attachmentIndex: number,
+    nested: readonly number[] = [],
   ): Promise<number[]> {
     return invoke<number[]>('get_attachment', {
       emailIndex,
       attachmentIndex,
+      nested,
     });
   }
 
+  async listArchive(
+    emailIndex: number,
+    attachmentIndex: number,
+    path: readonly string[] = [],
+  ): Promise<ArchiveListing> {
+    return invoke<ArchiveListing>('list_archive', {
+      emailIndex,
+      attachmentIndex,
+      path,
+    });
+  }
+
+  async extractArchiveEntry(
+    emailIndex: number,
+    attachmentIndex: number,
+    path: readonly string[],
+    destination: string,
+    conflict?: ConflictPolicy,
+  ): Promise<SavedAttachment> {
+    return invoke<SavedAttachment>('extract_archive_entry', {
+      emailIndex,
+      attachmentIndex,
+      path,
+      destination,
+      conflict,
+    });
+  }
+
+  async saveAttachment(
+    emailIndex: number,
+    attachmentIndex: number,
+    destination: string,
+    conflict?: ConflictPolicy,
+    nested: readonly number[] = [],
+  ): Promise<SavedAttachment> {
+    return invoke<SavedAttachment>('save_attachment', {
+      emailIndex,
+      attachmentIndex,
+      nested,
+      destination,
+      conflict,
+    });
+  }
+
+  async detectLanguages(): Promise<MboxStats> {
+    return invoke<MboxStats>('detect_languages');
+  }
+
+  async getEmailsByLanguage(language: string): Promise<EmailEntry[]> {
+    return invoke<EmailEntry[]>('get_emails_by_language', { language });
+  }
+
+  async indexAttachmentText(): Promise<number> {
+    return invoke<number>('index_attachment_text');
+  }
+
+  async getHitReport(terms: readonly string[]): Promise<HitReport> {
+    return invoke<HitReport>('get_hit_report', { terms });
+  }
+
+  async exportHitReport(
+    terms: readonly string[],
+    path: string,
+  ): Promise<HitReport> {
+    return invoke<HitReport>('export_hit_report', { terms, path });
+  }
+
+  async getSimilarEmails(
+    index: number,
+    limit: number,
+  ): Promise<SimilarMessage[]> {
+    return invoke<SimilarMessage[]>('get_similar_emails', { index, limit });
+  }
+
+  async summarizeEmail(index: number, sentences?: number): Promise<Summary> {
+    return invoke<Summary>('summarize_email', { index, sentences });
+  }
+
+  async summarizeThread(index: number, sentences?: number): Promise<Summary> {
+    return invoke<Summary>('summarize_thread', { index, sentences });
+  }
+
+  async findDuplicates(): Promise<DuplicateReport> {
+    return invoke<DuplicateReport>('find_duplicates');
+  }
+
+  async clusterTopics(clusters?: number): Promise<TopicCluster[]> {
+    return invoke<TopicCluster[]>('cluster_topics', { clusters });
+  }
+
+  async cancelClustering(): Promise<void> {
+    await invoke('cancel_clustering');
+  }
+
+  async getMailingLists(): Promise<MailingList[]> {
+    return invoke<MailingList[]>('get_mailing_lists');
+  }
+
+  async getEmailsByList(listId: string): Promise<EmailEntry[]> {
+    return invoke<EmailEntry[]>('get_emails_by_list', { listId });
+  }
+
+  async categorizeEmails(): Promise<LabelCount[]> {
+    return invoke<LabelCount[]>('categorize_emails');
+  }
+
+  async setDuplicateCollapse(mode: DuplicateCollapse): Promise<MboxStats> {
+    return invoke<MboxStats>('set_duplicate_collapse', { mode });
+  }
+
+  async getDuplicateCopies(index: number): Promise<DuplicateCopy[]> {
+    return invoke<DuplicateCopy[]>('get_duplicate_copies', { index });
+  }
+
   async getLabels(): Promise<LabelCount[]> {
     return invoke<LabelCount[]>('get_labels');
   }
 
+  async getSavedSearches(): Promise<SavedSearch[]> {
+    return invoke<SavedSearch[]>('get_saved_searches');
+  }
+
+  async saveSearch(name: string, query: string): Promise<LabelCount> {
+    return invoke<LabelCount>('save_search', { name, query });
+  }
+
+  async deleteSavedSearch(name: string): Promise<void> {
+    await invoke('delete_saved_search', { name });
+  }
+
+  async getRules(): Promise<LabelRule[]> {
+    return invoke<LabelRule[]>('get_rules');
+  }
+
+  async saveRule(rule: LabelRule): Promise<LabelCount> {
+    return invoke<LabelCount>('save_rule', { rule });
+  }
+
+  async deleteRule(name: string): Promise<void> {
+    await invoke('delete_rule', { name });
+  }
+
+  async extractAttachments(
+    request: ExtractionRequest,
+    onProgress?: (progress: ExtractionProgress) => void,
+  ): Promise<ExtractionSummary> {
+    const unlisten = onProgress
+      ? await listen<ExtractionProgress>(
+          'attachment-extraction-progress',
+          (event) => onProgress(event.payload),
+        )
+      : null;
+    try {
+      return await invoke<ExtractionSummary>('extract_attachments', {
+        request,
+      });
+    } finally {
+      unlisten?.();
+    }
+  }
+
+  async cancelExtraction(): Promise<void> {
+    await invoke('cancel_extraction');
+  }
+
+  async getAttachmentCatalog(
+    query: CatalogQuery = {},
+  ): Promise<AttachmentCatalog> {
+    return invoke<AttachmentCatalog>('get_attachment_catalog', { query });
+  }
+
+  async findAttachmentCopies(
+    emailIndex: number,
+    attachmentIndex: number,
+  ): Promise<AttachmentCopies> {
+    return invoke<AttachmentCopies>('find_attachment_copies', {
+      emailIndex,
+      attachmentIndex,
+    });
+  }
+
   async closeMbox(): Promise<void> {
     await invoke('close_mbox');
   }
@@ -211,7 +211,7 @@ export class MboxStateService {
 
     try {
       if (query.trim()) {
-        const results = await this.api.searchEmails(query, 500);
+        const results = await this.api.searchEmails({ pattern: query }, 500);
 
         if (searchId === this.currentSearchId) {
           this._emails.set([...results.emails]);